@@ -0,0 +1,397 @@
+//! Reading tabular data from csv into tensors.
+
+use super::TensorDataset;
+use crate::{
+    error::LinalgError, ndarr::tensor::Tensor, number::Real,
+    shape::ShapeDescriptor,
+};
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+// ======================= Column =======================
+/// A csv column, by header name or by 0-based position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for Column {
+    fn from(value: usize) -> Self {
+        Self::Index(value)
+    }
+}
+
+impl From<&str> for Column {
+    fn from(value: &str) -> Self {
+        Self::Name(value.into())
+    }
+}
+
+impl From<String> for Column {
+    fn from(value: String) -> Self {
+        Self::Name(value)
+    }
+}
+
+// ======================= Missing =======================
+/// What to do with an empty, `NA`, `NaN` or `null` field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Missing<T> {
+    /// fail with [`LinalgError::Csv`].
+    Error,
+    /// drop the whole row.
+    Skip,
+    /// use the value instead, a missing category one-hot encodes to zeros.
+    Fill(T),
+}
+
+// ======================= CsvData =======================
+/// The parsed table, one row per record.
+pub struct CsvData<T> {
+    /// `[rows, features]`.
+    pub features: Tensor<T>,
+    /// `[rows, targets]` when a target column was set.
+    pub targets: Option<Tensor<T>>,
+    /// the name of every feature column, one-hot columns are `name=value`.
+    pub feature_names: Vec<String>,
+    pub target_names: Vec<String>,
+}
+
+impl<T: Real> CsvData<T> {
+    /// The rows as a dataset, failing without a target column.
+    pub fn into_dataset(self) -> Result<TensorDataset<T, T>, LinalgError> {
+        let Some(targets) = self.targets else {
+            return Err(LinalgError::CsvColumn {
+                reason: "no target column was selected".into(),
+            });
+        };
+        TensorDataset::try_new(self.features, targets)
+    }
+}
+
+// ======================= CsvReader =======================
+/// Parses numeric csv into tensors.
+///
+/// ```
+/// use linalg::data::csv::{CsvReader, Missing};
+///
+/// let csv = "len,colour,price\n1.5,red,3\n,blue,4\n2.0,red,5\n";
+/// let data = CsvReader::<f32>::new()
+///     .with_target("price")
+///     .with_one_hot("colour")
+///     .with_missing(Missing::Skip)
+///     .read_str(csv)
+///     .unwrap();
+/// assert_eq!(data.feature_names, ["len", "colour=red"]);
+/// assert_eq!(&*data.features, &[1.5, 1.0, 2.0, 1.0]);
+/// ```
+///
+/// Fields may be wrapped in double quotes to contain the delimiter, with
+/// `""` for a literal quote, but a record can't span several lines.
+#[derive(Debug, Clone)]
+pub struct CsvReader<T> {
+    delimiter: char,
+    header: bool,
+    columns: Option<Vec<Column>>,
+    target: Option<Column>,
+    one_hot: Vec<Column>,
+    missing: Missing<T>,
+}
+
+impl<T: Real + FromStr> Default for CsvReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a field that is either numeric or a category, after the missing check.
+enum Cell<T> {
+    Number(T),
+    Category(String),
+    Missing,
+}
+
+impl<T: Real + FromStr> CsvReader<T> {
+    /// comma separated with a header row, every column a feature.
+    pub fn new() -> Self {
+        Self {
+            delimiter: ',',
+            header: true,
+            columns: None,
+            target: None,
+            one_hot: Vec::new(),
+            missing: Missing::Error,
+        }
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether the first line names the columns. Without it columns can
+    /// only be picked by index and are named after it.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// only these columns become features, in this order.
+    pub fn with_columns<C: Into<Column>>(
+        mut self,
+        columns: impl IntoIterator<Item = C>,
+    ) -> Self {
+        self.columns = Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// splits `column` off into [`CsvData::targets`].
+    pub fn with_target(mut self, column: impl Into<Column>) -> Self {
+        self.target = Some(column.into());
+        self
+    }
+
+    /// Treats `column` as categorical, it turns into one 0/1 column per
+    /// distinct value in sorted order. Can be the target.
+    pub fn with_one_hot(mut self, column: impl Into<Column>) -> Self {
+        self.one_hot.push(column.into());
+        self
+    }
+
+    pub fn with_missing(mut self, missing: Missing<T>) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn read_path(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<CsvData<T>, LinalgError> {
+        let file = std::fs::File::open(path)?;
+        self.read(BufReader::new(file))
+    }
+
+    pub fn read_str(&self, csv: &str) -> Result<CsvData<T>, LinalgError> {
+        self.read(csv.as_bytes())
+    }
+
+    pub fn read(
+        &self,
+        reader: impl BufRead,
+    ) -> Result<CsvData<T>, LinalgError> {
+        let mut records = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if !line.trim().is_empty() {
+                records.push((i + 1, self.split(line, i + 1)?));
+            }
+        }
+        let mut records = records.into_iter();
+
+        let mut header_line = None;
+        let names: Vec<String> = if self.header {
+            let (line, header) = records.next().unzip();
+            header_line = line;
+            let header = header.unwrap_or_default().into_iter();
+            header.map(|h| h.trim().to_owned()).collect()
+        } else {
+            let width = records.as_slice().first().map_or(0, |(_, r)| r.len());
+            (0..width).map(|i| i.to_string()).collect()
+        };
+
+        // resolve which source column feeds what
+        let resolve = |column: &Column| -> Result<usize, LinalgError> {
+            let found = match column {
+                Column::Index(i) => Some(*i).filter(|&i| i < names.len()),
+                Column::Name(name) if self.header => {
+                    names.iter().position(|n| n == name)
+                }
+                Column::Name(_) => None,
+            };
+            found.ok_or_else(|| {
+                let reason = format!("no column {column:?} among {names:?}");
+                // only a header line is to blame for the missing name
+                match header_line {
+                    Some(line) => LinalgError::Csv { line, reason },
+                    None => LinalgError::CsvColumn { reason },
+                }
+            })
+        };
+        let target = self.target.as_ref().map(resolve).transpose()?;
+        let features: Vec<usize> = match &self.columns {
+            Some(columns) => {
+                columns.iter().map(resolve).collect::<Result<_, _>>()?
+            }
+            None => (0..names.len()).filter(|&i| Some(i) != target).collect(),
+        };
+        let one_hot: Vec<usize> =
+            self.one_hot.iter().map(resolve).collect::<Result<_, _>>()?;
+        let used: Vec<usize> = features.iter().copied().chain(target).collect();
+
+        // parse the used fields of every record into cells
+        let mut rows: Vec<Vec<Cell<T>>> = Vec::new();
+        'records: for (line, record) in records {
+            if record.len() != names.len() {
+                return Err(LinalgError::Csv {
+                    line,
+                    reason: format!(
+                        "expected {} fields but found {}",
+                        names.len(),
+                        record.len()
+                    ),
+                });
+            }
+            let mut row = Vec::with_capacity(used.len());
+            for &c in &used {
+                let field = record[c].trim();
+                if is_missing(field) {
+                    match self.missing {
+                        Missing::Error => {
+                            return Err(LinalgError::Csv {
+                                line,
+                                reason: format!(
+                                    "missing value in column {:?}",
+                                    names[c]
+                                ),
+                            });
+                        }
+                        Missing::Skip => continue 'records,
+                        Missing::Fill(_) => row.push(Cell::Missing),
+                    }
+                } else if one_hot.contains(&c) {
+                    row.push(Cell::Category(field.to_owned()));
+                } else {
+                    let value = field.parse().map_err(|_| LinalgError::Csv {
+                        line,
+                        reason: format!(
+                            "cannot parse {field:?} in column {:?} as a number",
+                            names[c]
+                        ),
+                    })?;
+                    row.push(Cell::Number(value));
+                }
+            }
+            rows.push(row);
+        }
+
+        // the distinct categories of every one-hot column
+        let categories: Vec<Option<Vec<&str>>> = (0..used.len())
+            .map(|k| {
+                one_hot.contains(&used[k]).then(|| {
+                    let set: BTreeSet<&str> = rows
+                        .iter()
+                        .filter_map(|row| match &row[k] {
+                            Cell::Category(c) => Some(c.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    set.into_iter().collect()
+                })
+            })
+            .collect();
+
+        let fill = match self.missing {
+            Missing::Fill(value) => value,
+            _ => T::ZERO,
+        };
+        let encode =
+            |range: std::ops::Range<usize>| -> (Tensor<T>, Vec<String>) {
+                let mut header = Vec::new();
+                for k in range.clone() {
+                    match &categories[k] {
+                        Some(values) => header.extend(
+                            values
+                                .iter()
+                                .map(|v| format!("{}={v}", names[used[k]])),
+                        ),
+                        None => header.push(names[used[k]].clone()),
+                    }
+                }
+                let mut data = Vec::with_capacity(rows.len() * header.len());
+                for row in &rows {
+                    for k in range.clone() {
+                        match (&row[k], &categories[k]) {
+                            (Cell::Number(v), _) => data.push(*v),
+                            (Cell::Missing, None) => data.push(fill),
+                            (cell, Some(values)) => {
+                                data.extend(values.iter().map(|v| match cell {
+                                    Cell::Category(c) if c == v => T::ONE,
+                                    _ => T::ZERO,
+                                }))
+                            }
+                            (Cell::Category(_), None) => unreachable!(),
+                        }
+                    }
+                }
+                let shape =
+                    ShapeDescriptor(Box::new([rows.len(), header.len()]));
+                (Tensor::new(data.into(), shape), header)
+            };
+
+        let split = features.len();
+        let (features, feature_names) = encode(0..split);
+        let (targets, target_names) = match target {
+            Some(_) => {
+                let (t, names) = encode(split..used.len());
+                (Some(t), names)
+            }
+            None => (None, Vec::new()),
+        };
+        Ok(CsvData {
+            features,
+            targets,
+            feature_names,
+            target_names,
+        })
+    }
+
+    /// splits one record, honouring double quotes.
+    fn split(
+        &self,
+        line: &str,
+        number: usize,
+    ) -> Result<Vec<String>, LinalgError> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.trim().is_empty() => {
+                    field.clear();
+                    quoted = true;
+                }
+                c if c == self.delimiter && !quoted => {
+                    fields.push(std::mem::take(&mut field));
+                }
+                c => field.push(c),
+            }
+        }
+        if quoted {
+            return Err(LinalgError::Csv {
+                line: number,
+                reason: "unterminated quoted field".into(),
+            });
+        }
+        fields.push(field);
+        Ok(fields)
+    }
+}
+
+fn is_missing(field: &str) -> bool {
+    field.is_empty()
+        || ["na", "nan", "null"]
+            .iter()
+            .any(|m| field.eq_ignore_ascii_case(m))
+}
@@ -0,0 +1,142 @@
+//! The IDX format of the MNIST files.
+//!
+//! A file starts with two zero bytes, a dtype byte and the number of
+//! dimensions, followed by every dimension as a big-endian `u32` and then
+//! the row-major big-endian payload.
+
+use crate::{
+    error::LinalgError,
+    ndarr::tensor::Tensor,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{
+    borrow::Cow,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+// ======================= Idx =======================
+/// An IDX tensor in the element type its header declares.
+pub enum Idx {
+    U8(Tensor<u8>),
+    I8(Tensor<i8>),
+    I16(Tensor<i16>),
+    I32(Tensor<i32>),
+    F32(Tensor<f32>),
+    F64(Tensor<f64>),
+}
+
+fn malformed(reason: impl Into<String>) -> LinalgError {
+    LinalgError::Format {
+        format: "idx",
+        reason: reason.into(),
+    }
+}
+
+impl Idx {
+    pub fn read_path(path: impl AsRef<Path>) -> Result<Self, LinalgError> {
+        let file = std::fs::File::open(path)?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self, LinalgError> {
+        let mut magic = [0; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| malformed("no header"))?;
+        let [0, 0, dtype, rank] = magic else {
+            return Err(malformed("the magic number must start with 0x0000"));
+        };
+
+        let mut dims = vec![0; rank as usize];
+        for d in &mut dims {
+            let mut bytes = [0; 4];
+            reader
+                .read_exact(&mut bytes)
+                .map_err(|_| malformed("truncated dimensions"))?;
+            *d = u32::from_be_bytes(bytes) as usize;
+        }
+        let shape = ShapeDescriptor(dims.into());
+        let len = shape
+            .iter()
+            .try_fold(1usize, |n, &d| n.checked_mul(d))
+            .ok_or_else(|| malformed("the dimensions overflow"))?;
+
+        let width = match dtype {
+            0x08 | 0x09 => 1,
+            0x0B => 2,
+            0x0C | 0x0D => 4,
+            0x0E => 8,
+            _ => {
+                return Err(malformed(format!("unknown dtype 0x{dtype:02x}")));
+            }
+        };
+        let bytes = len
+            .checked_mul(width)
+            .ok_or_else(|| malformed("the dimensions overflow"))?;
+        // bounded by what's actually there rather than what the header says
+        let mut payload = Vec::new();
+        reader.take(bytes as u64).read_to_end(&mut payload)?;
+        if payload.len() != bytes {
+            return Err(malformed(format!(
+                "expected {bytes} bytes of data but found {}",
+                payload.len()
+            )));
+        }
+
+        macro_rules! decode {
+            ($variant:ident, $tt:ty) => {
+                Self::$variant(Tensor::new(
+                    payload
+                        .chunks_exact(width)
+                        .map(|c| <$tt>::from_be_bytes(c.try_into().unwrap()))
+                        .collect(),
+                    shape,
+                ))
+            };
+        }
+        Ok(match dtype {
+            0x08 => Self::U8(Tensor::new(payload.into(), shape)),
+            0x09 => decode!(I8, i8),
+            0x0B => decode!(I16, i16),
+            0x0C => decode!(I32, i32),
+            0x0D => decode!(F32, f32),
+            _ => decode!(F64, f64),
+        })
+    }
+
+    pub fn shape(&self) -> Cow<'_, ShapeDescriptor> {
+        match self {
+            Self::U8(t) => t.shape(),
+            Self::I8(t) => t.shape(),
+            Self::I16(t) => t.shape(),
+            Self::I32(t) => t.shape(),
+            Self::F32(t) => t.shape(),
+            Self::F64(t) => t.shape(),
+        }
+    }
+
+    /// the bytes of a `u8` file, which is what MNIST ships.
+    pub fn into_u8(self) -> Result<Tensor<u8>, LinalgError> {
+        match self {
+            Self::U8(t) => Ok(t),
+            _ => Err(malformed("expected unsigned byte data")),
+        }
+    }
+
+    /// Converts whatever the file holds, `f64` data loses precision.
+    pub fn into_f32(self) -> Tensor<f32> {
+        fn convert<T: Copy>(t: Tensor<T>, f: impl Fn(T) -> f32) -> Tensor<f32> {
+            let shape = t.shape().into_owned();
+            Tensor::new(t.iter().map(|&x| f(x)).collect(), shape)
+        }
+        match self {
+            Self::U8(t) => convert(t, f32::from),
+            Self::I8(t) => convert(t, f32::from),
+            Self::I16(t) => convert(t, f32::from),
+            Self::I32(t) => convert(t, |x| x as f32),
+            Self::F32(t) => t,
+            Self::F64(t) => convert(t, |x| x as f32),
+        }
+    }
+}
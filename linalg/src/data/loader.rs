@@ -0,0 +1,254 @@
+use super::Dataset;
+use crate::{
+    error::LinalgError,
+    ndarr::tensor::Tensor,
+    rng::{Rng, splitmix64},
+};
+use std::{
+    sync::{
+        Arc,
+        mpsc::{Receiver, SyncSender, sync_channel},
+    },
+    thread::JoinHandle,
+};
+
+// ======================= Batch =======================
+/// `inputs` and `targets` stacked along a new leading batch axis.
+pub struct Batch<I, T> {
+    pub inputs: Tensor<I>,
+    pub targets: Tensor<T>,
+    /// the dataset index of every sample, in batch order.
+    pub indices: Box<[usize]>,
+}
+
+// ======================= DataLoader =======================
+/// Splits a [`Dataset`] into mini-batches, one epoch per [`DataLoader::iter`].
+///
+/// With a shuffle seed every epoch visits the samples in a different order,
+/// but the order of epoch `e` only depends on the seed and `e` so runs are
+/// reproducible. Prefetching builds batches on background threads while the
+/// previous ones are consumed and yields them in the same order.
+pub struct DataLoader<D> {
+    dataset: Arc<D>,
+    batch_size: usize,
+    seed: Option<u64>,
+    drop_last: bool,
+    workers: usize,
+    depth: usize,
+    epoch: u64,
+}
+
+impl<D: Dataset + 'static> DataLoader<D> {
+    /// in order, keeping the last partial batch and without prefetching.
+    pub fn new(dataset: D, batch_size: usize) -> Self {
+        Self::from_arc(Arc::new(dataset), batch_size)
+    }
+
+    /// shares `dataset` with whoever else holds it.
+    pub fn from_arc(dataset: Arc<D>, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "[[linalg]] batch size must be at least 1");
+        Self {
+            dataset,
+            batch_size,
+            seed: None,
+            drop_last: false,
+            workers: 0,
+            depth: 0,
+            epoch: 0,
+        }
+    }
+
+    /// reshuffles every epoch from `seed`.
+    pub fn with_shuffle(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// whether to skip the last batch when it's smaller than the rest.
+    pub fn with_drop_last(mut self, drop_last: bool) -> Self {
+        self.drop_last = drop_last;
+        self
+    }
+
+    /// Builds batches on `workers` threads, each running at most `depth`
+    /// batches ahead. Zero workers builds them on the consuming thread.
+    pub fn with_prefetch(mut self, workers: usize, depth: usize) -> Self {
+        self.workers = workers;
+        self.depth = depth.max(1);
+        self
+    }
+
+    /// Continues after `epoch` finished epochs, e.g. when resuming from a
+    /// checkpoint, so the shuffles pick up where they left off.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    #[inline]
+    pub fn dataset(&self) -> &D {
+        &self.dataset
+    }
+
+    #[inline]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// the number of epochs started so far.
+    #[inline]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// the number of batches per epoch.
+    pub fn len(&self) -> usize {
+        let n = self.dataset.len();
+        if self.drop_last {
+            n / self.batch_size
+        } else {
+            n.div_ceil(self.batch_size)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Starts the next epoch. Dropping the iterator early stops any
+    /// prefetch threads.
+    pub fn iter(&mut self) -> Batches<D> {
+        let mut order: Vec<usize> = (0..self.dataset.len()).collect();
+        if let Some(seed) = self.seed {
+            // hashed rather than added or xored, which would let another
+            // seed replay these orders a few epochs apart
+            let key = splitmix64(seed ^ splitmix64(self.epoch));
+            Rng::seed_from_u64(key).shuffle(&mut order);
+        }
+        self.epoch += 1;
+
+        let plan = Arc::new(Plan {
+            dataset: Arc::clone(&self.dataset),
+            order: order.into(),
+            batch_size: self.batch_size,
+            batches: self.len(),
+        });
+        let workers = self.workers.min(plan.batches);
+        let mut receivers = Vec::with_capacity(workers);
+        let mut handles = Vec::with_capacity(workers);
+        for worker in 0..workers {
+            let (tx, rx) = sync_channel(self.depth);
+            let plan = Arc::clone(&plan);
+            handles.push(std::thread::spawn(move || {
+                plan.produce(worker, workers, tx)
+            }));
+            receivers.push(rx);
+        }
+
+        Batches {
+            plan,
+            next: 0,
+            receivers,
+            handles,
+        }
+    }
+}
+
+type BatchResult<D> =
+    Result<Batch<<D as Dataset>::Input, <D as Dataset>::Target>, LinalgError>;
+
+// ======================= Plan =======================
+/// everything needed to build the batches of one epoch.
+struct Plan<D> {
+    dataset: Arc<D>,
+    order: Box<[usize]>,
+    batch_size: usize,
+    batches: usize,
+}
+
+impl<D: Dataset> Plan<D> {
+    fn build(&self, batch: usize) -> BatchResult<D> {
+        let start = batch * self.batch_size;
+        let end = (start + self.batch_size).min(self.order.len());
+        let indices: Box<[usize]> = self.order[start..end].into();
+
+        let (inputs, targets): (Vec<_>, Vec<_>) =
+            indices.iter().map(|&i| self.dataset.get(i)).unzip();
+        Ok(Batch {
+            inputs: Tensor::try_stack(&inputs)?,
+            targets: Tensor::try_stack(&targets)?,
+            indices,
+        })
+    }
+
+    /// builds every `stride`-th batch starting at `first`, until the
+    /// consumer hangs up.
+    fn produce(
+        &self,
+        first: usize,
+        stride: usize,
+        tx: SyncSender<BatchResult<D>>,
+    ) {
+        for batch in (first..self.batches).step_by(stride) {
+            if tx.send(self.build(batch)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// ======================= Batches =======================
+/// The batches of one epoch, see [`DataLoader::iter`].
+pub struct Batches<D: Dataset> {
+    plan: Arc<Plan<D>>,
+    next: usize,
+    /// worker `k` sends the batches `k, k + n, k + 2n, ..`.
+    receivers: Vec<Receiver<BatchResult<D>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl<D: Dataset> Iterator for Batches<D> {
+    type Item = BatchResult<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.plan.batches {
+            return None;
+        }
+        let batch = self.next;
+        self.next += 1;
+
+        if self.receivers.is_empty() {
+            return Some(self.plan.build(batch));
+        }
+        let rx = &self.receivers[batch % self.receivers.len()];
+        match rx.recv() {
+            Ok(result) => Some(result),
+            // the worker panicked, surface it on the consuming thread
+            Err(_) => {
+                let handle = self.handles.remove(batch % self.handles.len());
+                self.receivers.clear();
+                match handle.join() {
+                    Err(panic) => std::panic::resume_unwind(panic),
+                    Ok(()) => unreachable!("[[linalg]] prefetch worker quit"),
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.plan.batches - self.next;
+        (left, Some(left))
+    }
+}
+
+impl<D: Dataset> ExactSizeIterator for Batches<D> {}
+
+impl<D: Dataset> Drop for Batches<D> {
+    fn drop(&mut self) {
+        // hanging up unblocks workers waiting on a full channel
+        self.receivers.clear();
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Feeding training data to models.
+
+pub mod csv;
+pub mod idx;
+pub mod loader;
+
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    ndarr::tensor::{Tensor, TensorAccess},
+    number::Scalar,
+    shape::{Shape, ShapeDescriptor},
+};
+
+// ======================= Dataset =======================
+/// An indexable collection of `(input, target)` samples.
+///
+/// Every sample of a dataset is expected to have the same input shape and
+/// the same target shape so that they stack into batches. Datasets are
+/// shared with the prefetch threads of a [`loader::DataLoader`].
+pub trait Dataset: Send + Sync {
+    type Input: Scalar;
+    type Target: Scalar;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the sample at `index`, which is below [`Dataset::len`].
+    fn get(&self, index: usize) -> (Tensor<Self::Input>, Tensor<Self::Target>);
+}
+
+// ======================= TensorDataset =======================
+/// A dataset over two tensors that are indexed along their first axis.
+pub struct TensorDataset<I, T> {
+    inputs: Tensor<I>,
+    targets: Tensor<T>,
+}
+
+impl<I: Scalar, T: Scalar> TensorDataset<I, T> {
+    pub fn new(inputs: Tensor<I>, targets: Tensor<T>) -> Self {
+        unwrap_or_panic(Self::try_new(inputs, targets))
+    }
+
+    /// `inputs` and `targets` need the same length along axis 0.
+    pub fn try_new(
+        inputs: Tensor<I>,
+        targets: Tensor<T>,
+    ) -> Result<Self, LinalgError> {
+        let (ishape, tshape) = (inputs.shape(), targets.shape());
+        if ishape.is_empty() || tshape.is_empty() || ishape[0] != tshape[0] {
+            return Err(LinalgError::ShapeMismatch {
+                op: "dataset",
+                lhs: ishape.into_owned(),
+                rhs: tshape.into_owned(),
+            });
+        }
+        Ok(Self {
+            inputs: inputs.to_owned_layout(),
+            targets: targets.to_owned_layout(),
+        })
+    }
+}
+
+/// row `index` of a contiguous tensor, as its own tensor.
+fn sample<T: Clone>(tensor: &Tensor<T>, index: usize) -> Tensor<T> {
+    let shape = tensor.shape();
+    let inner = ShapeDescriptor(shape[1..].into());
+    let len = inner.hypervolume();
+    let data = &tensor.data()[index * len..(index + 1) * len];
+    Tensor::new(data.into(), inner)
+}
+
+impl<I: Scalar, T: Scalar> Dataset for TensorDataset<I, T> {
+    type Input = I;
+    type Target = T;
+
+    fn len(&self) -> usize {
+        self.inputs.shape()[0]
+    }
+
+    fn get(&self, index: usize) -> (Tensor<I>, Tensor<T>) {
+        (sample(&self.inputs, index), sample(&self.targets, index))
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{ndarr::device::DeviceError, shape::ShapeDescriptor};
+use std::fmt;
+
+// ======================= LinalgError =======================
+/// The single error type for every fallible operation in `linalg`.
+///
+/// Shape sensitive operations come in pairs, the `try_` flavour returns this
+/// error while the plain one panics with its `Display` message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinalgError {
+    /// two operands of `op` don't have compatible shapes.
+    ShapeMismatch {
+        op: &'static str,
+        lhs: ShapeDescriptor,
+        rhs: ShapeDescriptor,
+    },
+    /// a reshape was asked to change the number of elements.
+    HypervolumeMismatch {
+        src: ShapeDescriptor,
+        dst: ShapeDescriptor,
+    },
+    /// reshape dimensions that can't describe a shape, e.g. two `-1`s.
+    InvalidDimensions {
+        dims: Box<[isize]>,
+    },
+    /// a zero-copy operation was asked of data that isn't row-major.
+    NotContiguous {
+        op: &'static str,
+    },
+    /// an operation expected a different number of dimensions.
+    RankMismatch {
+        op: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// an axis argument doesn't exist in a shape of the given rank.
+    AxisOutOfBounds {
+        axis: usize,
+        rank: usize,
+    },
+    /// only axes of length 1 can be squeezed away.
+    NotSqueezable {
+        axis: usize,
+        len: usize,
+    },
+    /// a logical index falls outside of the shape.
+    IndexOutOfBounds {
+        index: Box<[usize]>,
+        shape: ShapeDescriptor,
+    },
+    /// a data buffer doesn't hold exactly as many elements as its shape.
+    DataLength {
+        expected: usize,
+        found: usize,
+    },
+    /// gradcheck wasn't given exactly one gradient per input.
+    GradientCount {
+        inputs: usize,
+        grads: usize,
+    },
+    /// an einsum specification that can't be parsed or doesn't fit its
+    /// operands.
+    InvalidSubscripts {
+        spec: String,
+        reason: String,
+    },
+    /// a square matrix was expected.
+    NotSquare {
+        op: &'static str,
+        shape: ShapeDescriptor,
+    },
+    /// the matrix has no inverse, at least not at working precision.
+    Singular {
+        op: &'static str,
+    },
+    NotSymmetric {
+        op: &'static str,
+    },
+    NotPositiveDefinite,
+    /// an iterative method ran out of sweeps before it converged.
+    NoConvergence {
+        op: &'static str,
+        sweeps: usize,
+    },
+    /// reading or writing a file failed, `io::Error` itself isn't `Clone`.
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// a malformed record at (1-based) `line` of a csv file.
+    Csv {
+        line: usize,
+        reason: String,
+    },
+    /// a csv column that was asked for but isn't there, not tied to a line
+    /// of the file.
+    CsvColumn {
+        reason: String,
+    },
+    /// a binary file that doesn't follow `format`.
+    Format {
+        format: &'static str,
+        reason: String,
+    },
+    /// a layer configured with arguments that can't work together, or
+    /// given an input it can't be applied to.
+    InvalidLayer {
+        layer: &'static str,
+        reason: String,
+    },
+    Device(DeviceError),
+}
+
+impl fmt::Display for LinalgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ShapeMismatch { op, lhs, rhs } => write!(
+                f,
+                "shape mismatch in {op}: lhs {:?} is incompatible with rhs {:?}",
+                &**lhs, &**rhs
+            ),
+            Self::HypervolumeMismatch { src, dst } => write!(
+                f,
+                "cannot reshape {:?} ({} elements) into {:?} ({} elements)",
+                &**src,
+                src.iter().product::<usize>(),
+                &**dst,
+                dst.iter().product::<usize>()
+            ),
+            Self::InvalidDimensions { dims } => write!(
+                f,
+                "invalid dimensions {:?}, expected non-negative sizes and at \
+                 most one -1",
+                &**dims
+            ),
+            Self::NotContiguous { op } => write!(
+                f,
+                "{op} requires contiguous data, reshape copies instead"
+            ),
+            Self::RankMismatch {
+                op,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{op} expects a rank {expected} tensor but got rank {found}"
+            ),
+            Self::AxisOutOfBounds { axis, rank } => write!(
+                f,
+                "axis {axis} is out of bounds for a rank {rank} tensor"
+            ),
+            Self::NotSqueezable { axis, len } => write!(
+                f,
+                "cannot squeeze axis {axis} of length {len}, only length 1 \
+                 axes can be squeezed"
+            ),
+            Self::IndexOutOfBounds { index, shape } => write!(
+                f,
+                "index {:?} is out of bounds for shape {:?}",
+                &**index, &**shape
+            ),
+            Self::DataLength { expected, found } => write!(
+                f,
+                "shape requires {expected} elements but the buffer holds {found}"
+            ),
+            Self::GradientCount { inputs, grads } => write!(
+                f,
+                "gradcheck needs one gradient per input but got {grads} for \
+                 {inputs} inputs"
+            ),
+            Self::InvalidSubscripts { spec, reason } => {
+                write!(f, "invalid einsum subscripts {spec:?}: {reason}")
+            }
+            Self::NotSquare { op, shape } => write!(
+                f,
+                "{op} requires a square matrix but got {:?}",
+                &**shape
+            ),
+            Self::Singular { op } => write!(f, "{op} of a singular matrix"),
+            Self::NotSymmetric { op } => {
+                write!(f, "{op} requires a symmetric matrix")
+            }
+            Self::NotPositiveDefinite => {
+                write!(f, "cholesky requires a positive definite matrix")
+            }
+            Self::NoConvergence { op, sweeps } => {
+                write!(f, "{op} did not converge within {sweeps} sweeps")
+            }
+            Self::Io { kind, message } => {
+                write!(f, "io error ({kind}): {message}")
+            }
+            Self::Csv { line, reason } => {
+                write!(f, "invalid csv on line {line}: {reason}")
+            }
+            Self::CsvColumn { reason } => {
+                write!(f, "invalid csv column: {reason}")
+            }
+            Self::Format { format, reason } => {
+                write!(f, "invalid {format} data: {reason}")
+            }
+            Self::InvalidLayer { layer, reason } => {
+                write!(f, "invalid {layer}: {reason}")
+            }
+            Self::Device(err) => write!(f, "device error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LinalgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Device(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DeviceError> for LinalgError {
+    fn from(value: DeviceError) -> Self {
+        Self::Device(value)
+    }
+}
+
+impl From<std::io::Error> for LinalgError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io {
+            kind: value.kind(),
+            message: value.to_string(),
+        }
+    }
+}
+
+// ======================= unwrap_or_panic =======================
+/// the panicking half of every `try_` pair.
+#[track_caller]
+pub(crate) fn unwrap_or_panic<T>(result: Result<T, LinalgError>) -> T {
+    result.unwrap_or_else(|err| panic!("[[linalg]] {err}"))
+}
@@ -0,0 +1,212 @@
+//! Checks analytic gradients against central finite differences.
+//!
+//! Every element `x` of every input is perturbed in turn and the scalar
+//! function is evaluated at `x + eps` and `x - eps`, giving the numeric
+//! derivative `(f(x + eps) - f(x - eps)) / 2 eps`. An element passes when
+//! `|analytic - numeric| <= atol + rtol * |numeric|`.
+//!
+//! ```
+//! use linalg::{gradcheck::GradCheck, ndarr::tensor::Tensor, shape::ShapeDescriptor, slice};
+//!
+//! // f(x) = Σ x², so ∇f = 2x
+//! let x = Tensor::new(slice![1.0, -2.0, 0.5], ShapeDescriptor(slice![3]));
+//! let grad = Tensor::new(slice![2.0, -4.0, 1.0], ShapeDescriptor(slice![3]));
+//! let report = GradCheck::new()
+//!     .run(|xs| xs[0].iter_logical().map(|x| x * x).sum(), &[x], &[grad])
+//!     .unwrap();
+//! report.assert_passed();
+//! ```
+
+use crate::{
+    error::LinalgError, ndarr::tensor::Tensor, number::Real, shape::Shape,
+};
+use std::fmt::{self, Display, Formatter};
+
+/// failures past this many are only counted in the report.
+const MAX_LISTED: usize = 10;
+
+// ======================= GradCheck =======================
+/// The perturbation and tolerances of a gradient check.
+#[derive(Debug, Clone, Copy)]
+pub struct GradCheck<T> {
+    eps: T,
+    rtol: T,
+    atol: T,
+}
+
+impl<T: Real> Default for GradCheck<T> {
+    /// `eps = ε^(1/3)`, which balances truncation against rounding error,
+    /// `rtol = 1e-3` and `atol = √ε`.
+    fn default() -> Self {
+        Self {
+            eps: T::EPSILON.cbrt(),
+            rtol: T::ONE / T::from_usize(1000),
+            atol: T::EPSILON.sqrt(),
+        }
+    }
+}
+
+impl<T: Real> GradCheck<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the step of the central difference.
+    pub fn with_eps(mut self, eps: T) -> Self {
+        self.eps = eps;
+        self
+    }
+
+    pub fn with_rtol(mut self, rtol: T) -> Self {
+        self.rtol = rtol;
+        self
+    }
+
+    pub fn with_atol(mut self, atol: T) -> Self {
+        self.atol = atol;
+        self
+    }
+
+    /// Compares `grads[i]`, the analytic gradient of `f` with respect to
+    /// `inputs[i]`, against finite differences. Only fails if the gradients
+    /// don't match the inputs in number and shape.
+    pub fn run<F>(
+        &self,
+        mut f: F,
+        inputs: &[Tensor<T>],
+        grads: &[Tensor<T>],
+    ) -> Result<GradReport<T>, LinalgError>
+    where
+        F: FnMut(&[Tensor<T>]) -> T,
+    {
+        if inputs.len() != grads.len() {
+            return Err(LinalgError::GradientCount {
+                inputs: inputs.len(),
+                grads: grads.len(),
+            });
+        }
+        for (input, grad) in inputs.iter().zip(grads) {
+            if input.shape() != grad.shape() {
+                return Err(LinalgError::ShapeMismatch {
+                    op: "gradcheck",
+                    lhs: input.shape().into_owned(),
+                    rhs: grad.shape().into_owned(),
+                });
+            }
+        }
+
+        let two_eps = self.eps + self.eps;
+        let mut perturbed = inputs.to_vec();
+        let mut entries = Vec::new();
+        for (input, grad) in grads.iter().enumerate() {
+            for ((index, &analytic), &x) in
+                grad.indexed_iter().zip(inputs[input].iter_logical())
+            {
+                perturbed[input][&*index] = x + self.eps;
+                let plus = f(&perturbed);
+                perturbed[input][&*index] = x - self.eps;
+                let minus = f(&perturbed);
+                perturbed[input][&*index] = x;
+
+                let numeric = (plus - minus) / two_eps;
+                entries.push(GradEntry {
+                    input,
+                    index,
+                    analytic,
+                    numeric,
+                    error: (analytic - numeric).abs(),
+                    tolerance: self.atol + self.rtol * numeric.abs(),
+                });
+            }
+        }
+        Ok(GradReport { entries })
+    }
+}
+
+// ======================= GradReport =======================
+/// The comparison of a single gradient element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradEntry<T> {
+    /// which of the inputs the element belongs to.
+    pub input: usize,
+    /// the logical index within that input.
+    pub index: Box<[usize]>,
+    pub analytic: T,
+    pub numeric: T,
+    /// `|analytic - numeric|`
+    pub error: T,
+    /// `atol + rtol * |numeric|`
+    pub tolerance: T,
+}
+
+impl<T: Real> GradEntry<T> {
+    /// NaNs never pass.
+    pub fn passed(&self) -> bool {
+        self.error <= self.tolerance
+    }
+}
+
+/// Every element of a gradient check, in input and logical order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradReport<T> {
+    pub entries: Vec<GradEntry<T>>,
+}
+
+impl<T: Real> GradReport<T> {
+    pub fn passed(&self) -> bool {
+        self.entries.iter().all(GradEntry::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &GradEntry<T>> + '_ {
+        self.entries.iter().filter(|e| !e.passed())
+    }
+
+    /// the entry with the largest error relative to its tolerance.
+    pub fn worst(&self) -> Option<&GradEntry<T>> {
+        self.entries.iter().max_by(|a, b| {
+            let (a, b) = (a.error / a.tolerance, b.error / b.tolerance);
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+    }
+
+    /// panics listing the failed elements.
+    #[track_caller]
+    pub fn assert_passed(&self)
+    where
+        T: Display,
+    {
+        if !self.passed() {
+            panic!("[[linalg]] {self}");
+        }
+    }
+}
+
+impl<T: Real + Display> Display for GradReport<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let failed = self.failures().count();
+        if failed == 0 {
+            return write!(
+                f,
+                "gradcheck passed {} elements",
+                self.entries.len()
+            );
+        }
+        write!(
+            f,
+            "gradcheck failed {failed} of {} elements",
+            self.entries.len()
+        )?;
+        for e in self.failures().take(MAX_LISTED) {
+            write!(
+                f,
+                "\n  input {} at {:?}: analytic {}, numeric {}, \
+                 error {} > tolerance {}",
+                e.input, &*e.index, e.analytic, e.numeric, e.error, e.tolerance
+            )?;
+        }
+        if failed > MAX_LISTED {
+            write!(f, "\n  ... and {} more", failed - MAX_LISTED)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+//! A compact native format for everything needed to resume training.
+//!
+//! ```text
+//! magic     b"LINALGCK"
+//! version   u16 major, u16 minor
+//! sections  u32 tag, u64 length, payload   (repeated)
+//! checksum  u32 crc32 of everything before it
+//! ```
+//!
+//! All integers are little-endian. Readers skip sections with unknown tags,
+//! so newer minor versions can add sections older readers still load, while
+//! a new major version marks an incompatible layout.
+
+use super::{crc::crc32, npy::NpyElement};
+use crate::{
+    error::LinalgError,
+    ndarr::tensor::Tensor,
+    rng::Rng,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+const MAGIC: &[u8; 8] = b"LINALGCK";
+pub const VERSION_MAJOR: u16 = 1;
+pub const VERSION_MINOR: u16 = 0;
+
+const TAG_COUNTERS: u32 = 1;
+const TAG_RNG: u32 = 2;
+const TAG_PARAMS: u32 = 3;
+const TAG_OPTIMIZER: u32 = 4;
+
+fn malformed(reason: impl Into<String>) -> LinalgError {
+    LinalgError::Format {
+        format: "checkpoint",
+        reason: reason.into(),
+    }
+}
+
+// ======================= Checkpoint =======================
+/// The full state of a training run.
+#[derive(Clone)]
+pub struct Checkpoint<T> {
+    /// the number of finished epochs.
+    pub epoch: u64,
+    /// the optimizer / scheduler step.
+    pub step: u64,
+    pub rng: Option<Rng>,
+    /// the model parameters by name.
+    pub params: BTreeMap<String, Tensor<T>>,
+    /// optimizer buffers by name, e.g. `"fc1.weight.m"` for an Adam moment.
+    pub optimizer: BTreeMap<String, Tensor<T>>,
+}
+
+impl<T> Default for Checkpoint<T> {
+    fn default() -> Self {
+        Self {
+            epoch: 0,
+            step: 0,
+            rng: None,
+            params: BTreeMap::new(),
+            optimizer: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: NpyElement> Checkpoint<T> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend(VERSION_MAJOR.to_le_bytes());
+        out.extend(VERSION_MINOR.to_le_bytes());
+
+        let mut counters = Vec::with_capacity(16);
+        counters.extend(self.epoch.to_le_bytes());
+        counters.extend(self.step.to_le_bytes());
+        section(&mut out, TAG_COUNTERS, &counters);
+        if let Some(rng) = &self.rng {
+            let state: Vec<u8> =
+                rng.state().iter().flat_map(|w| w.to_le_bytes()).collect();
+            section(&mut out, TAG_RNG, &state);
+        }
+        section(&mut out, TAG_PARAMS, &encode_tensors(&self.params));
+        section(&mut out, TAG_OPTIMIZER, &encode_tensors(&self.optimizer));
+
+        let checksum = crc32(&out);
+        out.extend(checksum.to_le_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        if bytes.len() < MAGIC.len() + 8 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(malformed("not a checkpoint"));
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 4);
+        if crc32(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(malformed("checksum mismatch, the file is corrupt"));
+        }
+
+        let mut reader = Reader(&body[MAGIC.len()..]);
+        let major = u16::from_le_bytes(reader.take()?);
+        let _minor = u16::from_le_bytes(reader.take()?);
+        if major != VERSION_MAJOR {
+            return Err(malformed(format!(
+                "version {major} can't be read, expected {VERSION_MAJOR}"
+            )));
+        }
+
+        let mut checkpoint = Self::default();
+        while !reader.0.is_empty() {
+            let tag = u32::from_le_bytes(reader.take()?);
+            let len = u64::from_le_bytes(reader.take()?);
+            let mut payload = Reader(reader.slice(len)?);
+            match tag {
+                TAG_COUNTERS => {
+                    checkpoint.epoch = u64::from_le_bytes(payload.take()?);
+                    checkpoint.step = u64::from_le_bytes(payload.take()?);
+                }
+                TAG_RNG => {
+                    let mut state = [0; 4];
+                    for word in &mut state {
+                        *word = u64::from_le_bytes(payload.take()?);
+                    }
+                    checkpoint.rng = Some(Rng::from_state(state));
+                }
+                TAG_PARAMS => checkpoint.params = decode_tensors(payload)?,
+                TAG_OPTIMIZER => {
+                    checkpoint.optimizer = decode_tensors(payload)?;
+                }
+                // written by a newer minor version
+                _ => {}
+            }
+        }
+        Ok(checkpoint)
+    }
+
+    /// Writes to a temporary file next to `path` and renames it over, so a
+    /// run killed mid-save still has its previous checkpoint.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LinalgError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&self.to_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LinalgError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+fn section(out: &mut Vec<u8>, tag: u32, payload: &[u8]) {
+    out.extend(tag.to_le_bytes());
+    out.extend((payload.len() as u64).to_le_bytes());
+    out.extend(payload);
+}
+
+/// count, then name, dtype, shape and data of every tensor.
+fn encode_tensors<T: NpyElement>(
+    tensors: &BTreeMap<String, Tensor<T>>,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend((tensors.len() as u64).to_le_bytes());
+    for (name, tensor) in tensors {
+        let descr = T::descr();
+        let shape = tensor.shape();
+        out.extend((name.len() as u64).to_le_bytes());
+        out.extend(name.bytes());
+        out.extend((descr.len() as u64).to_le_bytes());
+        out.extend(descr.bytes());
+        out.extend((shape.len() as u64).to_le_bytes());
+        for &d in shape.iter() {
+            out.extend((d as u64).to_le_bytes());
+        }
+        for &x in tensor.iter_logical() {
+            x.write_le(&mut out);
+        }
+    }
+    out
+}
+
+fn decode_tensors<T: NpyElement>(
+    mut reader: Reader<'_>,
+) -> Result<BTreeMap<String, Tensor<T>>, LinalgError> {
+    let count = u64::from_le_bytes(reader.take()?);
+    let mut tensors = BTreeMap::new();
+    for _ in 0..count {
+        let len = u64::from_le_bytes(reader.take()?);
+        let name = std::str::from_utf8(reader.slice(len)?)
+            .map_err(|_| malformed("tensor name isn't utf-8"))?
+            .to_owned();
+        let len = u64::from_le_bytes(reader.take()?);
+        let descr = reader.slice(len)?;
+        if descr != T::descr().as_bytes() {
+            return Err(malformed(format!(
+                "{name} is {} but {} was expected",
+                String::from_utf8_lossy(descr),
+                T::descr()
+            )));
+        }
+
+        let rank = u64::from_le_bytes(reader.take()?);
+        let mut dims = Vec::new();
+        for _ in 0..rank {
+            dims.push(u64::from_le_bytes(reader.take()?) as usize);
+        }
+        let shape = ShapeDescriptor(dims.into());
+        let bytes = shape
+            .iter()
+            .try_fold(T::SIZE, |n, &d| n.checked_mul(d))
+            .ok_or_else(|| malformed(format!("{name} overflows")))?;
+        let data = reader
+            .slice(bytes as u64)?
+            .chunks_exact(T::SIZE)
+            .map(|c| T::from_bytes(c, true))
+            .collect();
+        tensors.insert(name, Tensor::try_new(data, shape)?);
+    }
+    Ok(tensors)
+}
+
+/// bounds checked reads off the front of a buffer.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn slice(&mut self, len: u64) -> Result<&'a [u8], LinalgError> {
+        if len > self.0.len() as u64 {
+            return Err(malformed("truncated"));
+        }
+        let (head, rest) = self.0.split_at(len as usize);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], LinalgError> {
+        Ok(self.slice(N as u64)?.try_into().unwrap())
+    }
+}
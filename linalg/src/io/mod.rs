@@ -0,0 +1,8 @@
+//! Saving and loading tensors in file formats shared with other tooling.
+
+pub mod checkpoint;
+pub(crate) mod crc;
+pub(crate) mod json;
+pub mod npy;
+pub mod npz;
+pub mod safetensors;
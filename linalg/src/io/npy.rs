@@ -0,0 +1,388 @@
+//! NumPy's `.npy` format, a single array behind a python dict header.
+//!
+//! ```text
+//! \x93NUMPY <major> <minor> <header len>
+//! {'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }
+//! ```
+//! followed by the raw elements. Arrays are written as version 1.0 in
+//! C order and little-endian.
+
+use crate::{
+    error::LinalgError,
+    ndarr::{
+        tensor::{Tensor, TensorAccess},
+        transform::concrete_transformers::IdentityTransform,
+    },
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+// ======================= NpyElement =======================
+/// An element type with a numpy dtype.
+pub trait NpyElement: Copy {
+    /// the dtype kind, `f`, `i` or `u`.
+    const KIND: char;
+    const SIZE: usize;
+
+    /// decodes one element from exactly [`NpyElement::SIZE`] bytes.
+    fn from_bytes(bytes: &[u8], little_endian: bool) -> Self;
+
+    fn write_le(self, out: &mut Vec<u8>);
+
+    /// the dtype string this is written with, e.g. `<f4`.
+    fn descr() -> String {
+        let order = if Self::SIZE == 1 { '|' } else { '<' };
+        format!("{order}{}{}", Self::KIND, Self::SIZE)
+    }
+}
+
+macro_rules! impl_npy_element {
+    ($($tt:ty => $kind:literal),* $(,)?) => {
+        $(
+            impl NpyElement for $tt {
+                const KIND: char = $kind;
+                const SIZE: usize = size_of::<$tt>();
+
+                fn from_bytes(bytes: &[u8], little_endian: bool) -> Self {
+                    let bytes = bytes.try_into().unwrap();
+                    if little_endian {
+                        <$tt>::from_le_bytes(bytes)
+                    } else {
+                        <$tt>::from_be_bytes(bytes)
+                    }
+                }
+
+                fn write_le(self, out: &mut Vec<u8>) {
+                    out.extend(self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_npy_element![
+    f32 => 'f',
+    f64 => 'f',
+    i8 => 'i',
+    i16 => 'i',
+    i32 => 'i',
+    i64 => 'i',
+    u8 => 'u',
+    u16 => 'u',
+    u32 => 'u',
+    u64 => 'u',
+];
+
+fn malformed(reason: impl Into<String>) -> LinalgError {
+    LinalgError::Format {
+        format: "npy",
+        reason: reason.into(),
+    }
+}
+
+// ======================= Header =======================
+/// What the header dict of a `.npy` file describes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyHeader {
+    pub descr: String,
+    pub fortran_order: bool,
+    pub shape: ShapeDescriptor,
+}
+
+impl NpyHeader {
+    /// Reads the magic, version and header dict, leaving `reader` at the
+    /// first element.
+    pub fn read(reader: &mut impl Read) -> Result<Self, LinalgError> {
+        let mut preamble = [0; 8];
+        reader
+            .read_exact(&mut preamble)
+            .map_err(|_| malformed("no header"))?;
+        if &preamble[..6] != MAGIC {
+            return Err(malformed("missing the \\x93NUMPY magic"));
+        }
+        let len = match preamble[6] {
+            1 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_le_bytes(len) as usize
+            }
+            2 | 3 => {
+                let mut len = [0; 4];
+                reader.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            major => {
+                return Err(malformed(format!("unknown version {major}")));
+            }
+        };
+        let mut dict = Vec::new();
+        reader.take(len as u64).read_to_end(&mut dict)?;
+        if dict.len() != len {
+            return Err(malformed("truncated header"));
+        }
+        let dict = String::from_utf8(dict)
+            .map_err(|_| malformed("header isn't utf-8"))?;
+        Self::parse(&dict)
+    }
+
+    /// parses the python dict literal of the header.
+    fn parse(dict: &str) -> Result<Self, LinalgError> {
+        let mut descr = None;
+        let mut fortran_order = None;
+        let mut shape = None;
+
+        let mut tokens = Tokens(dict.trim());
+        tokens.expect('{')?;
+        while !tokens.eat('}') {
+            let key = tokens.string()?;
+            tokens.expect(':')?;
+            match key.as_str() {
+                "descr" => descr = Some(tokens.string()?),
+                "fortran_order" => fortran_order = Some(tokens.boolean()?),
+                "shape" => shape = Some(tokens.tuple()?),
+                _ => return Err(malformed(format!("unknown key {key:?}"))),
+            }
+            if !tokens.eat(',') {
+                tokens.expect('}')?;
+                break;
+            }
+        }
+
+        match (descr, fortran_order, shape) {
+            (Some(descr), Some(fortran_order), Some(shape)) => Ok(Self {
+                descr,
+                fortran_order,
+                shape: ShapeDescriptor(shape.into()),
+            }),
+            _ => Err(malformed("header needs descr, fortran_order and shape")),
+        }
+    }
+
+    /// the dict padded so the data starts 64-byte aligned, version 1.0.
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), LinalgError> {
+        let shape = match &self.shape[..] {
+            [d] => format!("({d},)"),
+            dims => {
+                let dims: Vec<String> =
+                    dims.iter().map(|d| d.to_string()).collect();
+                format!("({})", dims.join(", "))
+            }
+        };
+        let order = if self.fortran_order { "True" } else { "False" };
+        let mut dict = format!(
+            "{{'descr': '{}', 'fortran_order': {order}, 'shape': {shape}, }}",
+            self.descr
+        );
+        // magic, version and length take 10 bytes, the dict ends in '\n'
+        let pad = (64 - (10 + dict.len() + 1) % 64) % 64;
+        dict.extend(std::iter::repeat_n(' ', pad));
+        dict.push('\n');
+        let len = u16::try_from(dict.len())
+            .map_err(|_| malformed("too many dimensions for a 1.0 header"))?;
+
+        out.extend(MAGIC);
+        out.extend([1, 0]);
+        out.extend(len.to_le_bytes());
+        out.extend(dict.bytes());
+        Ok(())
+    }
+
+    /// Checks that `descr` holds `T`, returning whether it's little-endian.
+    fn little_endian<T: NpyElement>(&self) -> Result<bool, LinalgError> {
+        let mut chars = self.descr.chars();
+        let order = chars.next();
+        let kind = chars.next();
+        let size: Option<usize> = chars.as_str().parse().ok();
+        let little = match order {
+            Some('<') => true,
+            Some('>') => false,
+            // single bytes and native order
+            Some('|' | '=') => cfg!(target_endian = "little"),
+            _ => {
+                return Err(malformed(format!(
+                    "unknown dtype {:?}",
+                    self.descr
+                )));
+            }
+        };
+        if kind != Some(T::KIND) || size != Some(T::SIZE) {
+            return Err(malformed(format!(
+                "dtype {:?} can't be read as {}",
+                self.descr,
+                std::any::type_name::<T>()
+            )));
+        }
+        Ok(little)
+    }
+}
+
+/// just enough of a tokenizer for the header dict.
+struct Tokens<'a>(&'a str);
+
+impl Tokens<'_> {
+    fn skip_whitespace(&mut self) {
+        self.0 = self.0.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.0.strip_prefix(c) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), LinalgError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(malformed(format!("expected {c:?} in the header")))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, LinalgError> {
+        self.skip_whitespace();
+        let quote = match self.0.chars().next() {
+            Some(q @ ('\'' | '"')) => q,
+            _ => return Err(malformed("expected a string in the header")),
+        };
+        let rest = &self.0[1..];
+        let end = rest
+            .find(quote)
+            .ok_or_else(|| malformed("unterminated string in the header"))?;
+        self.0 = &rest[end + 1..];
+        Ok(rest[..end].into())
+    }
+
+    fn boolean(&mut self) -> Result<bool, LinalgError> {
+        self.skip_whitespace();
+        for (word, value) in [("True", true), ("False", false)] {
+            if let Some(rest) = self.0.strip_prefix(word) {
+                self.0 = rest;
+                return Ok(value);
+            }
+        }
+        Err(malformed("expected True or False in the header"))
+    }
+
+    fn tuple(&mut self) -> Result<Vec<usize>, LinalgError> {
+        self.expect('(')?;
+        let mut dims = Vec::new();
+        while !self.eat(')') {
+            self.skip_whitespace();
+            let end = self
+                .0
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(self.0.len());
+            // python 2 era files write longs as `3L`
+            let dim = self.0[..end]
+                .parse()
+                .map_err(|_| malformed("expected a dimension in the shape"))?;
+            self.0 = self.0[end..].strip_prefix('L').unwrap_or(&self.0[end..]);
+            dims.push(dim);
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+        Ok(dims)
+    }
+}
+
+// ======================= read / write =======================
+/// Reads a `.npy` array of `T`s.
+///
+/// Fortran ordered data keeps its buffer and is viewed through column-major
+/// strides, [`Tensor::to_owned_layout`] makes it row-major.
+pub fn read_npy<T: NpyElement>(
+    mut reader: impl Read,
+) -> Result<Tensor<T>, LinalgError> {
+    let header = NpyHeader::read(&mut reader)?;
+    let little = header.little_endian::<T>()?;
+    let len = header
+        .shape
+        .iter()
+        .try_fold(1usize, |n, &d| n.checked_mul(d))
+        .and_then(|n| n.checked_mul(T::SIZE))
+        .ok_or_else(|| malformed("the shape overflows"))?;
+
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(malformed(format!(
+            "expected {len} bytes of data but found {}",
+            bytes.len()
+        )));
+    }
+    let data = bytes
+        .chunks_exact(T::SIZE)
+        .map(|c| T::from_bytes(c, little))
+        .collect();
+
+    if !header.fortran_order {
+        return Tensor::try_new(data, header.shape);
+    }
+    // column-major, the first axis moves fastest
+    let mut strides = vec![1; header.shape.len()];
+    for axis in 1..strides.len() {
+        strides[axis] = strides[axis - 1] * header.shape[axis - 1];
+    }
+    let reversed: Box<[usize]> = header.shape.iter().rev().copied().collect();
+    let mut tensor = Tensor::try_new(data, ShapeDescriptor(reversed))?;
+    tensor.set_transform(Arc::new(IdentityTransform(
+        header.shape,
+        strides.into(),
+    )));
+    Ok(tensor)
+}
+
+pub fn read_npy_path<T: NpyElement>(
+    path: impl AsRef<Path>,
+) -> Result<Tensor<T>, LinalgError> {
+    let file = std::fs::File::open(path)?;
+    read_npy(BufReader::new(file))
+}
+
+/// the `.npy` encoding of `tensor`, in C order.
+pub(crate) fn to_npy_bytes<T: NpyElement>(
+    tensor: &Tensor<T>,
+) -> Result<Vec<u8>, LinalgError> {
+    let header = NpyHeader {
+        descr: T::descr(),
+        fortran_order: false,
+        shape: tensor.shape().into_owned(),
+    };
+    let mut out = Vec::with_capacity(128 + tensor.hypervolume() * T::SIZE);
+    header.write(&mut out)?;
+    for &x in tensor.iter_logical() {
+        x.write_le(&mut out);
+    }
+    Ok(out)
+}
+
+/// Writes `tensor` as a `.npy` array in its logical layout.
+pub fn write_npy<T: NpyElement>(
+    mut writer: impl Write,
+    tensor: &Tensor<T>,
+) -> Result<(), LinalgError> {
+    writer.write_all(&to_npy_bytes(tensor)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_npy_path<T: NpyElement>(
+    path: impl AsRef<Path>,
+    tensor: &Tensor<T>,
+) -> Result<(), LinalgError> {
+    let file = std::fs::File::create(path)?;
+    write_npy(BufWriter::new(file), tensor)
+}
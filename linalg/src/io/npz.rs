@@ -0,0 +1,276 @@
+//! NumPy's `.npz` archives, a zip of `.npy` files as written by `np.savez`.
+//!
+//! Only stored (uncompressed) entries are understood, `np.savez_compressed`
+//! archives are rejected.
+
+use super::{
+    crc::crc32,
+    npy::{NpyElement, read_npy, to_npy_bytes},
+};
+use crate::{error::LinalgError, ndarr::tensor::Tensor};
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// a 32-bit field that moved into the zip64 extra field.
+const ZIP64_MARKER: u32 = 0xffff_ffff;
+/// 1980-01-01, the earliest date zip can represent.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn malformed(reason: impl Into<String>) -> LinalgError {
+    LinalgError::Format {
+        format: "npz",
+        reason: reason.into(),
+    }
+}
+
+/// `N` bytes of `bytes` at `at`, failing instead of panicking past the end.
+fn field<const N: usize>(
+    bytes: &[u8],
+    at: usize,
+) -> Result<[u8; N], LinalgError> {
+    bytes
+        .get(at..at + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| malformed("truncated archive"))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Result<u16, LinalgError> {
+    field(bytes, at).map(u16::from_le_bytes)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32, LinalgError> {
+    field(bytes, at).map(u32::from_le_bytes)
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Result<u64, LinalgError> {
+    field(bytes, at).map(u64::from_le_bytes)
+}
+
+// ======================= Npz =======================
+/// The arrays of an `.npz` archive, decoded on [`Npz::get`] since each may
+/// hold a different dtype.
+pub struct Npz {
+    /// the name without `.npy` and the `.npy` bytes of every array.
+    arrays: Vec<(String, Vec<u8>)>,
+}
+
+impl Npz {
+    pub fn read_path(path: impl AsRef<Path>) -> Result<Self, LinalgError> {
+        let file = std::fs::File::open(path)?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self, LinalgError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        // the end record is the last 22 bytes plus an optional comment
+        let end = (0..=bytes.len().saturating_sub(22))
+            .rev()
+            .find(|&at| u32_at(bytes, at).ok() == Some(END_OF_DIRECTORY))
+            .ok_or_else(|| malformed("not a zip archive"))?;
+        let count = u16_at(bytes, end + 10)?;
+        let mut at = u32_at(bytes, end + 16)? as usize;
+
+        let mut arrays = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if u32_at(bytes, at)? != CENTRAL_HEADER {
+                return Err(malformed("corrupt central directory"));
+            }
+            let method = u16_at(bytes, at + 10)?;
+            let crc = u32_at(bytes, at + 16)?;
+            let mut size = u32_at(bytes, at + 20)? as u64;
+            let raw_size = u32_at(bytes, at + 24)?;
+            let name_len = u16_at(bytes, at + 28)? as usize;
+            let extra_len = u16_at(bytes, at + 30)? as usize;
+            let comment_len = u16_at(bytes, at + 32)? as usize;
+            let mut offset = u32_at(bytes, at + 42)? as u64;
+            let name = bytes
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| malformed("truncated archive"))?;
+            let name = String::from_utf8_lossy(name).into_owned();
+
+            // zip64 moves the saturated fields into extra field 0x0001, in
+            // the order uncompressed size, compressed size, offset
+            let extra = at + 46 + name_len;
+            let mut e = extra;
+            while e + 4 <= extra + extra_len {
+                let (id, len) = (u16_at(bytes, e)?, u16_at(bytes, e + 2)?);
+                if id == 0x0001 {
+                    // stored entries have both sizes equal, skip the first
+                    let mut next = e + 4;
+                    if raw_size == ZIP64_MARKER {
+                        next += 8;
+                    }
+                    if size == ZIP64_MARKER as u64 {
+                        size = u64_at(bytes, next)?;
+                        next += 8;
+                    }
+                    if offset == ZIP64_MARKER as u64 {
+                        offset = u64_at(bytes, next)?;
+                    }
+                }
+                e += 4 + len as usize;
+            }
+            at = extra + extra_len + comment_len;
+
+            if method != 0 {
+                return Err(malformed(format!(
+                    "{name} is compressed, only stored entries are supported"
+                )));
+            }
+            let local = offset as usize;
+            if u32_at(bytes, local)? != LOCAL_HEADER {
+                return Err(malformed(format!("{name} has no local header")));
+            }
+            let start = local
+                + 30
+                + u16_at(bytes, local + 26)? as usize
+                + u16_at(bytes, local + 28)? as usize;
+            let data = start
+                .checked_add(size as usize)
+                .and_then(|end| bytes.get(start..end))
+                .ok_or_else(|| malformed("truncated archive"))?;
+            if crc32(data) != crc {
+                return Err(malformed(format!("{name} fails its checksum")));
+            }
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_owned();
+            arrays.push((name, data.to_vec()));
+        }
+        Ok(Self { arrays })
+    }
+
+    /// the array names in archive order, without `.npy`.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.arrays.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.arrays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrays.is_empty()
+    }
+
+    /// decodes the array `name`, see [`read_npy`].
+    pub fn get<T: NpyElement>(
+        &self,
+        name: &str,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let (_, bytes) = self
+            .arrays
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| malformed(format!("no array named {name:?}")))?;
+        read_npy(&bytes[..])
+    }
+}
+
+// ======================= NpzWriter =======================
+/// Writes arrays into a stored `.npz`, call [`NpzWriter::finish`] at the
+/// end or the archive is left without its directory.
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    /// name, crc, size and local header offset of every entry so far.
+    entries: Vec<(String, u32, u32, u32)>,
+}
+
+impl NpzWriter<BufWriter<std::fs::File>> {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, LinalgError> {
+        Ok(Self::new(BufWriter::new(std::fs::File::create(path)?)))
+    }
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// adds `tensor` as `name`, which numpy loads back as `npz[name]`.
+    pub fn add<T: NpyElement>(
+        &mut self,
+        name: &str,
+        tensor: &Tensor<T>,
+    ) -> Result<&mut Self, LinalgError> {
+        let data = to_npy_bytes(tensor)?;
+        let name = format!("{name}.npy");
+        let too_large = || malformed("archives over 4GiB aren't supported");
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| malformed("name too long"))?;
+        let crc = crc32(&data);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend(LOCAL_HEADER.to_le_bytes());
+        // version 2.0, no flags, stored, 00:00 on DOS_DATE
+        for half in [20, 0, 0, 0, DOS_DATE] {
+            header.extend(u16::to_le_bytes(half));
+        }
+        for word in [crc, size, size] {
+            header.extend(word.to_le_bytes());
+        }
+        header.extend(name_len.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(name.bytes());
+
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&data)?;
+        self.offset += (header.len() + data.len()) as u64;
+        self.entries.push((name, crc, size, offset));
+        Ok(self)
+    }
+
+    /// writes the central directory, handing back the writer.
+    pub fn finish(mut self) -> Result<W, LinalgError> {
+        let start = self.offset;
+        let mut directory = Vec::new();
+        for (name, crc, size, offset) in &self.entries {
+            directory.extend(CENTRAL_HEADER.to_le_bytes());
+            for half in [20, 20, 0, 0, 0, DOS_DATE] {
+                directory.extend(u16::to_le_bytes(half));
+            }
+            for word in [*crc, *size, *size] {
+                directory.extend(word.to_le_bytes());
+            }
+            // name, extra and comment lengths, disk and internal attributes
+            for half in [name.len() as u16, 0, 0, 0, 0] {
+                directory.extend(half.to_le_bytes());
+            }
+            directory.extend(0u32.to_le_bytes());
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.bytes());
+        }
+        let too_large = || malformed("archives over 4GiB aren't supported");
+        let count = u16::try_from(self.entries.len())
+            .map_err(|_| malformed("too many arrays for one archive"))?;
+        let size = u32::try_from(directory.len()).map_err(|_| too_large())?;
+        let start = u32::try_from(start).map_err(|_| too_large())?;
+
+        directory.extend(END_OF_DIRECTORY.to_le_bytes());
+        for half in [0, 0, count, count] {
+            directory.extend(u16::to_le_bytes(half));
+        }
+        directory.extend(size.to_le_bytes());
+        directory.extend(start.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+
+        self.writer.write_all(&directory)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
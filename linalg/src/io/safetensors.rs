@@ -0,0 +1,337 @@
+//! The safetensors layout, an 8-byte little-endian header length, a JSON
+//! header and the raw little-endian data.
+//!
+//! ```text
+//! {"__metadata__": {..},
+//!  "w": {"dtype": "F32", "shape": [2, 3], "data_offsets": [0, 24]}}
+//! ```
+//!
+//! Offsets are relative to the end of the header, so a buffer holding the
+//! whole file (e.g. memory mapped) can be viewed without copying.
+
+use super::{json::Json, npy::NpyElement};
+use crate::{
+    error::LinalgError,
+    ndarr::tensor::Tensor,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+/// headers larger than this are rejected before being parsed.
+const MAX_HEADER: usize = 100 << 20;
+
+fn malformed(reason: impl Into<String>) -> LinalgError {
+    LinalgError::Format {
+        format: "safetensors",
+        reason: reason.into(),
+    }
+}
+
+// ======================= SafetensorsElement =======================
+/// An element type with a safetensors dtype.
+pub trait SafetensorsElement: NpyElement {
+    const DTYPE: &'static str;
+}
+
+macro_rules! impl_safetensors_element {
+    ($($tt:ty => $dtype:literal),* $(,)?) => {
+        $(
+            impl SafetensorsElement for $tt {
+                const DTYPE: &'static str = $dtype;
+            }
+        )*
+    };
+}
+
+impl_safetensors_element![
+    f32 => "F32",
+    f64 => "F64",
+    i8 => "I8",
+    i16 => "I16",
+    i32 => "I32",
+    i64 => "I64",
+    u8 => "U8",
+    u16 => "U16",
+    u32 => "U32",
+    u64 => "U64",
+];
+
+/// the byte width of every dtype the format knows, not just ours.
+fn dtype_size(dtype: &str) -> Option<usize> {
+    Some(match dtype {
+        "BOOL" | "U8" | "I8" | "F8_E5M2" | "F8_E4M3" => 1,
+        "U16" | "I16" | "F16" | "BF16" => 2,
+        "U32" | "I32" | "F32" => 4,
+        "U64" | "I64" | "F64" => 8,
+        _ => return None,
+    })
+}
+
+// ======================= TensorView =======================
+/// A tensor of a safetensors buffer, borrowing its bytes.
+pub struct TensorView<'a> {
+    dtype: String,
+    shape: ShapeDescriptor,
+    data: &'a [u8],
+}
+
+impl<'a> TensorView<'a> {
+    #[inline]
+    pub fn dtype(&self) -> &str {
+        &self.dtype
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &ShapeDescriptor {
+        &self.shape
+    }
+
+    /// the raw little-endian bytes.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// decodes the elements, which have to be of dtype `T::DTYPE`.
+    pub fn to_tensor<T: SafetensorsElement>(
+        &self,
+    ) -> Result<Tensor<T>, LinalgError> {
+        if self.dtype != T::DTYPE {
+            return Err(malformed(format!(
+                "dtype {} can't be read as {}",
+                self.dtype,
+                std::any::type_name::<T>()
+            )));
+        }
+        let data = self
+            .data
+            .chunks_exact(T::SIZE)
+            .map(|c| T::from_bytes(c, true))
+            .collect();
+        Tensor::try_new(data, self.shape.clone())
+    }
+}
+
+// ======================= SafeTensors =======================
+/// The header of a safetensors buffer along with views into its data.
+pub struct SafeTensors<'a> {
+    /// sorted by offset, i.e. in file order.
+    tensors: Vec<(String, TensorView<'a>)>,
+    metadata: Vec<(String, String)>,
+}
+
+impl<'a> SafeTensors<'a> {
+    /// Parses the header of a whole safetensors file, checking that the
+    /// tensors exactly tile the data without copying any of it.
+    pub fn deserialize(bytes: &'a [u8]) -> Result<Self, LinalgError> {
+        let len = bytes
+            .get(..8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| malformed("no header length"))?;
+        if len > MAX_HEADER as u64 {
+            return Err(malformed(format!(
+                "header of {len} bytes is too large"
+            )));
+        }
+        let header = bytes
+            .get(8..8 + len as usize)
+            .ok_or_else(|| malformed("truncated header"))?;
+        let data = &bytes[8 + len as usize..];
+        let header = std::str::from_utf8(header)
+            .map_err(|_| malformed("header isn't utf-8"))?;
+        let Json::Object(entries) = Json::parse(header).map_err(malformed)?
+        else {
+            return Err(malformed("header isn't a json object"));
+        };
+
+        let mut tensors = Vec::with_capacity(entries.len());
+        let mut metadata = Vec::new();
+        for (name, entry) in entries {
+            if name == "__metadata__" {
+                let Json::Object(pairs) = entry else {
+                    return Err(malformed("__metadata__ isn't an object"));
+                };
+                for (key, value) in pairs {
+                    let value = value.as_str().ok_or_else(|| {
+                        malformed(format!("metadata {key:?} isn't a string"))
+                    })?;
+                    metadata.push((key, value.to_owned()));
+                }
+                continue;
+            }
+
+            let invalid = |what: &str| malformed(format!("{name}: {what}"));
+            let dtype = entry
+                .get("dtype")
+                .and_then(Json::as_str)
+                .ok_or_else(|| invalid("missing dtype"))?;
+            let size = dtype_size(dtype)
+                .ok_or_else(|| invalid(&format!("unknown dtype {dtype}")))?;
+            let dims: Option<Vec<usize>> = entry
+                .get("shape")
+                .and_then(Json::as_array)
+                .ok_or_else(|| invalid("missing shape"))?
+                .iter()
+                .map(|d| d.as_u64().map(|d| d as usize))
+                .collect();
+            let shape = ShapeDescriptor(
+                dims.ok_or_else(|| invalid("invalid shape"))?.into(),
+            );
+            let offsets = entry
+                .get("data_offsets")
+                .and_then(Json::as_array)
+                .and_then(|o| match o {
+                    [begin, end] => Some((begin.as_u64()?, end.as_u64()?)),
+                    _ => None,
+                })
+                .ok_or_else(|| invalid("invalid data_offsets"))?;
+
+            let (begin, end) = (offsets.0 as usize, offsets.1 as usize);
+            let expected = shape
+                .iter()
+                .try_fold(size, |n, &d| n.checked_mul(d))
+                .ok_or_else(|| invalid("shape overflows"))?;
+            if begin > end || end - begin != expected {
+                return Err(invalid("data_offsets don't match the shape"));
+            }
+            let bytes = data
+                .get(begin..end)
+                .ok_or_else(|| invalid("data_offsets past the end"))?;
+            let view = TensorView {
+                dtype: dtype.to_owned(),
+                shape,
+                data: bytes,
+            };
+            tensors.push((name, (begin, view)));
+        }
+
+        // the tensors have to cover the data without gaps or overlaps
+        tensors.sort_by_key(|(_, (begin, _))| *begin);
+        let mut covered = 0;
+        for (name, (begin, view)) in &tensors {
+            if *begin != covered {
+                return Err(malformed(format!("{name} doesn't follow on")));
+            }
+            covered += view.data.len();
+        }
+        if covered != data.len() {
+            return Err(malformed("trailing bytes after the last tensor"));
+        }
+
+        Ok(Self {
+            tensors: tensors
+                .into_iter()
+                .map(|(name, (_, view))| (name, view))
+                .collect(),
+            metadata,
+        })
+    }
+
+    /// the tensor names in file order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.tensors.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tensors.is_empty()
+    }
+
+    /// the free-form `__metadata__` string pairs.
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    pub fn view(&self, name: &str) -> Result<&TensorView<'a>, LinalgError> {
+        self.tensors
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, view)| view)
+            .ok_or_else(|| malformed(format!("no tensor named {name:?}")))
+    }
+
+    pub fn tensor<T: SafetensorsElement>(
+        &self,
+        name: &str,
+    ) -> Result<Tensor<T>, LinalgError> {
+        self.view(name)?.to_tensor()
+    }
+}
+
+// ======================= load / save =======================
+/// Reads every tensor of the file at `path`, which all have to be `T`s.
+pub fn load<T: SafetensorsElement>(
+    path: impl AsRef<Path>,
+) -> Result<BTreeMap<String, Tensor<T>>, LinalgError> {
+    let bytes = std::fs::read(path)?;
+    let file = SafeTensors::deserialize(&bytes)?;
+    file.tensors
+        .iter()
+        .map(|(name, view)| Ok((name.clone(), view.to_tensor()?)))
+        .collect()
+}
+
+/// The safetensors encoding of `tensors`, written in the given order.
+pub fn serialize<T: SafetensorsElement>(
+    tensors: &[(&str, &Tensor<T>)],
+    metadata: &[(&str, &str)],
+) -> Result<Vec<u8>, LinalgError> {
+    let mut entries = Vec::with_capacity(tensors.len() + 1);
+    if !metadata.is_empty() {
+        let pairs = metadata
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), Json::String(v.to_owned())))
+            .collect();
+        entries.push(("__metadata__".to_owned(), Json::Object(pairs)));
+    }
+    let number = |n: usize| Json::Number(n.to_string());
+    let mut offset = 0;
+    for &(name, tensor) in tensors {
+        if name == "__metadata__" || entries.iter().any(|(n, _)| n == name) {
+            return Err(malformed(format!("duplicate tensor name {name:?}")));
+        }
+        let len = tensor.hypervolume() * T::SIZE;
+        let shape = tensor.shape().iter().map(|&d| number(d)).collect();
+        entries.push((
+            name.to_owned(),
+            Json::Object(vec![
+                ("dtype".into(), Json::String(T::DTYPE.into())),
+                ("shape".into(), Json::Array(shape)),
+                (
+                    "data_offsets".into(),
+                    Json::Array(vec![number(offset), number(offset + len)]),
+                ),
+            ]),
+        ));
+        offset += len;
+    }
+
+    let mut header = String::new();
+    Json::Object(entries).write(&mut header);
+    // pad with spaces so the data starts 8-byte aligned
+    header.extend(std::iter::repeat_n(' ', (8 - header.len() % 8) % 8));
+
+    let mut out = Vec::with_capacity(8 + header.len() + offset);
+    out.extend((header.len() as u64).to_le_bytes());
+    out.extend(header.bytes());
+    for &(_, tensor) in tensors {
+        for &x in tensor.iter_logical() {
+            x.write_le(&mut out);
+        }
+    }
+    Ok(out)
+}
+
+pub fn save<T: SafetensorsElement>(
+    path: impl AsRef<Path>,
+    tensors: &[(&str, &Tensor<T>)],
+    metadata: &[(&str, &str)],
+) -> Result<(), LinalgError> {
+    let bytes = serialize(tensors, metadata)?;
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(&bytes)?;
+    Ok(())
+}
@@ -1,6 +1,16 @@
+pub mod data;
+pub mod error;
+pub mod gradcheck;
+pub mod io;
 pub mod ndarr;
+pub mod nn;
 pub mod number;
+pub mod quat;
+pub mod rng;
+#[cfg(feature = "serde")]
+mod serde_impl;
 pub mod shape;
+pub mod simd;
 
 #[cfg(test)]
 mod test;
@@ -1,16 +1,18 @@
 use crate::ndarr::tensor::TensorAccess;
-use super::{tensor::Tensor, transform::compute_flat_index};
+use super::tensor::Tensor;
 use crate::{
+    error::{LinalgError, unwrap_or_panic},
     number::RealFuncs,
     shape::{Shape, ShapeDescriptor},
+    simd::{SimdElement, reduce_as},
 };
 use std::{borrow::Cow, iter::Sum, ops::*};
 
 //====================== Arr1 ======================
 #[derive(Clone)]
-pub struct Arr1<'a, T>(Tensor<'a, T>);
+pub struct Arr1<T>(Tensor<T>);
 
-impl<T> Arr1<'_, T> {
+impl<T> Arr1<T> {
     pub fn new(data: Box<[T]>) -> Self {
         let size = data.len();
         Self(Tensor::new(data, ShapeDescriptor(Box::new([size]))))
@@ -22,52 +24,105 @@ impl<T> Arr1<'_, T> {
         T: Sub<Output = T>,
         T: Mul<Output = T>,
         T: Sum<T>,
-        T: Clone + Copy,
+        T: Clone + Copy + 'static,
     {
-        assert_eq!(self.0.len(), rhs.0.len());
-        let s: T = self
-            .0
-            .iter()
-            .zip(rhs.0.iter())
-            .map(|(&r, &l)| (r - l) * (r - l))
-            .sum();
-        s.sqrt()
+        unwrap_or_panic(self.try_distance(rhs))
     }
 
-    pub fn manhattan(&self, rhs: &Self) -> T
+    pub fn try_distance(&self, rhs: &Self) -> Result<T, LinalgError>
     where
+        T: RealFuncs<T>,
+        T: Sub<Output = T>,
         T: Mul<Output = T>,
-        T: Default,
+        T: Sum<T>,
+        T: Clone + Copy + 'static,
+    {
+        self.matching_len("distance", rhs)?;
+        let (lhs, rhs) = (&*self.0, &*rhs.0);
+        let s: T = reduce_as(lhs, rhs, f32::sq_dist)
+            .or_else(|| reduce_as(lhs, rhs, f64::sq_dist))
+            .unwrap_or_else(|| {
+                lhs.iter()
+                    .zip(rhs.iter())
+                    .map(|(&r, &l)| (r - l) * (r - l))
+                    .sum()
+            });
+        Ok(s.sqrt())
+    }
+
+    pub fn manhattan(&self, rhs: &Self) -> T
+    where
         T: Sub<Output = T>,
-        T: Neg<Output = T>,
         T: PartialOrd,
         T: Sum<T>,
-        T: Clone + Copy,
+        T: Clone + Copy + 'static,
     {
-        assert_eq!(self.0.len(), rhs.0.len());
-        self.0
-            .iter()
-            .zip(rhs.0.iter())
-            .map(|(r, l)| {
-                let diff = *r - *l;
-                if diff < T::default() { -diff } else { diff }
-            })
-            .sum()
+        unwrap_or_panic(self.try_manhattan(rhs))
+    }
+
+    pub fn try_manhattan(&self, rhs: &Self) -> Result<T, LinalgError>
+    where
+        T: Sub<Output = T>,
+        T: PartialOrd,
+        T: Sum<T>,
+        T: Clone + Copy + 'static,
+    {
+        self.matching_len("manhattan", rhs)?;
+        let (lhs, rhs) = (&*self.0, &*rhs.0);
+        Ok(reduce_as(lhs, rhs, f32::l1_dist)
+            .or_else(|| reduce_as(lhs, rhs, f64::l1_dist))
+            .unwrap_or_else(|| {
+                lhs.iter()
+                    .zip(rhs.iter())
+                    // larger minus smaller, so unsigned types work too
+                    .map(|(&r, &l)| if r > l { r - l } else { l - r })
+                    .sum()
+            }))
     }
 
     pub fn inner_product(&self, rhs: &Self) -> T
     where
         T: Mul<Output = T>,
         T: Sum<T>,
-        T: Clone + Copy,
+        T: Clone + Copy + 'static,
     {
-        assert_eq!(self.0.len(), rhs.0.len());
-        self.0.iter().zip(rhs.0.iter()).map(|(r, l)| *r * *l).sum()
+        unwrap_or_panic(self.try_inner_product(rhs))
+    }
+
+    pub fn try_inner_product(&self, rhs: &Self) -> Result<T, LinalgError>
+    where
+        T: Mul<Output = T>,
+        T: Sum<T>,
+        T: Clone + Copy + 'static,
+    {
+        self.matching_len("inner_product", rhs)?;
+        let (lhs, rhs) = (&*self.0, &*rhs.0);
+        Ok(reduce_as(lhs, rhs, f32::dot)
+            .or_else(|| reduce_as(lhs, rhs, f64::dot))
+            .unwrap_or_else(|| {
+                lhs.iter().zip(rhs.iter()).map(|(&r, &l)| r * l).sum()
+            }))
+    }
+
+    fn matching_len(
+        &self,
+        op: &'static str,
+        rhs: &Self,
+    ) -> Result<(), LinalgError> {
+        if self.0.len() == rhs.0.len() {
+            Ok(())
+        } else {
+            Err(LinalgError::ShapeMismatch {
+                op,
+                lhs: self.shape().into_owned(),
+                rhs: rhs.shape().into_owned(),
+            })
+        }
     }
 }
 
 //====================== Arr1 Shape ======================
-impl<T> Shape for Arr1<'_, T> {
+impl<T> Shape for Arr1<T> {
     #[inline(always)]
     fn shape(&self) -> Cow<ShapeDescriptor> {
         self.0.shape()
@@ -84,8 +139,8 @@ impl<T> Shape for Arr1<'_, T> {
 }
 
 //====================== Arr1 Deref ======================
-impl<'a, T> Deref for Arr1<'a, T> {
-    type Target = Tensor<'a, T>;
+impl<T> Deref for Arr1<T> {
+    type Target = Tensor<T>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -93,14 +148,14 @@ impl<'a, T> Deref for Arr1<'a, T> {
 }
 
 //====================== Arr1 DerefMut ======================
-impl<T> DerefMut for Arr1<'_, T> {
+impl<T> DerefMut for Arr1<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
 
 //====================== Arr1 From<Vec<T>> ======================
-impl<T> From<Vec<T>> for Arr1<'_, T> {
+impl<T> From<Vec<T>> for Arr1<T> {
     #[inline]
     fn from(value: Vec<T>) -> Self {
         Self::new(value.into())
@@ -108,7 +163,7 @@ impl<T> From<Vec<T>> for Arr1<'_, T> {
 }
 
 //====================== Arr1 From<Box<[T]> ======================
-impl<T> From<Box<[T]>> for Arr1<'_, T> {
+impl<T> From<Box<[T]>> for Arr1<T> {
     #[inline]
     fn from(value: Box<[T]>) -> Self {
         Self::new(value)
@@ -116,21 +171,17 @@ impl<T> From<Box<[T]>> for Arr1<'_, T> {
 }
 
 //====================== Arr1 Index ======================
-impl<T> Index<[usize; 1]> for Arr1<'_, T> {
+impl<T> Index<[usize; 1]> for Arr1<T> {
     type Output = T;
 
     fn index(&self, logical: [usize; 1]) -> &Self::Output {
-        let strides = self.strides();
-        let flat = compute_flat_index(&logical, strides);
-        &self.0[flat]
+        &self.0[&logical[..]]
     }
 }
 
 //====================== Arr1 IndexMut ======================
-impl<T> IndexMut<[usize; 1]> for Arr1<'_, T> {
+impl<T> IndexMut<[usize; 1]> for Arr1<T> {
     fn index_mut(&mut self, logical: [usize; 1]) -> &mut Self::Output {
-        let strides = self.strides();
-        let flat = compute_flat_index(&logical, strides);
-        &mut self.0[flat]
+        &mut self.0[&logical[..]]
     }
 }
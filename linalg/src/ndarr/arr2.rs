@@ -1,34 +1,67 @@
-use crate::ndarr::tensor::TensorAccess;
 use crate::{
-    ndarr::transform::{compute_flat_index, default_slice},
+    error::{LinalgError, unwrap_or_panic},
+    ndarr::transform::slice_from_fn_uninit,
     number::RealFuncs,
     shape::{Shape, ShapeDescriptor},
+    simd::{SimdElement, zip_as},
 };
 use std::{borrow::Cow, iter::Sum, ops::*};
 
-use super::tensor::Tensor;
+use super::{
+    tensor::Tensor,
+    view::{TensorView, TensorViewMut},
+};
 
 //====================== Arr2 ======================
-pub struct Arr2<'a, T>(Tensor<'a, T>);
+pub struct Arr2<T>(Tensor<T>);
 
-impl<T> Arr2<'_, T> {
+impl<T> Arr2<T> {
     pub fn new(data: Box<[T]>, shape: (usize, usize)) -> Self {
-        Self(Tensor::new(
+        unwrap_or_panic(Self::try_new(data, shape))
+    }
+
+    pub fn try_new(
+        data: Box<[T]>,
+        shape: (usize, usize),
+    ) -> Result<Self, LinalgError> {
+        Ok(Self(Tensor::try_new(
             data,
             ShapeDescriptor(Box::new([shape.0, shape.1])),
-        ))
+        )?))
     }
 
     #[inline]
-    pub fn rows(&self) -> usize {
+    pub fn nrows(&self) -> usize {
         self.shape()[0]
     }
 
     #[inline]
-    pub fn cols(&self) -> usize {
+    pub fn ncols(&self) -> usize {
         self.shape()[1]
     }
 
+    /// iterates the rows as rank 1 views, in logical order.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = TensorView<'_, T>> {
+        self.0.axis_iter(0)
+    }
+
+    /// iterates the columns as rank 1 views, in logical order.
+    pub fn cols(&self) -> impl ExactSizeIterator<Item = TensorView<'_, T>> {
+        self.0.axis_iter(1)
+    }
+
+    pub fn rows_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = TensorViewMut<'_, T>> {
+        self.0.axis_iter_mut(0)
+    }
+
+    pub fn cols_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = TensorViewMut<'_, T>> {
+        self.0.axis_iter_mut(1)
+    }
+
     pub fn matmul(self, rhs: Self) -> Self
     where
         T: Clone + Copy,
@@ -36,63 +69,66 @@ impl<T> Arr2<'_, T> {
         T: Default,
         T: Sum<T>,
     {
-        let (m, n) = (self.rows(), self.cols());
-        let (n_rhs, p) = (rhs.rows(), rhs.cols());
+        unwrap_or_panic(self.try_matmul(&rhs))
+    }
+
+    pub fn try_matmul(&self, rhs: &Self) -> Result<Self, LinalgError>
+    where
+        T: Clone + Copy,
+        T: Mul<Output = T>,
+        T: Sum<T>,
+    {
+        let (m, n) = (self.nrows(), self.ncols());
+        let (n_rhs, p) = (rhs.nrows(), rhs.ncols());
 
         // checking for at least 1 matching dim.
-        assert_eq!(
-            n, n_rhs,
-            "[[linalg]] Matrix multiplication dimensions mismatch"
-        );
-
-        let mut buff: Box<[T]> = default_slice(m * p);
-        let strides = self.strides();
-
-        for i in 0..m {
-            for j in 0..p {
-                let logical = [i, j];
-                let flat = compute_flat_index(&logical, strides);
-
-                (0..n).for_each(|k| {
-                    buff[flat] = self[[i, k]] * rhs[[k, j]];
-                });
-            }
+        if n != n_rhs {
+            return Err(LinalgError::ShapeMismatch {
+                op: "matmul",
+                lhs: self.shape().into_owned(),
+                rhs: rhs.shape().into_owned(),
+            });
         }
 
-        Arr2::new(buff, (m, p))
+        let buff = slice_from_fn_uninit(m * p, |flat| {
+            let (i, j) = (flat / p, flat % p);
+            (0..n).map(|k| self[[i, k]] * rhs[[k, j]]).sum()
+        });
+
+        Arr2::try_new(buff, (m, p))
     }
 
     pub fn matadd(self, rhs: Self) -> Self
     where
-        T: Clone + Copy,
+        T: Clone + Copy + 'static,
         T: Add<Output = T>,
         T: Default,
     {
-        let shape = self.shape();
-        assert_eq!(
-            shape,
-            rhs.shape(),
-            "[[linalg]] Matrix Addition dimensions mismatch"
-        );
-
-        let (m, n) = (shape[0], shape[1]);
-
-        let mut buff: Box<[T]> = default_slice(m * n);
-        let strides = self.strides();
-        for i in 0..m {
-            for j in 0..n {
-                let logical = [i, j];
-                let flat = compute_flat_index(&logical, strides);
-                buff[flat] = self[[i, j]] + rhs[[i, j]];
+        unwrap_or_panic(self.try_matadd(&rhs))
+    }
+
+    pub fn try_matadd(&self, rhs: &Self) -> Result<Self, LinalgError>
+    where
+        T: Clone + Copy + 'static,
+        T: Add<Output = T>,
+    {
+        let same_shape = self.shape() == rhs.shape();
+        if same_shape && self.is_contiguous() && rhs.is_contiguous() {
+            let (lhs, rhs) = (&*self.0, &*rhs.0);
+            let mut data = lhs.to_vec();
+            if zip_as(lhs, rhs, &mut data, f32::add_slices)
+                || zip_as(lhs, rhs, &mut data, f64::add_slices)
+            {
+                let shape = self.shape().into_owned();
+                return Ok(Self(Tensor::try_new(data.into(), shape)?));
             }
         }
-
-        Arr2::new(buff, (m, n))
+        Ok(Self(self.0.try_add(&rhs.0)?))
     }
 }
 
 ///====================== Arr2 Shape ======================
-impl<T> Shape for Arr2<'_, T> {
+impl<T> Shape for Arr2<T> {
     fn shape(&self) -> Cow<ShapeDescriptor> {
         self.0.shape()
     }
@@ -107,8 +143,8 @@ impl<T> Shape for Arr2<'_, T> {
 }
 
 ///====================== Arr2 Deref ======================
-impl<'a, T> Deref for Arr2<'a, T> {
-    type Target = Tensor<'a, T>;
+impl<T> Deref for Arr2<T> {
+    type Target = Tensor<T>;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
@@ -117,7 +153,7 @@ impl<'a, T> Deref for Arr2<'a, T> {
 }
 
 ///====================== Arr2 DerefMut ======================
-impl<T> DerefMut for Arr2<'_, T> {
+impl<T> DerefMut for Arr2<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
@@ -125,7 +161,7 @@ impl<T> DerefMut for Arr2<'_, T> {
 }
 
 ///====================== Arr2 From<Vec<Vec<T>>>======================
-impl<T> From<Vec<Vec<T>>> for Arr2<'_, T> {
+impl<T> From<Vec<Vec<T>>> for Arr2<T> {
     #[inline]
     fn from(value: Vec<Vec<T>>) -> Self {
         todo!()
@@ -133,7 +169,7 @@ impl<T> From<Vec<Vec<T>>> for Arr2<'_, T> {
 }
 
 ///====================== Arr2 Mul ======================
-impl<'a, T> Mul for Arr2<'a, T>
+impl<T> Mul for Arr2<T>
 where
     T: Mul<Output = T>,
     T: Sum<T>,
@@ -141,42 +177,38 @@ where
     T: RealFuncs<T>,
     T: Clone + Copy,
 {
-    type Output = Arr2<'a, T>;
+    type Output = Arr2<T>;
     fn mul(self, rhs: Self) -> Self::Output {
         self.matmul(rhs)
     }
 }
 
 ///====================== Arr2 Add ======================
-impl<'a, T> Add for Arr2<'a, T>
+impl<T> Add for Arr2<T>
 where
     T: Add<Output = T>,
     T: RealFuncs<T>,
-    T: Clone + Copy,
+    T: Clone + Copy + 'static,
     T: Default,
 {
-    type Output = Arr2<'a, T>;
+    type Output = Arr2<T>;
     fn add(self, rhs: Self) -> Self::Output {
         self.matadd(rhs)
     }
 }
 
 ///====================== Arr2 Index ======================
-impl<T> Index<[usize; 2]> for Arr2<'_, T> {
+impl<T> Index<[usize; 2]> for Arr2<T> {
     type Output = T;
 
     fn index(&self, logical: [usize; 2]) -> &Self::Output {
-        let strides = self.strides();
-        let flat = compute_flat_index(&logical, strides);
-        &self.0[flat]
+        &self.0[&logical[..]]
     }
 }
 
 ///====================== Arr2 IndexMut ======================
-impl<T> IndexMut<[usize; 2]> for Arr2<'_, T> {
+impl<T> IndexMut<[usize; 2]> for Arr2<T> {
     fn index_mut(&mut self, logical: [usize; 2]) -> &mut Self::Output {
-        let strides = self.strides();
-        let flat = compute_flat_index(&logical, strides);
-        &mut self.0[flat]
+        &mut self.0[&logical[..]]
     }
 }
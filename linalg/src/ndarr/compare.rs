@@ -0,0 +1,181 @@
+//! Exact and approximate comparisons between tensors.
+//!
+//! Both only look at the logical shape and values, so a transposed view
+//! equals the materialized transpose. For floats prefer
+//! [`Tensor::allclose`] or [`assert_tensor_close!`](crate::assert_tensor_close)
+//! which reports where the tensors first differ.
+
+use super::{arr1::Arr1, arr2::Arr2, tensor::Tensor, unravel};
+use crate::{
+    number::Real,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::fmt::{self, Display, Formatter};
+
+/// the default relative tolerance of `assert_tensor_close!`.
+pub const RTOL: f64 = 1e-5;
+/// the default absolute tolerance of `assert_tensor_close!`.
+pub const ATOL: f64 = 1e-8;
+
+// ======================= PartialEq =======================
+impl<T: PartialEq> PartialEq for Tensor<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape() == other.shape()
+            && self.iter_logical().eq(other.iter_logical())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Arr1<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: PartialEq> PartialEq for Arr2<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+// ======================= Mismatch =======================
+/// Why two tensors aren't close.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch<T> {
+    Shape {
+        lhs: ShapeDescriptor,
+        rhs: ShapeDescriptor,
+    },
+    Values {
+        /// the logical index of the first element out of tolerance.
+        index: Box<[usize]>,
+        lhs: T,
+        rhs: T,
+        /// `atol + rtol * |rhs|` at `index`.
+        tolerance: T,
+        /// how many elements are out of tolerance.
+        count: usize,
+        total: usize,
+        /// the largest `|lhs - rhs|` over all elements.
+        max_diff: T,
+    },
+}
+
+impl<T: Display> Display for Mismatch<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shape { lhs, rhs } => {
+                write!(f, "shapes differ, {:?} vs {:?}", &**lhs, &**rhs)
+            }
+            Self::Values {
+                index,
+                lhs,
+                rhs,
+                tolerance,
+                count,
+                total,
+                max_diff,
+            } => write!(
+                f,
+                "{count} of {total} elements differ, first at {index:?}: \
+                 {lhs} vs {rhs} (tolerance {tolerance}), max |diff| {max_diff}",
+            ),
+        }
+    }
+}
+
+// ======================= allclose =======================
+impl<T: Real> Tensor<T> {
+    /// `|self - other| <= atol + rtol * |other|` for every element, like
+    /// numpy's `allclose`. Equal infinities are close, NaNs never are.
+    pub fn allclose(&self, other: &Tensor<T>, rtol: T, atol: T) -> bool {
+        self.check_close(other, rtol, atol).is_ok()
+    }
+
+    /// [`Tensor::allclose`] reporting the first element out of tolerance.
+    pub fn check_close(
+        &self,
+        other: &Tensor<T>,
+        rtol: T,
+        atol: T,
+    ) -> Result<(), Mismatch<T>> {
+        let (lhs_shape, rhs_shape) = (self.shape(), other.shape());
+        if lhs_shape != rhs_shape {
+            return Err(Mismatch::Shape {
+                lhs: lhs_shape.into_owned(),
+                rhs: rhs_shape.into_owned(),
+            });
+        }
+
+        let mut first = None;
+        let mut count = 0;
+        let mut max_diff = T::ZERO;
+        let pairs = self.iter_logical().zip(other.iter_logical());
+        for (k, (&a, &b)) in pairs.enumerate() {
+            // equal infinities would otherwise differ by a NaN
+            let diff = if a == b { T::ZERO } else { (a - b).abs() };
+            let tolerance = atol + rtol * b.abs();
+            // an infinite diff would pass the infinite tolerance of `b = ∞`,
+            // and a NaN is incomparable, neither is ever close
+            let unbounded = (diff * T::ZERO).partial_cmp(&T::ZERO).is_none();
+            if unbounded || diff > tolerance {
+                count += 1;
+                first.get_or_insert((k, a, b, tolerance));
+            }
+            // once NaN, the max stays NaN
+            let nan = max_diff.partial_cmp(&max_diff).is_none();
+            if !nan && diff.partial_cmp(&max_diff).is_none_or(|o| o.is_gt()) {
+                max_diff = diff;
+            }
+        }
+
+        let Some((k, lhs, rhs, tolerance)) = first else {
+            return Ok(());
+        };
+        let mut index = vec![0; lhs_shape.len()].into_boxed_slice();
+        unravel(k, &lhs_shape, &mut index);
+        Err(Mismatch::Values {
+            index,
+            lhs,
+            rhs,
+            tolerance,
+            count,
+            total: lhs_shape.hypervolume(),
+            max_diff,
+        })
+    }
+}
+
+/// Asserts that two tensors are [`allclose`](Tensor::allclose), panicking
+/// with the first mismatching logical index. The tolerances default to
+/// [`RTOL`] and [`ATOL`].
+///
+/// ```
+/// use linalg::{assert_tensor_close, ndarr::arr1::Arr1, slice};
+///
+/// let a = Arr1::new(slice![1.0, 2.0]);
+/// assert_tensor_close!(a, Arr1::new(slice![1.0, 2.0 + 1e-9]));
+/// assert_tensor_close!(a, Arr1::new(slice![1.1, 2.0]), rtol = 0.1, atol = 0.0);
+/// ```
+#[macro_export]
+macro_rules! assert_tensor_close {
+    ($lhs:expr, $rhs:expr $(,)?) => {
+        $crate::assert_tensor_close!(
+            $lhs,
+            $rhs,
+            rtol = $crate::ndarr::compare::RTOL as _,
+            atol = $crate::ndarr::compare::ATOL as _,
+        )
+    };
+    ($lhs:expr, $rhs:expr, rtol = $rtol:expr, atol = $atol:expr $(,)?) => {
+        match (&$lhs, &$rhs) {
+            (lhs, rhs) => {
+                if let Err(mismatch) = lhs.check_close(rhs, $rtol, $atol) {
+                    panic!(
+                        "[[linalg]] tensors aren't close: {mismatch}\n  \
+                         left: {lhs}\n right: {rhs}"
+                    );
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,264 @@
+use super::{logical_data, tensor::Tensor, unravel};
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    number::Scalar,
+    shape::{Shape, ShapeDescriptor},
+    simd::SimdLevel,
+};
+use std::borrow::Cow;
+
+// ======================= Tensor bmm =======================
+impl<T: Scalar> Tensor<T> {
+    /// `[.., m, n] x [.., n, p] -> [.., m, p]`, see [`Tensor::try_bmm`].
+    pub fn bmm(&self, rhs: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_bmm(rhs))
+    }
+
+    /// Batched matrix product over the last two axes.
+    ///
+    /// The leading batch axes broadcast like numpy, so `[b, m, n] x [n, p]`
+    /// multiplies every matrix of the batch with the same rhs.
+    pub fn try_bmm(&self, rhs: &Tensor<T>) -> Result<Tensor<T>, LinalgError> {
+        let (lshape, rshape) = (self.shape(), rhs.shape());
+        for shape in [&lshape, &rshape] {
+            // a single matrix is a batch of none
+            if shape.len() < 2 {
+                return Err(LinalgError::RankMismatch {
+                    op: "bmm",
+                    expected: 2,
+                    found: shape.len(),
+                });
+            }
+        }
+        let mismatch = || LinalgError::ShapeMismatch {
+            op: "bmm",
+            lhs: lshape.clone().into_owned(),
+            rhs: rshape.clone().into_owned(),
+        };
+
+        let (lbatch, lmat) = lshape.split_at(lshape.len() - 2);
+        let (rbatch, rmat) = rshape.split_at(rshape.len() - 2);
+        let (m, n, p) = (lmat[0], lmat[1], rmat[1]);
+        if rmat[0] != n {
+            return Err(mismatch());
+        }
+        let batch = ShapeDescriptor(lbatch.into())
+            .broadcast(&ShapeDescriptor(rbatch.into()))
+            .ok_or_else(mismatch)?;
+
+        let (lhs, rhs) = (logical_data(self), logical_data(rhs));
+        let lstrides = broadcast_strides(lbatch, &batch, m * n);
+        let rstrides = broadcast_strides(rbatch, &batch, n * p);
+        let level = SimdLevel::detect();
+
+        let mut out = vec![T::default(); batch.hypervolume() * m * p];
+        let mut index = vec![0; batch.len()];
+        for b in 0..batch.hypervolume() {
+            unravel(b, &batch, &mut index);
+            let offset = |strides: &[usize]| -> usize {
+                index.iter().zip(strides).map(|(i, s)| i * s).sum()
+            };
+            let (lo, ro) = (offset(&lstrides), offset(&rstrides));
+            let lhs = &lhs[lo..lo + m * n];
+            let rhs = &rhs[ro..ro + n * p];
+            let out = &mut out[b * m * p..(b + 1) * m * p];
+
+            // i-k-j, one axpy per element of lhs
+            for i in 0..m {
+                let out_row = &mut out[i * p..(i + 1) * p];
+                for k in 0..n {
+                    let rhs_row = &rhs[k * p..(k + 1) * p];
+                    T::axpy(level, lhs[i * n + k], rhs_row, out_row);
+                }
+            }
+        }
+
+        let mut shape = batch.0.into_vec();
+        shape.extend([m, p]);
+        Tensor::try_new(out.into(), ShapeDescriptor(shape.into()))
+    }
+}
+
+// ======================= einsum =======================
+/// Einstein summation over `operands`, see [`try_einsum`].
+pub fn einsum<T: Scalar>(spec: &str, operands: &[&Tensor<T>]) -> Tensor<T> {
+    unwrap_or_panic(try_einsum(spec, operands))
+}
+
+/// Contracts `operands` as described by `spec`, e.g. `"bij,bjk->bik"`.
+///
+/// Each operand gets one letter per axis. Letters shared between operands
+/// must have the same length, letters missing from the output are summed
+/// over and a letter repeated within an operand walks its diagonal. Without
+/// `->` the output is every letter used exactly once, in alphabetical order.
+///
+/// This walks every combination of letters, for the batched matrix product
+/// [`Tensor::try_bmm`] is a lot faster.
+pub fn try_einsum<T: Scalar>(
+    spec: &str,
+    operands: &[&Tensor<T>],
+) -> Result<Tensor<T>, LinalgError> {
+    let subscripts = Subscripts::parse(spec)?;
+    let invalid = |reason: String| LinalgError::InvalidSubscripts {
+        spec: spec.into(),
+        reason,
+    };
+    if subscripts.inputs.len() != operands.len() {
+        return Err(invalid(format!(
+            "{} operands were given for {} subscripts",
+            operands.len(),
+            subscripts.inputs.len()
+        )));
+    }
+
+    // output letters first so the outer loop fills `out` in order
+    let mut labels = subscripts.output.clone();
+    for &c in subscripts.inputs.iter().flatten() {
+        if !labels.contains(&c) {
+            labels.push(c);
+        }
+    }
+    let position = |c: u8| labels.iter().position(|&l| l == c).unwrap();
+
+    // the length of every letter along with the operand that fixed it
+    let mut extents: Vec<Option<(usize, usize)>> = vec![None; labels.len()];
+    let mut strides = vec![vec![0; labels.len()]; operands.len()];
+    for (k, (subs, tensor)) in
+        subscripts.inputs.iter().zip(operands).enumerate()
+    {
+        let shape = tensor.shape();
+        if subs.len() != shape.len() {
+            return Err(LinalgError::RankMismatch {
+                op: "einsum",
+                expected: subs.len(),
+                found: shape.len(),
+            });
+        }
+        let tensor_strides = shape.compute_strides();
+        for (axis, &c) in subs.iter().enumerate() {
+            let l = position(c);
+            match extents[l] {
+                None => extents[l] = Some((shape[axis], k)),
+                Some((len, _)) if len == shape[axis] => {}
+                Some((_, owner)) => {
+                    return Err(LinalgError::ShapeMismatch {
+                        op: "einsum",
+                        lhs: operands[owner].shape().into_owned(),
+                        rhs: shape.into_owned(),
+                    });
+                }
+            }
+            // a repeated letter moves along both axes at once
+            strides[k][l] += tensor_strides[axis];
+        }
+    }
+
+    let extents: Vec<usize> =
+        extents.into_iter().map(|e| e.unwrap().0).collect();
+    let (out_extents, sum_extents) = extents.split_at(subscripts.output.len());
+    let out_shape = ShapeDescriptor(out_extents.into());
+    let sum_shape = ShapeDescriptor(sum_extents.into());
+    let data: Vec<Cow<'_, [T]>> =
+        operands.iter().map(|t| logical_data(t)).collect();
+
+    let mut index = vec![0; labels.len()];
+    let mut out = Vec::with_capacity(out_shape.hypervolume());
+    for o in 0..out_shape.hypervolume() {
+        unravel(o, &out_shape, &mut index[..out_extents.len()]);
+        let mut acc = T::default();
+        for s in 0..sum_shape.hypervolume() {
+            unravel(s, &sum_shape, &mut index[out_extents.len()..]);
+            let elem = |k: usize| {
+                let at: usize =
+                    index.iter().zip(&strides[k]).map(|(i, s)| i * s).sum();
+                data[k][at]
+            };
+            acc = acc + (1..operands.len()).fold(elem(0), |p, k| p * elem(k));
+        }
+        out.push(acc);
+    }
+    Tensor::try_new(out.into(), out_shape)
+}
+
+// ======================= Subscripts =======================
+/// A parsed einsum specification, one letter per axis.
+struct Subscripts {
+    inputs: Vec<Vec<u8>>,
+    output: Vec<u8>,
+}
+
+impl Subscripts {
+    fn parse(spec: &str) -> Result<Self, LinalgError> {
+        let invalid = |reason: &str| LinalgError::InvalidSubscripts {
+            spec: spec.into(),
+            reason: reason.into(),
+        };
+        let compact: String =
+            spec.chars().filter(|c| !c.is_whitespace()).collect();
+        let (lhs, output) = match compact.split_once("->") {
+            Some((lhs, output)) => (lhs, Some(output)),
+            None => (compact.as_str(), None),
+        };
+
+        let letters = |s: &str| -> Result<Vec<u8>, LinalgError> {
+            if s.bytes().all(|c| c.is_ascii_alphabetic()) {
+                Ok(s.bytes().collect())
+            } else {
+                Err(invalid("subscripts must be ascii letters"))
+            }
+        };
+        let inputs =
+            lhs.split(',').map(letters).collect::<Result<Vec<_>, _>>()?;
+        let count =
+            |c: u8| inputs.iter().flatten().filter(|&&l| l == c).count();
+
+        let output = match output {
+            Some(output) => {
+                let output = letters(output)?;
+                for (i, &c) in output.iter().enumerate() {
+                    if output[..i].contains(&c) {
+                        return Err(invalid("output letters must be unique"));
+                    }
+                    if count(c) == 0 {
+                        return Err(invalid(
+                            "output letters must appear in an input",
+                        ));
+                    }
+                }
+                output
+            }
+            None => {
+                let mut output: Vec<u8> = inputs
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .filter(|&c| count(c) == 1)
+                    .collect();
+                output.sort_unstable();
+                output
+            }
+        };
+        Ok(Self { inputs, output })
+    }
+}
+
+// ======================= helpers =======================
+/// Strides of the batch axes `dims`, laid out row-major over matrices of
+/// `matrix` elements, against the broadcast `batch` shape. Axes that are
+/// missing or of length 1 get a stride of 0 so every batch index reuses them.
+fn broadcast_strides(
+    dims: &[usize],
+    batch: &ShapeDescriptor,
+    matrix: usize,
+) -> Box<[usize]> {
+    let mut strides = vec![0; batch.len()];
+    let mut stride = matrix;
+    for (i, &d) in dims.iter().enumerate().rev() {
+        let axis = batch.len() - dims.len() + i;
+        if d != 1 {
+            strides[axis] = stride;
+        }
+        stride *= d;
+    }
+    strides.into()
+}
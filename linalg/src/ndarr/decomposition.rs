@@ -0,0 +1,659 @@
+use super::arr2::Arr2;
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    number::Real,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::ops::{Index, IndexMut};
+
+/// sweeps the Jacobi methods get before giving up, they usually need < 10.
+const MAX_SWEEPS: usize = 64;
+
+// ======================= Mat =======================
+/// Row-major scratch matrix the algorithms work on in place.
+#[derive(Clone)]
+struct Mat<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T: Real> Mat<T> {
+    fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![T::ZERO; rows * cols],
+        }
+    }
+
+    fn identity(n: usize) -> Self {
+        let mut eye = Self::zeros(n, n);
+        (0..n).for_each(|i| eye[(i, i)] = T::ONE);
+        eye
+    }
+
+    fn from_arr2(arr: &Arr2<T>) -> Self {
+        Self {
+            rows: arr.nrows(),
+            cols: arr.ncols(),
+            data: arr.iter_logical().copied().collect(),
+        }
+    }
+
+    fn into_arr2(self) -> Arr2<T> {
+        Arr2::new(self.data.into(), (self.rows, self.cols))
+    }
+
+    fn transpose(&self) -> Self {
+        let mut t = Self::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+        t
+    }
+
+    fn matmul(&self, rhs: &Self) -> Self {
+        debug_assert_eq!(self.cols, rhs.rows);
+        let mut out = Self::zeros(self.rows, rhs.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                for j in 0..rhs.cols {
+                    out[(i, j)] = out[(i, j)] + a * rhs[(k, j)];
+                }
+            }
+        }
+        out
+    }
+
+    /// the columns in the order of `order`.
+    fn select_cols(&self, order: &[usize]) -> Self {
+        let mut out = Self::zeros(self.rows, order.len());
+        for i in 0..self.rows {
+            for (j, &c) in order.iter().enumerate() {
+                out[(i, j)] = self[(i, c)];
+            }
+        }
+        out
+    }
+
+    fn max_abs(&self) -> T {
+        self.data.iter().fold(T::ZERO, |max, &x| max.max(x.abs()))
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..self.cols {
+            self.data.swap(a * self.cols + j, b * self.cols + j);
+        }
+    }
+
+    /// applies the plane rotation `(c, s)` to columns `p` and `q`.
+    fn rotate_cols(&mut self, p: usize, q: usize, c: T, s: T) {
+        for k in 0..self.rows {
+            let (kp, kq) = (self[(k, p)], self[(k, q)]);
+            self[(k, p)] = c * kp - s * kq;
+            self[(k, q)] = s * kp + c * kq;
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Mat<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Mat<T> {
+    #[inline]
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+/// `t` of the Jacobi rotation that zeroes an off diagonal entry, from
+/// `theta = (a_qq - a_pp) / 2 a_pq`.
+fn jacobi_tangent<T: Real>(theta: T) -> T {
+    let sign = if theta < T::ZERO { -T::ONE } else { T::ONE };
+    sign / (theta.abs() + (theta * theta + T::ONE).sqrt())
+}
+
+/// below this a pivot or singular value of `m` counts as zero.
+fn tolerance<T: Real>(dim: usize, scale: T) -> T {
+    T::from_usize(dim.max(1)) * T::EPSILON * scale
+}
+
+// ======================= Lu =======================
+/// `PA = LU` with partial pivoting, `L` unit lower and `U` upper triangular.
+pub struct Lu<T> {
+    /// `L` below the diagonal and `U` on and above it.
+    factors: Mat<T>,
+    perm: Box<[usize]>,
+    sign: T,
+    tolerance: T,
+}
+
+impl<T: Real> Lu<T> {
+    fn new(a: Mat<T>) -> Self {
+        let n = a.rows;
+        let tolerance = tolerance(n, a.max_abs());
+        let mut a = a;
+        let mut perm: Box<[usize]> = (0..n).collect();
+        let mut sign = T::ONE;
+
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&i, &j| {
+                    let (x, y) = (a[(i, k)].abs(), a[(j, k)].abs());
+                    x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            if a[(pivot, k)] == T::ZERO {
+                // nothing to eliminate, the column already is zero below k
+                continue;
+            }
+            if pivot != k {
+                a.swap_rows(pivot, k);
+                perm.swap(pivot, k);
+                sign = -sign;
+            }
+            for i in k + 1..n {
+                let factor = a[(i, k)] / a[(k, k)];
+                a[(i, k)] = factor;
+                for j in k + 1..n {
+                    a[(i, j)] = a[(i, j)] - factor * a[(k, j)];
+                }
+            }
+        }
+
+        Self {
+            factors: a,
+            perm,
+            sign,
+            tolerance,
+        }
+    }
+
+    /// the unit lower triangular factor.
+    pub fn l(&self) -> Arr2<T> {
+        let n = self.factors.rows;
+        let mut l = Mat::identity(n);
+        for i in 0..n {
+            for j in 0..i {
+                l[(i, j)] = self.factors[(i, j)];
+            }
+        }
+        l.into_arr2()
+    }
+
+    /// the upper triangular factor.
+    pub fn u(&self) -> Arr2<T> {
+        let n = self.factors.rows;
+        let mut u = Mat::zeros(n, n);
+        for i in 0..n {
+            for j in i..n {
+                u[(i, j)] = self.factors[(i, j)];
+            }
+        }
+        u.into_arr2()
+    }
+
+    /// row `i` of `PA` is row `permutation()[i]` of `A`.
+    pub fn permutation(&self) -> &[usize] {
+        &self.perm
+    }
+
+    pub fn det(&self) -> T {
+        let n = self.factors.rows;
+        (0..n).fold(self.sign, |det, i| det * self.factors[(i, i)])
+    }
+
+    /// whether a pivot vanished at working precision.
+    pub fn is_singular(&self) -> bool {
+        let n = self.factors.rows;
+        (0..n).any(|i| self.factors[(i, i)].abs() <= self.tolerance)
+    }
+
+    pub fn solve(&self, b: &Arr2<T>) -> Arr2<T> {
+        unwrap_or_panic(self.try_solve(b))
+    }
+
+    /// `X` s.t. `AX = b`, one column of `X` per column of `b`.
+    pub fn try_solve(&self, b: &Arr2<T>) -> Result<Arr2<T>, LinalgError> {
+        let n = self.factors.rows;
+        if b.nrows() != n {
+            return Err(LinalgError::ShapeMismatch {
+                op: "solve",
+                lhs: ShapeDescriptor(Box::new([n, n])),
+                rhs: b.shape().into_owned(),
+            });
+        }
+        if self.is_singular() {
+            return Err(LinalgError::Singular { op: "solve" });
+        }
+
+        let b = Mat::from_arr2(b);
+        let mut x = Mat::zeros(n, b.cols);
+        for i in 0..n {
+            for j in 0..b.cols {
+                x[(i, j)] = b[(self.perm[i], j)];
+            }
+        }
+        for j in 0..b.cols {
+            // forward with the unit L, then back with U
+            for i in 0..n {
+                let s = (0..i).fold(x[(i, j)], |s, k| {
+                    s - self.factors[(i, k)] * x[(k, j)]
+                });
+                x[(i, j)] = s;
+            }
+            for i in (0..n).rev() {
+                let s = (i + 1..n).fold(x[(i, j)], |s, k| {
+                    s - self.factors[(i, k)] * x[(k, j)]
+                });
+                x[(i, j)] = s / self.factors[(i, i)];
+            }
+        }
+        Ok(x.into_arr2())
+    }
+}
+
+// ======================= Qr =======================
+/// `A = QR` of an `m x n` matrix in reduced form, `Q` is `m x k` with
+/// orthonormal columns and `R` is `k x n` upper triangular, `k = min(m, n)`.
+pub struct Qr<T> {
+    pub q: Arr2<T>,
+    pub r: Arr2<T>,
+}
+
+// ======================= Cholesky =======================
+/// `A = LLᵀ` of a symmetric positive definite matrix.
+pub struct Cholesky<T> {
+    /// lower triangular with a positive diagonal.
+    pub l: Arr2<T>,
+}
+
+// ======================= SymmetricEigen =======================
+/// `A = V diag(values) Vᵀ` of a symmetric matrix.
+pub struct SymmetricEigen<T> {
+    /// the eigenvalues from largest to smallest.
+    pub values: Box<[T]>,
+    /// the matching unit eigenvectors, one per column.
+    pub vectors: Arr2<T>,
+}
+
+// ======================= Svd =======================
+/// `A = U diag(s) Vᵀ` of an `m x n` matrix with `k = min(m, n)`.
+///
+/// Columns of `u` that belong to a zero singular value are left zero.
+pub struct Svd<T> {
+    /// `m x k`
+    pub u: Arr2<T>,
+    /// the singular values from largest to smallest.
+    pub s: Box<[T]>,
+    /// `k x n`
+    pub vt: Arr2<T>,
+}
+
+impl<T: Real> Svd<T> {
+    /// below this a singular value counts as zero.
+    fn tolerance(&self) -> T {
+        let (m, n) = (self.u.nrows(), self.vt.ncols());
+        let largest = self.s.first().copied().unwrap_or(T::ZERO);
+        tolerance(m.max(n), largest)
+    }
+
+    /// the number of singular values above the tolerance.
+    pub fn rank(&self) -> usize {
+        let tol = self.tolerance();
+        self.s.iter().filter(|&&s| s > tol).count()
+    }
+
+    /// `V diag(1 / s) Uᵀ` over the singular values above the tolerance.
+    pub fn pinv(&self) -> Arr2<T> {
+        let (u, vt) = (Mat::from_arr2(&self.u), Mat::from_arr2(&self.vt));
+        let (m, n, k) = (u.rows, vt.cols, self.s.len());
+        let tol = self.tolerance();
+        let mut pinv = Mat::zeros(n, m);
+        for (l, &s) in self.s.iter().enumerate().take(k) {
+            if s <= tol {
+                continue;
+            }
+            for i in 0..n {
+                let v = vt[(l, i)] / s;
+                for j in 0..m {
+                    pinv[(i, j)] = pinv[(i, j)] + v * u[(j, l)];
+                }
+            }
+        }
+        pinv.into_arr2()
+    }
+}
+
+// ======================= Arr2 decompositions =======================
+impl<T: Real> Arr2<T> {
+    /// the `n x n` identity.
+    pub fn identity(n: usize) -> Self {
+        Mat::identity(n).into_arr2()
+    }
+
+    fn square(&self, op: &'static str) -> Result<Mat<T>, LinalgError> {
+        if self.nrows() == self.ncols() {
+            Ok(Mat::from_arr2(self))
+        } else {
+            Err(LinalgError::NotSquare {
+                op,
+                shape: self.shape().into_owned(),
+            })
+        }
+    }
+
+    pub fn lu(&self) -> Lu<T> {
+        unwrap_or_panic(self.try_lu())
+    }
+
+    /// LU with partial pivoting, singular matrices still factor.
+    pub fn try_lu(&self) -> Result<Lu<T>, LinalgError> {
+        Ok(Lu::new(self.square("lu")?))
+    }
+
+    /// Householder QR, see [`Qr`].
+    pub fn qr(&self) -> Qr<T> {
+        let mut a = Mat::from_arr2(self);
+        let (m, n) = (a.rows, a.cols);
+        let k = m.min(n);
+        let mut q = Mat::identity(m);
+
+        for j in 0..k {
+            let norm = (j..m).map(|i| a[(i, j)] * a[(i, j)]).sum::<T>().sqrt();
+            if norm == T::ZERO {
+                continue;
+            }
+            // reflect onto -sign(a_jj) |x| e_j to avoid cancellation
+            let alpha = if a[(j, j)] > T::ZERO { -norm } else { norm };
+            let mut v: Vec<T> = (j..m).map(|i| a[(i, j)]).collect();
+            v[0] = v[0] - alpha;
+            let v_norm = v.iter().map(|&x| x * x).sum::<T>();
+            if v_norm == T::ZERO {
+                continue;
+            }
+            let two = T::ONE + T::ONE;
+
+            for c in j..n {
+                let dot = (0..v.len()).map(|i| v[i] * a[(j + i, c)]).sum::<T>();
+                let f = two * dot / v_norm;
+                for (i, &vi) in v.iter().enumerate() {
+                    a[(j + i, c)] = a[(j + i, c)] - f * vi;
+                }
+            }
+            for r in 0..m {
+                let dot = (0..v.len()).map(|i| q[(r, j + i)] * v[i]).sum::<T>();
+                let f = two * dot / v_norm;
+                for (i, &vi) in v.iter().enumerate() {
+                    q[(r, j + i)] = q[(r, j + i)] - f * vi;
+                }
+            }
+        }
+
+        let mut r = Mat::zeros(k, n);
+        for i in 0..k {
+            for j in i..n {
+                r[(i, j)] = a[(i, j)];
+            }
+        }
+        let q = q.select_cols(&(0..k).collect::<Vec<_>>());
+        Qr {
+            q: q.into_arr2(),
+            r: r.into_arr2(),
+        }
+    }
+
+    pub fn cholesky(&self) -> Cholesky<T> {
+        unwrap_or_panic(self.try_cholesky())
+    }
+
+    /// Only the lower triangle is read, the upper one is assumed to mirror it.
+    pub fn try_cholesky(&self) -> Result<Cholesky<T>, LinalgError> {
+        let a = self.square("cholesky")?;
+        let n = a.rows;
+        let mut l = Mat::zeros(n, n);
+        for j in 0..n {
+            let diag = (0..j).fold(a[(j, j)], |s, k| s - l[(j, k)] * l[(j, k)]);
+            if diag <= T::ZERO {
+                return Err(LinalgError::NotPositiveDefinite);
+            }
+            let diag = diag.sqrt();
+            l[(j, j)] = diag;
+            for i in j + 1..n {
+                let s =
+                    (0..j).fold(a[(i, j)], |s, k| s - l[(i, k)] * l[(j, k)]);
+                l[(i, j)] = s / diag;
+            }
+        }
+        Ok(Cholesky { l: l.into_arr2() })
+    }
+
+    pub fn symmetric_eigen(&self) -> SymmetricEigen<T> {
+        unwrap_or_panic(self.try_symmetric_eigen())
+    }
+
+    /// Cyclic Jacobi, rotates away the off diagonal entries one at a time.
+    pub fn try_symmetric_eigen(
+        &self,
+    ) -> Result<SymmetricEigen<T>, LinalgError> {
+        const OP: &str = "symmetric_eigen";
+        let mut a = self.square(OP)?;
+        let n = a.rows;
+        let tol = tolerance(n, a.max_abs());
+        for i in 0..n {
+            for j in 0..i {
+                if (a[(i, j)] - a[(j, i)]).abs() > tol {
+                    return Err(LinalgError::NotSymmetric { op: OP });
+                }
+            }
+        }
+
+        let mut v = Mat::identity(n);
+        let scale = a.data.iter().map(|&x| x * x).sum::<T>().sqrt();
+        let mut converged = false;
+        for _ in 0..MAX_SWEEPS {
+            let off = (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .map(|(i, j)| a[(i, j)] * a[(i, j)])
+                .sum::<T>();
+            if off.sqrt() <= T::EPSILON * scale {
+                converged = true;
+                break;
+            }
+            for p in 0..n {
+                for q in p + 1..n {
+                    let apq = a[(p, q)];
+                    if apq == T::ZERO {
+                        continue;
+                    }
+                    let two = T::ONE + T::ONE;
+                    let t =
+                        jacobi_tangent((a[(q, q)] - a[(p, p)]) / (two * apq));
+                    let c = T::ONE / (t * t + T::ONE).sqrt();
+                    let s = t * c;
+                    // A <- JᵀAJ, first the columns then the rows
+                    a.rotate_cols(p, q, c, s);
+                    for k in 0..n {
+                        let (pk, qk) = (a[(p, k)], a[(q, k)]);
+                        a[(p, k)] = c * pk - s * qk;
+                        a[(q, k)] = s * pk + c * qk;
+                    }
+                    v.rotate_cols(p, q, c, s);
+                }
+            }
+        }
+        if !converged {
+            return Err(LinalgError::NoConvergence {
+                op: OP,
+                sweeps: MAX_SWEEPS,
+            });
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| {
+            a[(j, j)]
+                .partial_cmp(&a[(i, i)])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(SymmetricEigen {
+            values: order.iter().map(|&i| a[(i, i)]).collect(),
+            vectors: v.select_cols(&order).into_arr2(),
+        })
+    }
+
+    pub fn svd(&self) -> Svd<T> {
+        unwrap_or_panic(self.try_svd())
+    }
+
+    /// One-sided Jacobi, orthogonalises the columns of `A` against each other.
+    pub fn try_svd(&self) -> Result<Svd<T>, LinalgError> {
+        let a = Mat::from_arr2(self);
+        if a.rows < a.cols {
+            // Aᵀ = U S Vᵀ  =>  A = V S Uᵀ
+            let Svd { u, s, vt } = a.transpose().into_arr2().try_svd()?;
+            let (u, vt) = (Mat::from_arr2(&u), Mat::from_arr2(&vt));
+            return Ok(Svd {
+                u: vt.transpose().into_arr2(),
+                s,
+                vt: u.transpose().into_arr2(),
+            });
+        }
+
+        let n = a.cols;
+        let mut u = a;
+        let mut v = Mat::identity(n);
+        let column = |u: &Mat<T>, p: usize, q: usize| {
+            (0..u.rows).map(|i| u[(i, p)] * u[(i, q)]).sum::<T>()
+        };
+        // columns below this are zero up to rounding and stop rotating,
+        // otherwise rank deficient input never settles
+        let scale = u.data.iter().map(|&x| x * x).sum::<T>();
+        let floor = T::EPSILON * T::EPSILON * scale;
+        let mut converged = n < 2;
+        for _ in 0..MAX_SWEEPS {
+            let mut rotated = false;
+            for p in 0..n {
+                for q in p + 1..n {
+                    let alpha = column(&u, p, p);
+                    let beta = column(&u, q, q);
+                    let gamma = column(&u, p, q);
+                    if alpha <= floor
+                        || beta <= floor
+                        || gamma.abs() <= T::EPSILON * (alpha * beta).sqrt()
+                    {
+                        continue;
+                    }
+                    rotated = true;
+                    let two = T::ONE + T::ONE;
+                    let t = jacobi_tangent((beta - alpha) / (two * gamma));
+                    let c = T::ONE / (t * t + T::ONE).sqrt();
+                    let s = t * c;
+                    u.rotate_cols(p, q, c, s);
+                    v.rotate_cols(p, q, c, s);
+                }
+            }
+            if !rotated {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            return Err(LinalgError::NoConvergence {
+                op: "svd",
+                sweeps: MAX_SWEEPS,
+            });
+        }
+
+        let norms: Vec<T> = (0..n).map(|j| column(&u, j, j).sqrt()).collect();
+        for (j, &norm) in norms.iter().enumerate() {
+            if norm > T::ZERO {
+                (0..u.rows).for_each(|i| u[(i, j)] = u[(i, j)] / norm);
+            }
+        }
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| {
+            norms[j]
+                .partial_cmp(&norms[i])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(Svd {
+            u: u.select_cols(&order).into_arr2(),
+            s: order.iter().map(|&i| norms[i]).collect(),
+            vt: v.select_cols(&order).transpose().into_arr2(),
+        })
+    }
+
+    pub fn det(&self) -> T {
+        unwrap_or_panic(self.try_det())
+    }
+
+    pub fn try_det(&self) -> Result<T, LinalgError> {
+        Ok(Lu::new(self.square("det")?).det())
+    }
+
+    pub fn inv(&self) -> Self {
+        unwrap_or_panic(self.try_inv())
+    }
+
+    pub fn try_inv(&self) -> Result<Self, LinalgError> {
+        let lu = Lu::new(self.square("inv")?);
+        if lu.is_singular() {
+            return Err(LinalgError::Singular { op: "inv" });
+        }
+        lu.try_solve(&Self::identity(self.nrows()))
+    }
+
+    pub fn solve(&self, b: &Self) -> Self {
+        unwrap_or_panic(self.try_solve(b))
+    }
+
+    /// `X` s.t. `AX = b` for a square, non singular `A`.
+    pub fn try_solve(&self, b: &Self) -> Result<Self, LinalgError> {
+        Lu::new(self.square("solve")?).try_solve(b)
+    }
+
+    pub fn lstsq(&self, b: &Self) -> Self {
+        unwrap_or_panic(self.try_lstsq(b))
+    }
+
+    /// The `X` minimising `‖AX - b‖`, the smallest such one when `A` is
+    /// rank deficient.
+    pub fn try_lstsq(&self, b: &Self) -> Result<Self, LinalgError> {
+        if b.nrows() != self.nrows() {
+            return Err(LinalgError::ShapeMismatch {
+                op: "lstsq",
+                lhs: self.shape().into_owned(),
+                rhs: b.shape().into_owned(),
+            });
+        }
+        let pinv = Mat::from_arr2(&self.try_pinv()?);
+        Ok(pinv.matmul(&Mat::from_arr2(b)).into_arr2())
+    }
+
+    pub fn rank(&self) -> usize {
+        unwrap_or_panic(self.try_rank())
+    }
+
+    pub fn try_rank(&self) -> Result<usize, LinalgError> {
+        Ok(self.try_svd()?.rank())
+    }
+
+    /// the Moore-Penrose pseudo inverse.
+    pub fn pinv(&self) -> Self {
+        unwrap_or_panic(self.try_pinv())
+    }
+
+    pub fn try_pinv(&self) -> Result<Self, LinalgError> {
+        Ok(self.try_svd()?.pinv())
+    }
+}
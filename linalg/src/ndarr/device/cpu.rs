@@ -0,0 +1,325 @@
+use super::{
+    Device, DeviceBackend, DeviceError, DeviceId, DeviceTensorHandle, Kernel,
+    KernelOp, memory::DeviceMemory,
+};
+use crate::{
+    ndarr::{tensor::Tensor, transform::default_slice},
+    number::Scalar,
+    shape::{Shape, ShapeDescriptor},
+    simd::SimdLevel,
+};
+use std::{num::NonZeroUsize, ops::Range, thread};
+
+/// below this many elements of work per thread it's cheaper to stay on the
+/// calling thread than to spawn.
+const DEFAULT_GRAIN: usize = 1 << 12;
+
+// ======================= CpuDevice =======================
+/// Runs kernels on the host, splitting each one over scoped threads.
+///
+/// There is no long lived pool, every launch scopes its own threads so the
+/// borrowed buffers never outlive the call. Within a thread the work goes
+/// through the [`SimdElement`] kernels of the best [`SimdLevel`] the cpu has.
+pub struct CpuDevice {
+    threads: usize,
+    grain: usize,
+    simd: SimdLevel,
+    memory: DeviceMemory,
+}
+
+impl CpuDevice {
+    /// uses every core the OS reports.
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+        Self::with_threads(threads)
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            grain: DEFAULT_GRAIN,
+            simd: SimdLevel::detect(),
+            memory: DeviceMemory::new(DeviceBackend::CPU),
+        }
+    }
+
+    /// sets the minimum amount of elements each thread should work on.
+    pub fn with_grain(mut self, grain: usize) -> Self {
+        self.grain = grain.max(1);
+        self
+    }
+
+    /// caps the instruction set the kernels may use, e.g. to compare
+    /// against [`SimdLevel::Portable`]. Levels the cpu lacks are lowered.
+    pub fn with_simd(mut self, level: SimdLevel) -> Self {
+        self.simd = level.supported();
+        self
+    }
+
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    #[inline]
+    pub fn simd(&self) -> SimdLevel {
+        self.simd
+    }
+
+    #[inline]
+    pub fn id(&self) -> DeviceId {
+        self.memory.device()
+    }
+
+    /// how many buffers are currently allocated on the device.
+    #[inline]
+    pub fn live_buffers(&self) -> usize {
+        self.memory.live()
+    }
+
+    /// how many threads a job of `work` elements split in `units` is worth.
+    fn workers(&self, units: usize, work: usize) -> usize {
+        self.threads.min(units).min(work / self.grain).max(1)
+    }
+
+    /// Splits `out` into chunks of whole `unit`s and hands each chunk along
+    /// with the index of its first unit to `f`, one chunk per thread.
+    fn par_chunks_mut<T, F>(
+        &self,
+        out: &mut [T],
+        unit: usize,
+        work: usize,
+        f: F,
+    ) where
+        T: Send,
+        F: Fn(usize, &mut [T]) + Sync,
+    {
+        let units = out.len() / unit.max(1);
+        let workers = self.workers(units, work);
+        if workers == 1 {
+            f(0, out);
+            return;
+        }
+
+        let per_worker = units.div_ceil(workers);
+        thread::scope(|scope| {
+            let mut chunks = out.chunks_mut(per_worker * unit).enumerate();
+            // the calling thread takes the first chunk itself
+            let first = chunks.next();
+            for (i, chunk) in chunks {
+                let f = &f;
+                scope.spawn(move || f(i * per_worker, chunk));
+            }
+            if let Some((_, chunk)) = first {
+                f(0, chunk);
+            }
+        });
+    }
+
+    // ======================= kernels =======================
+    /// `out[m, p] = lhs[m, n] x rhs[n, p]` on row-major buffers, split by
+    /// rows of `out`.
+    pub fn matmul<T: Scalar>(
+        &self,
+        lhs: &[T],
+        rhs: &[T],
+        out: &mut [T],
+        (m, n, p): (usize, usize, usize),
+    ) {
+        assert!(lhs.len() == m * n && rhs.len() == n * p && out.len() == m * p);
+        self.par_chunks_mut(out, p, m * n * p, |first_row, rows| {
+            for (r, out_row) in rows.chunks_mut(p.max(1)).enumerate() {
+                let i = first_row + r;
+                out_row.fill(T::default());
+                // i-k-j order turns every step into an axpy along rows
+                for k in 0..n {
+                    let rhs_row = &rhs[k * p..(k + 1) * p];
+                    T::axpy(self.simd, lhs[i * n + k], rhs_row, out_row);
+                }
+            }
+        });
+    }
+
+    /// `out[i] = lhs[i] + rhs[i]`
+    pub fn add<T: Scalar>(&self, lhs: &[T], rhs: &[T], out: &mut [T]) {
+        self.par_slices(lhs, rhs, out, T::add_slices);
+    }
+
+    /// `out[i] = lhs[i] - rhs[i]`
+    pub fn sub<T: Scalar>(&self, lhs: &[T], rhs: &[T], out: &mut [T]) {
+        self.par_slices(lhs, rhs, out, T::sub_slices);
+    }
+
+    /// `out[i] = lhs[i] * rhs[i]`
+    pub fn mul<T: Scalar>(&self, lhs: &[T], rhs: &[T], out: &mut [T]) {
+        self.par_slices(lhs, rhs, out, T::mul_slices);
+    }
+
+    /// `y[i] += alpha * x[i]`
+    pub fn axpy<T: Scalar>(&self, alpha: T, x: &[T], y: &mut [T]) {
+        assert!(x.len() == y.len());
+        self.par_chunks_mut(y, 1, x.len(), |first, chunk| {
+            let x = &x[first..first + chunk.len()];
+            T::axpy(self.simd, alpha, x, chunk);
+        });
+    }
+
+    /// `Σ lhs[i] * rhs[i]`
+    pub fn dot<T: Scalar>(&self, lhs: &[T], rhs: &[T]) -> T {
+        assert!(lhs.len() == rhs.len());
+        let len = lhs.len();
+        self.par_fold(len, |range| {
+            Some(T::dot(self.simd, &lhs[range.clone()], &rhs[range]))
+        })
+        .into_iter()
+        .sum()
+    }
+
+    /// sum of every element, zero when `input` is empty.
+    pub fn sum<T: Scalar>(&self, input: &[T]) -> T {
+        self.par_fold(input.len(), |range| {
+            Some(T::sum_slice(self.simd, &input[range]))
+        })
+        .into_iter()
+        .sum()
+    }
+
+    /// the largest element, `None` when `input` is empty.
+    pub fn max<T: Scalar>(&self, input: &[T]) -> Option<T> {
+        let partials = self.par_fold(input.len(), |range| {
+            T::max_slice(self.simd, &input[range])
+        });
+        T::max_slice(self.simd, &partials)
+    }
+
+    /// runs a vector kernel over matching chunks of `lhs`, `rhs` and `out`.
+    fn par_slices<T: Scalar>(
+        &self,
+        lhs: &[T],
+        rhs: &[T],
+        out: &mut [T],
+        kernel: fn(SimdLevel, &[T], &[T], &mut [T]),
+    ) {
+        assert!(lhs.len() == out.len() && rhs.len() == out.len());
+        self.par_chunks_mut(out, 1, lhs.len(), |first, chunk| {
+            let range = first..first + chunk.len();
+            kernel(self.simd, &lhs[range.clone()], &rhs[range], chunk);
+        });
+    }
+
+    /// Splits `0..len` into one range per thread and collects what `f`
+    /// makes of each, leaving out the `None`s.
+    fn par_fold<T, F>(&self, len: usize, f: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(Range<usize>) -> Option<T> + Sync,
+    {
+        let workers = self.workers(len, len);
+        if workers == 1 {
+            return f(0..len).into_iter().collect();
+        }
+
+        let per_worker = len.div_ceil(workers);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..len)
+                .step_by(per_worker)
+                .map(|start| {
+                    let f = &f;
+                    scope.spawn(move || f(start..(start + per_worker).min(len)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|h| {
+                    h.join().expect("[[linalg]] cpu kernel panicked")
+                })
+                .collect()
+        })
+    }
+
+    /// Runs an already validated `kernel` on the buffers behind its handles,
+    /// used by every device that borrows the host for compute.
+    pub(crate) fn run<T: Scalar>(
+        &self,
+        kernel: &Kernel<T>,
+        inputs: &[&[T]],
+        out: &mut [T],
+    ) -> Result<(), DeviceError> {
+        match kernel.op {
+            KernelOp::MatMul => {
+                let (l, r) =
+                    (kernel.inputs[0].shape(), kernel.inputs[1].shape());
+                self.matmul(inputs[0], inputs[1], out, (l[0], l[1], r[1]));
+            }
+            KernelOp::Add => self.add(inputs[0], inputs[1], out),
+            KernelOp::Sub => self.sub(inputs[0], inputs[1], out),
+            KernelOp::Mul => self.mul(inputs[0], inputs[1], out),
+            KernelOp::Dot => out[0] = self.dot(inputs[0], inputs[1]),
+            KernelOp::Sum => out[0] = self.sum(inputs[0]),
+            KernelOp::Max => {
+                out[0] = self.max(inputs[0]).ok_or_else(|| {
+                    DeviceError::KernelFailed {
+                        kernel: KernelOp::Max,
+                        reason: "empty input".into(),
+                    }
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for CpuDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ======================= CpuDevice Device =======================
+impl Device for CpuDevice {
+    fn backend(&self) -> DeviceBackend {
+        DeviceBackend::CPU
+    }
+
+    fn upload<T: Scalar>(
+        &self,
+        tensor: &Tensor<T>,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError> {
+        let data = tensor.iter_logical().copied().collect();
+        Ok(self.memory.insert(data, tensor.shape().into_owned()))
+    }
+
+    fn alloc<T: Scalar>(
+        &self,
+        shape: ShapeDescriptor,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError> {
+        Ok(self
+            .memory
+            .insert(default_slice(shape.hypervolume()), shape))
+    }
+
+    fn launch_kernel<T: Scalar>(
+        &self,
+        kernel: &Kernel<T>,
+    ) -> Result<(), DeviceError> {
+        self.memory
+            .with_kernel(kernel, |inputs, out| self.run(kernel, inputs, out))
+    }
+
+    fn download<T: Scalar>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<Tensor<T>, DeviceError> {
+        let data = self.memory.read(handle)?;
+        Ok(Tensor::new(data, handle.shape().clone()))
+    }
+
+    fn free<T: Scalar>(
+        &self,
+        handle: DeviceTensorHandle<T>,
+    ) -> Result<(), DeviceError> {
+        self.memory.remove(&handle)
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    DeviceBackend, DeviceError, DeviceId, DeviceTensorHandle, HandleId, Kernel,
+};
+use crate::{number::Scalar, shape::ShapeDescriptor};
+use std::{
+    any::Any,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+type Buffers = HashMap<HandleId, Box<dyn Any + Send>>;
+
+// ======================= DeviceMemory =======================
+/// The buffers owned by one device, each a type erased `Box<[T]>` that is
+/// only reachable through the handles [`DeviceMemory::insert`] gives out.
+pub(crate) struct DeviceMemory {
+    device: DeviceId,
+    next: AtomicU64,
+    bytes: AtomicUsize,
+    buffers: Mutex<Buffers>,
+}
+
+impl DeviceMemory {
+    pub(crate) fn new(backend: DeviceBackend) -> Self {
+        Self {
+            device: DeviceId::unique(backend),
+            next: AtomicU64::new(0),
+            bytes: AtomicUsize::new(0),
+            buffers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn device(&self) -> DeviceId {
+        self.device
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buffers> {
+        // a panicking kernel only loses its own output buffer
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// number of live buffers.
+    pub(crate) fn live(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// size of every live buffer together.
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn insert<T: Scalar>(
+        &self,
+        data: Box<[T]>,
+        shape: ShapeDescriptor,
+    ) -> DeviceTensorHandle<T> {
+        debug_assert_eq!(data.len(), shape.iter().product::<usize>());
+        let id = HandleId(self.next.fetch_add(1, Ordering::Relaxed));
+        self.bytes
+            .fetch_add(size_of_val::<[T]>(&data), Ordering::Relaxed);
+        self.lock().insert(id, Box::new(data));
+        DeviceTensorHandle {
+            id,
+            device: self.device,
+            shape,
+            dtype: PhantomData,
+        }
+    }
+
+    fn check<T>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<(), DeviceError> {
+        let host = DeviceBackend::CPU;
+        if handle.device == self.device {
+            Ok(())
+        } else if handle.device.backend == host && self.device.backend != host {
+            Err(DeviceError::HostMemory { handle: handle.id })
+        } else {
+            Err(DeviceError::WrongDevice {
+                expected: self.device,
+                found: handle.device,
+            })
+        }
+    }
+
+    /// copies the buffer behind `handle` out.
+    pub(crate) fn read<T: Scalar>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<Box<[T]>, DeviceError> {
+        self.check(handle)?;
+        Ok(buffer::<T>(&self.lock(), handle.id)?.into())
+    }
+
+    pub(crate) fn remove<T: Scalar>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<(), DeviceError> {
+        self.check(handle)?;
+        let mut buffers = self.lock();
+        let bytes = size_of_val(buffer::<T>(&buffers, handle.id)?);
+        buffers.remove(&handle.id);
+        self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Validates `kernel` and runs `f` on its input buffers and its output
+    /// buffer, in the order the kernel lists them.
+    pub(crate) fn with_kernel<T, R, F>(
+        &self,
+        kernel: &Kernel<T>,
+        f: F,
+    ) -> Result<R, DeviceError>
+    where
+        T: Scalar,
+        F: FnOnce(&[&[T]], &mut [T]) -> Result<R, DeviceError>,
+    {
+        kernel.validate()?;
+        for handle in kernel.inputs.iter().chain(Some(&kernel.output)) {
+            self.check(handle)?;
+        }
+
+        let mut buffers = self.lock();
+        // the output is taken out of the map so it can be written while the
+        // inputs are borrowed, validate() already ruled out aliasing
+        buffer::<T>(&buffers, kernel.output.id)?;
+        let mut output = buffers.remove(&kernel.output.id).unwrap();
+
+        let result = kernel
+            .inputs
+            .iter()
+            .map(|h| buffer::<T>(&buffers, h.id))
+            .collect::<Result<Vec<&[T]>, _>>()
+            .and_then(|inputs| {
+                let out = output.downcast_mut::<Box<[T]>>().unwrap();
+                f(&inputs, out)
+            });
+
+        buffers.insert(kernel.output.id, output);
+        result
+    }
+}
+
+fn buffer<T: Scalar>(
+    buffers: &Buffers,
+    id: HandleId,
+) -> Result<&[T], DeviceError> {
+    buffers
+        .get(&id)
+        .ok_or(DeviceError::UnknownHandle { handle: id })?
+        .downcast_ref::<Box<[T]>>()
+        .map(|b| &**b)
+        .ok_or(DeviceError::DTypeMismatch { handle: id })
+}
@@ -0,0 +1,417 @@
+pub mod cpu;
+mod memory;
+pub mod simulated;
+
+use std::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::tensor::Tensor;
+use crate::{
+    number::Scalar,
+    shape::{Shape, ShapeDescriptor},
+};
+use simulated::FailurePoint;
+
+// ======================= DeviceError =======================
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceError {
+    /// the handle was freed or never belonged to any memory.
+    UnknownHandle { handle: HandleId },
+    /// the handle was created by another device.
+    WrongDevice { expected: DeviceId, found: DeviceId },
+    /// the memory behind the handle holds another element type.
+    DTypeMismatch { handle: HandleId },
+    /// the handles given to a kernel don't fit together.
+    ShapeMismatch {
+        kernel: KernelOp,
+        shapes: Box<[ShapeDescriptor]>,
+    },
+    /// a kernel got the wrong number of inputs.
+    Arity {
+        kernel: KernelOp,
+        expected: usize,
+        found: usize,
+    },
+    /// a kernel would write into one of its own inputs.
+    AliasedOutput { kernel: KernelOp },
+    /// the kernel can't run on this input, e.g. the max of nothing.
+    KernelFailed { kernel: KernelOp, reason: String },
+    /// host memory was handed to a device that only computes on its own.
+    HostMemory { handle: HandleId },
+    /// the device doesn't have room for another buffer.
+    OutOfMemory { requested: usize, available: usize },
+    /// a failure set up with [`simulated::SimulatedDevice::inject_failure`].
+    Injected { point: FailurePoint },
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownHandle { handle } => {
+                write!(f, "{handle:?} doesn't refer to live device memory")
+            }
+            Self::WrongDevice { expected, found } => write!(
+                f,
+                "handle belongs to {found:?} but was used on {expected:?}"
+            ),
+            Self::DTypeMismatch { handle } => {
+                write!(f, "{handle:?} holds a different element type")
+            }
+            Self::ShapeMismatch { kernel, shapes } => {
+                let shapes: Vec<&[usize]> =
+                    shapes.iter().map(|s| &**s).collect();
+                write!(
+                    f,
+                    "mismatched shapes {shapes:?} for the {kernel:?} kernel"
+                )
+            }
+            Self::Arity {
+                kernel,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the {kernel:?} kernel takes {expected} inputs but got {found}"
+            ),
+            Self::AliasedOutput { kernel } => write!(
+                f,
+                "the {kernel:?} kernel can't write into one of its inputs"
+            ),
+            Self::KernelFailed { kernel, reason } => {
+                write!(f, "the {kernel:?} kernel failed: {reason}")
+            }
+            Self::HostMemory { handle } => write!(
+                f,
+                "{handle:?} lives in host memory, upload it to the device first"
+            ),
+            Self::OutOfMemory {
+                requested,
+                available,
+            } => write!(
+                f,
+                "out of device memory: requested {requested} bytes but only \
+                 {available} are available"
+            ),
+            Self::Injected { point } => {
+                write!(f, "injected failure during {point:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+// ======================= Device =======================
+/// A place tensors can be moved to and computed on.
+///
+/// Memory lives inside the device and is only reachable through the handles
+/// it gives out. It's released with [`Device::free`] or when the device
+/// itself is dropped.
+pub trait Device {
+    fn backend(&self) -> DeviceBackend;
+
+    /// copies the logical elements of `tensor` into new device memory.
+    fn upload<T: Scalar>(
+        &self,
+        tensor: &Tensor<T>,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError>;
+
+    /// reserves zeroed device memory, e.g. for a kernel output.
+    fn alloc<T: Scalar>(
+        &self,
+        shape: ShapeDescriptor,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError>;
+
+    fn launch_kernel<T: Scalar>(
+        &self,
+        kernel: &Kernel<T>,
+    ) -> Result<(), DeviceError>;
+
+    /// copies the device memory back into a host tensor.
+    fn download<T: Scalar>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<Tensor<T>, DeviceError>;
+
+    fn free<T: Scalar>(
+        &self,
+        handle: DeviceTensorHandle<T>,
+    ) -> Result<(), DeviceError>;
+}
+
+// ======================= DeviceId =======================
+/// Tells devices apart so handles can't be used on the wrong one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceId {
+    id: u64,
+    backend: DeviceBackend,
+}
+
+impl DeviceId {
+    pub(crate) fn unique(backend: DeviceBackend) -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT.fetch_add(1, Ordering::Relaxed),
+            backend,
+        }
+    }
+
+    /// the kind of device the id was handed out to.
+    #[inline]
+    pub fn backend(&self) -> DeviceBackend {
+        self.backend
+    }
+}
+
+// ======================= HandleId =======================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandleId(pub(crate) u64);
+
+// ======================= DeviceTensorHandle =======================
+/// A typed reference to one contiguous row-major tensor in device memory.
+///
+/// Handles are cheap to clone, the memory isn't duplicated.
+#[derive(Debug)]
+pub struct DeviceTensorHandle<T> {
+    id: HandleId,
+    device: DeviceId,
+    shape: ShapeDescriptor,
+    dtype: PhantomData<T>,
+}
+
+impl<T> DeviceTensorHandle<T> {
+    #[inline]
+    pub fn id(&self) -> HandleId {
+        self.id
+    }
+
+    #[inline]
+    pub fn device(&self) -> DeviceId {
+        self.device
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &ShapeDescriptor {
+        &self.shape
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shape.hypervolume()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Clone for DeviceTensorHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            device: self.device,
+            shape: self.shape.clone(),
+            dtype: PhantomData,
+        }
+    }
+}
+
+// ======================= KernelOp =======================
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelOp {
+    /// `[m, n] x [n, p] -> [m, p]`
+    MatMul,
+    /// elementwise `lhs + rhs`
+    Add,
+    /// elementwise `lhs - rhs`
+    Sub,
+    /// elementwise `lhs * rhs`
+    Mul,
+    /// `Σ lhs * rhs` of two equally long inputs into a single element output.
+    Dot,
+    /// sums every element into a single element output.
+    Sum,
+    /// the largest element into a single element output.
+    Max,
+}
+
+impl KernelOp {
+    /// how many inputs the op reads.
+    pub fn arity(&self) -> usize {
+        match self {
+            Self::MatMul | Self::Add | Self::Sub | Self::Mul | Self::Dot => 2,
+            Self::Sum | Self::Max => 1,
+        }
+    }
+}
+
+// ======================= Kernel =======================
+/// A description of one launch: the op, the handles it reads and the
+/// handle it writes. It doesn't own any memory and can be launched again.
+#[derive(Debug, Clone)]
+pub struct Kernel<T> {
+    pub op: KernelOp,
+    pub inputs: Box<[DeviceTensorHandle<T>]>,
+    pub output: DeviceTensorHandle<T>,
+}
+
+impl<T> Kernel<T> {
+    pub fn new(
+        op: KernelOp,
+        inputs: &[&DeviceTensorHandle<T>],
+        output: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self {
+            op,
+            inputs: inputs.iter().map(|&h| h.clone()).collect(),
+            output: output.clone(),
+        }
+    }
+
+    pub fn matmul(
+        lhs: &DeviceTensorHandle<T>,
+        rhs: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::MatMul, &[lhs, rhs], out)
+    }
+
+    pub fn add(
+        lhs: &DeviceTensorHandle<T>,
+        rhs: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Add, &[lhs, rhs], out)
+    }
+
+    pub fn sub(
+        lhs: &DeviceTensorHandle<T>,
+        rhs: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Sub, &[lhs, rhs], out)
+    }
+
+    pub fn mul(
+        lhs: &DeviceTensorHandle<T>,
+        rhs: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Mul, &[lhs, rhs], out)
+    }
+
+    pub fn dot(
+        lhs: &DeviceTensorHandle<T>,
+        rhs: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Dot, &[lhs, rhs], out)
+    }
+
+    pub fn sum(
+        input: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Sum, &[input], out)
+    }
+
+    pub fn max(
+        input: &DeviceTensorHandle<T>,
+        out: &DeviceTensorHandle<T>,
+    ) -> Self {
+        Self::new(KernelOp::Max, &[input], out)
+    }
+
+    /// Checks the arity, aliasing and shapes of the handles against the op.
+    /// Every backend runs this before touching memory.
+    pub fn validate(&self) -> Result<(), DeviceError> {
+        let op = self.op;
+        if self.inputs.len() != op.arity() {
+            return Err(DeviceError::Arity {
+                kernel: op,
+                expected: op.arity(),
+                found: self.inputs.len(),
+            });
+        }
+        if self.inputs.iter().any(|h| h.id == self.output.id) {
+            return Err(DeviceError::AliasedOutput { kernel: op });
+        }
+
+        let shapes: Vec<&ShapeDescriptor> = self
+            .inputs
+            .iter()
+            .chain(Some(&self.output))
+            .map(|h| &h.shape)
+            .collect();
+        let fits = match op {
+            KernelOp::MatMul => {
+                let (l, r, o) = (shapes[0], shapes[1], shapes[2]);
+                l.len() == 2
+                    && r.len() == 2
+                    && l[1] == r[0]
+                    && **o == [l[0], r[1]]
+            }
+            KernelOp::Add | KernelOp::Sub | KernelOp::Mul => {
+                shapes[0] == shapes[1] && shapes[0] == shapes[2]
+            }
+            KernelOp::Dot => {
+                shapes[0].hypervolume() == shapes[1].hypervolume()
+                    && shapes[2].hypervolume() == 1
+            }
+            KernelOp::Sum | KernelOp::Max => shapes[1].hypervolume() == 1,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(DeviceError::ShapeMismatch {
+                kernel: op,
+                shapes: shapes.into_iter().cloned().collect(),
+            })
+        }
+    }
+}
+
+// ======================= DeviceBackend =======================
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceBackend {
+    #[default]
+    CPU,
+    CUDA,
+    VULKAN,
+    WEBGL,
+}
+
+// pub mod slice_cpu {
+//     use crate::ndarr::tensor::Tensor;
+//     use super::Device;
+
+//     impl<T> Device<T> for Box<[T]> {
+//         type Idx = usize;
+
+//         fn at(&self, flat_index: Self::Idx) -> Option<&T> {
+//             if 0 >= flat_index && flat_index < self.len() {
+//                 Some(&self[flat_index])
+//             } else {
+//                 panic!("flat indexing out of bounds");
+//             }
+//         }
+
+//         fn set_at(&mut self, flat_index: Self::Idx, value: T) {
+//             if 0 >= flat_index && flat_index < self.len() {
+//                 self[flat_index] = value;
+//             } else {
+//                 panic!("flat indexing out of bounds");
+//             }
+//         }
+//     }
+
+//     impl<'a, T> From<Box<[T]>> for Tensor<'a, T, Box<[T]>> {
+//         fn from(value: Box<[T]>) -> Self {
+//             Tensor::new(value)
+//         }
+//     }
+// }
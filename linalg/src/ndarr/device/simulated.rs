@@ -0,0 +1,236 @@
+use super::{
+    Device, DeviceBackend, DeviceError, DeviceId, DeviceTensorHandle, Kernel,
+    cpu::CpuDevice, memory::DeviceMemory,
+};
+use crate::{
+    ndarr::{tensor::Tensor, transform::default_slice},
+    number::Scalar,
+    shape::{Shape, ShapeDescriptor},
+};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// ======================= FailurePoint =======================
+/// The device calls a failure can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePoint {
+    Upload,
+    Alloc,
+    Launch,
+    Download,
+}
+
+// ======================= DeviceStats =======================
+/// What crossed the simulated bus so far, see [`SimulatedDevice::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceStats {
+    pub uploads: usize,
+    pub downloads: usize,
+    /// host to device bytes.
+    pub bytes_uploaded: usize,
+    /// device to host bytes.
+    pub bytes_downloaded: usize,
+    pub kernel_launches: usize,
+    /// the most device memory that was live at once.
+    pub peak_bytes: usize,
+}
+
+#[derive(Default)]
+struct SimState {
+    stats: DeviceStats,
+    /// pending failures as `(point, calls left before it fires)`.
+    failures: Vec<(FailurePoint, usize)>,
+}
+
+// ======================= SimulatedDevice =======================
+/// A stand-in for a discrete accelerator that runs entirely on the host.
+///
+/// Buffers live in the device's own arena and only handles cross the
+/// boundary, so code that forgets to upload or download fails the same way
+/// it would on real hardware. Host memory, e.g. a [`CpuDevice`] handle, is
+/// rejected with [`DeviceError::HostMemory`]. Kernels are computed on a
+/// single thread with the cpu kernels.
+pub struct SimulatedDevice {
+    backend: DeviceBackend,
+    capacity: Option<usize>,
+    memory: DeviceMemory,
+    compute: CpuDevice,
+    state: Mutex<SimState>,
+}
+
+impl SimulatedDevice {
+    /// pretends to be a device of kind `backend` with unlimited memory.
+    pub fn new(backend: DeviceBackend) -> Self {
+        Self {
+            backend,
+            capacity: None,
+            memory: DeviceMemory::new(backend),
+            compute: CpuDevice::with_threads(1),
+            state: Mutex::new(SimState::default()),
+        }
+    }
+
+    /// limits the device memory to `bytes`, going over is an
+    /// [`DeviceError::OutOfMemory`].
+    pub fn with_capacity(mut self, bytes: usize) -> Self {
+        self.capacity = Some(bytes);
+        self
+    }
+
+    #[inline]
+    pub fn id(&self) -> DeviceId {
+        self.memory.device()
+    }
+
+    #[inline]
+    pub fn live_buffers(&self) -> usize {
+        self.memory.live()
+    }
+
+    /// bytes of device memory currently allocated.
+    #[inline]
+    pub fn allocated_bytes(&self) -> usize {
+        self.memory.bytes()
+    }
+
+    pub fn stats(&self) -> DeviceStats {
+        self.lock().stats.clone()
+    }
+
+    pub fn reset_stats(&self) {
+        self.lock().stats = DeviceStats {
+            peak_bytes: self.memory.bytes(),
+            ..DeviceStats::default()
+        };
+    }
+
+    /// makes the next call at `point` fail with [`DeviceError::Injected`].
+    pub fn inject_failure(&self, point: FailurePoint) {
+        self.inject_failure_after(point, 0);
+    }
+
+    /// lets `calls` calls at `point` through, then fails the one after.
+    pub fn inject_failure_after(&self, point: FailurePoint, calls: usize) {
+        self.lock().failures.push((point, calls));
+    }
+
+    /// drops every failure that hasn't fired yet.
+    pub fn clear_failures(&self) {
+        self.lock().failures.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// counts a call at `point` against the pending failures.
+    fn trip(&self, point: FailurePoint) -> Result<(), DeviceError> {
+        let mut state = self.lock();
+        let mut fired = false;
+        state.failures.retain_mut(|(p, calls)| {
+            if *p != point || fired {
+                return true;
+            }
+            if *calls == 0 {
+                fired = true;
+                return false;
+            }
+            *calls -= 1;
+            true
+        });
+        if fired {
+            Err(DeviceError::Injected { point })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// checks the capacity and stores the buffer under one lock, so
+    /// concurrent calls can't each see room for their own buffer.
+    fn insert<T: Scalar>(
+        &self,
+        shape: ShapeDescriptor,
+        data: impl FnOnce() -> Box<[T]>,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError> {
+        let mut state = self.lock();
+        let bytes = self.memory.bytes();
+        let available = self
+            .capacity
+            .map_or(usize::MAX, |capacity| capacity.saturating_sub(bytes));
+        match shape.hypervolume().checked_mul(size_of::<T>()) {
+            Some(requested) if requested <= available => {}
+            requested => {
+                return Err(DeviceError::OutOfMemory {
+                    requested: requested.unwrap_or(usize::MAX),
+                    available,
+                });
+            }
+        }
+        let handle = self.memory.insert(data(), shape);
+        state.stats.peak_bytes =
+            state.stats.peak_bytes.max(self.memory.bytes());
+        Ok(handle)
+    }
+}
+
+// ======================= SimulatedDevice Device =======================
+impl Device for SimulatedDevice {
+    fn backend(&self) -> DeviceBackend {
+        self.backend
+    }
+
+    fn upload<T: Scalar>(
+        &self,
+        tensor: &Tensor<T>,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError> {
+        self.trip(FailurePoint::Upload)?;
+        let handle = self.insert(tensor.shape().into_owned(), || {
+            tensor.iter_logical().copied().collect()
+        })?;
+        let stats = &mut self.lock().stats;
+        stats.uploads += 1;
+        stats.bytes_uploaded += tensor.shape().hypervolume() * size_of::<T>();
+        Ok(handle)
+    }
+
+    fn alloc<T: Scalar>(
+        &self,
+        shape: ShapeDescriptor,
+    ) -> Result<DeviceTensorHandle<T>, DeviceError> {
+        self.trip(FailurePoint::Alloc)?;
+        let len = shape.hypervolume();
+        self.insert(shape, || default_slice(len))
+    }
+
+    fn launch_kernel<T: Scalar>(
+        &self,
+        kernel: &Kernel<T>,
+    ) -> Result<(), DeviceError> {
+        self.trip(FailurePoint::Launch)?;
+        self.memory.with_kernel(kernel, |inputs, out| {
+            self.compute.run(kernel, inputs, out)
+        })?;
+        self.lock().stats.kernel_launches += 1;
+        Ok(())
+    }
+
+    fn download<T: Scalar>(
+        &self,
+        handle: &DeviceTensorHandle<T>,
+    ) -> Result<Tensor<T>, DeviceError> {
+        self.trip(FailurePoint::Download)?;
+        let data = self.memory.read(handle)?;
+        {
+            let stats = &mut self.lock().stats;
+            stats.downloads += 1;
+            stats.bytes_downloaded += size_of_val::<[T]>(&data);
+        }
+        Ok(Tensor::new(data, handle.shape().clone()))
+    }
+
+    fn free<T: Scalar>(
+        &self,
+        handle: DeviceTensorHandle<T>,
+    ) -> Result<(), DeviceError> {
+        self.memory.remove(&handle)
+    }
+}
@@ -0,0 +1,178 @@
+//! `Display` and `Debug` for tensors, printed like numpy arrays.
+//!
+//! Elements are visited through the active transform, right aligned to the
+//! widest one and formatted with the precision of the format string, so
+//! `{:.2}` prints every float with two decimals. Tensors with more than
+//! [`THRESHOLD`] elements only print the first and last [`EDGE_ITEMS`] of
+//! every axis with `...` in between.
+
+use super::{
+    arr1::Arr1,
+    arr2::Arr2,
+    tensor::{Tensor, TensorAccess},
+};
+use crate::shape::Shape;
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// tensors with more elements than this are summarized.
+pub const THRESHOLD: usize = 1000;
+/// the number of leading and trailing entries kept per summarized axis.
+pub const EDGE_ITEMS: usize = 3;
+
+/// the printed positions along an axis of length `len`, `None` is the gap.
+fn visible(len: usize, summarize: bool) -> Vec<Option<usize>> {
+    if summarize && len > 2 * EDGE_ITEMS {
+        let head = (0..EDGE_ITEMS).map(Some);
+        let tail = (len - EDGE_ITEMS..len).map(Some);
+        head.chain([None]).chain(tail).collect()
+    } else {
+        (0..len).map(Some).collect()
+    }
+}
+
+/// Prints `tensor` as nested brackets, each element through `cell`.
+fn pretty<T>(
+    tensor: &Tensor<T>,
+    f: &mut Formatter<'_>,
+    cell: impl Fn(&T) -> String,
+) -> fmt::Result {
+    let shape = tensor.shape();
+    let summarize = shape.hypervolume() > THRESHOLD;
+
+    // format everything that's shown up front to know the column width
+    let mut cells = Vec::new();
+    let mut index = vec![0; shape.len()];
+    collect(tensor, &shape, summarize, 0, &mut index, &mut |x| {
+        cells.push(cell(x))
+    });
+    let width = cells.iter().map(|c| c.chars().count()).max().unwrap_or(0);
+    let mut cells = cells.into_iter();
+    write_axis(f, &shape, summarize, 0, width, &mut cells)
+}
+
+fn collect<T>(
+    tensor: &Tensor<T>,
+    shape: &[usize],
+    summarize: bool,
+    axis: usize,
+    index: &mut [usize],
+    push: &mut impl FnMut(&T),
+) {
+    if axis == shape.len() {
+        push(&tensor[&*index]);
+        return;
+    }
+    for i in visible(shape[axis], summarize).into_iter().flatten() {
+        index[axis] = i;
+        collect(tensor, shape, summarize, axis + 1, index, push);
+    }
+}
+
+fn write_axis(
+    f: &mut Formatter<'_>,
+    shape: &[usize],
+    summarize: bool,
+    axis: usize,
+    width: usize,
+    cells: &mut impl Iterator<Item = String>,
+) -> fmt::Result {
+    if axis == shape.len() {
+        let cell = cells.next().unwrap_or_default();
+        return write!(f, "{cell:>width$}");
+    }
+    let innermost = axis + 1 == shape.len();
+    f.write_str("[")?;
+    for (k, i) in visible(shape[axis], summarize).into_iter().enumerate() {
+        if k > 0 {
+            if innermost {
+                f.write_str(", ")?;
+            } else {
+                // a blank line per axis left below, then line up the brackets
+                f.write_str(",")?;
+                for _ in axis + 1..shape.len() {
+                    f.write_str("\n")?;
+                }
+                write!(f, "{:indent$}", "", indent = axis + 1)?;
+            }
+        }
+        match i {
+            Some(_) => write_axis(f, shape, summarize, axis + 1, width, cells)?,
+            None => f.write_str("...")?,
+        }
+    }
+    f.write_str("]")
+}
+
+/// the data of a tensor as its `Debug` elements.
+struct DebugData<'a, T>(&'a Tensor<T>);
+
+impl<T: Debug> Debug for DebugData<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(p) => pretty(self.0, f, |x| format!("{x:.p$?}")),
+            None => pretty(self.0, f, |x| format!("{x:?}")),
+        }
+    }
+}
+
+fn debug<T: Debug>(
+    name: &str,
+    tensor: &Tensor<T>,
+    f: &mut Formatter<'_>,
+) -> fmt::Result {
+    let strides = match tensor.transform() {
+        Some(t) => t.out_strides(),
+        None => Cow::Borrowed(tensor.strides()),
+    };
+    f.debug_struct(name)
+        .field("shape", &&*tensor.shape().0)
+        .field("strides", &&*strides)
+        .field("contiguous", &tensor.is_contiguous())
+        .field("transform", &tensor.transform().is_some())
+        .field("data", &DebugData(tensor))
+        .finish()
+}
+
+// ======================= Tensor =======================
+impl<T: Display> Display for Tensor<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(p) => pretty(self, f, |x| format!("{x:.p$}")),
+            None => pretty(self, f, |x| format!("{x}")),
+        }
+    }
+}
+
+impl<T: Debug> Debug for Tensor<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        debug("Tensor", self, f)
+    }
+}
+
+// ======================= Arr1 / Arr2 =======================
+impl<T: Display> Display for Arr1<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl<T: Debug> Debug for Arr1<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        debug("Arr1", self, f)
+    }
+}
+
+impl<T: Display> Display for Arr2<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl<T: Debug> Debug for Arr2<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        debug("Arr2", self, f)
+    }
+}
@@ -1,5 +1,34 @@
 pub mod arr1;
 pub mod arr2;
+pub mod compare;
+pub mod contract;
+pub mod decomposition;
 pub mod device;
+pub mod display;
+pub mod stensor;
 pub mod tensor;
 pub mod transform;
+pub mod view;
+
+use std::borrow::Cow;
+use tensor::{Tensor, TensorAccess};
+
+// ======================= helpers =======================
+/// the logical elements of `tensor` in row-major order, borrowed when the
+/// buffer already holds them that way.
+pub(crate) fn logical_data<T: Clone>(tensor: &Tensor<T>) -> Cow<'_, [T]> {
+    if tensor.is_contiguous() {
+        Cow::Borrowed(tensor.data())
+    } else {
+        Cow::Owned(tensor.iter_logical().cloned().collect())
+    }
+}
+
+/// writes the multi-index of the `k`th element of a row-major `shape` into
+/// `index`, one entry per axis.
+pub(crate) fn unravel(mut k: usize, shape: &[usize], index: &mut [usize]) {
+    for (i, &d) in index.iter_mut().zip(shape).rev() {
+        *i = k % d;
+        k /= d;
+    }
+}
@@ -0,0 +1,252 @@
+use super::{arr2::Arr2, tensor::Tensor};
+use crate::shape::{Shape, ShapeDescriptor};
+use std::{borrow::Cow, iter::Sum, ops::*};
+
+//====================== STensor ======================
+/// A statically shaped `R x C` tensor.
+///
+/// Unlike [`Arr2`] the dimensions live in the type, so shape mismatches in
+/// `matmul`/`matadd` are caught by the compiler instead of an `assert_eq!`.
+/// Converting to and from the dynamic [`Tensor`] is a single flat copy.
+///
+/// ```compile_fail
+/// use linalg::ndarr::stensor::STensor;
+///
+/// let a = STensor::new([[1.0, 2.0], [3.0, 4.0]]); // 2x2
+/// let b = STensor::new([[1.0, 2.0, 3.0]]); // 1x3
+/// let _ = a.matmul(&b); // 2x2 * 1x3 does not compile
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct STensor<T, const R: usize, const C: usize>([[T; C]; R]);
+
+/// A statically shaped column vector.
+pub type SVec<T, const N: usize> = STensor<T, N, 1>;
+
+impl<T, const R: usize, const C: usize> STensor<T, R, C> {
+    pub const ROWS: usize = R;
+    pub const COLS: usize = C;
+
+    pub fn new(data: [[T; C]; R]) -> Self {
+        Self(data)
+    }
+
+    pub fn from_fn<F>(mut f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        Self(std::array::from_fn(|i| std::array::from_fn(|j| f(i, j))))
+    }
+
+    #[inline]
+    pub const fn nrows(&self) -> usize {
+        R
+    }
+
+    #[inline]
+    pub const fn ncols(&self) -> usize {
+        C
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> &[[T; C]; R] {
+        &self.0
+    }
+
+    #[inline]
+    pub fn into_array(self) -> [[T; C]; R] {
+        self.0
+    }
+
+    pub fn transpose(&self) -> STensor<T, C, R>
+    where
+        T: Clone + Copy,
+    {
+        STensor::from_fn(|i, j| self.0[j][i])
+    }
+
+    pub fn matmul<const P: usize>(
+        &self,
+        rhs: &STensor<T, C, P>,
+    ) -> STensor<T, R, P>
+    where
+        T: Clone + Copy,
+        T: Mul<Output = T>,
+        T: Sum<T>,
+    {
+        STensor::from_fn(|i, j| {
+            (0..C).map(|k| self.0[i][k] * rhs.0[k][j]).sum()
+        })
+    }
+
+    pub fn matadd(&self, rhs: &Self) -> Self
+    where
+        T: Clone + Copy,
+        T: Add<Output = T>,
+    {
+        Self::from_fn(|i, j| self.0[i][j] + rhs.0[i][j])
+    }
+
+    /// flattens the rows into a row-major buffer.
+    fn flat_slice(&self) -> Box<[T]>
+    where
+        T: Clone + Copy,
+    {
+        self.0.iter().flatten().copied().collect()
+    }
+}
+
+//====================== STensor Default ======================
+impl<T, const R: usize, const C: usize> Default for STensor<T, R, C>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Self::from_fn(|_, _| T::default())
+    }
+}
+
+//====================== STensor Shape ======================
+impl<T, const R: usize, const C: usize> Shape for STensor<T, R, C> {
+    #[inline(always)]
+    fn shape(&self) -> Cow<'_, ShapeDescriptor> {
+        Cow::Owned(ShapeDescriptor(Box::new([R, C])))
+    }
+
+    #[inline(always)]
+    fn hypervolume(&self) -> usize {
+        R * C
+    }
+
+    #[inline(always)]
+    fn rank(&self) -> usize {
+        2
+    }
+}
+
+//====================== STensor Index ======================
+impl<T, const R: usize, const C: usize> Index<[usize; 2]> for STensor<T, R, C> {
+    type Output = T;
+
+    fn index(&self, [i, j]: [usize; 2]) -> &Self::Output {
+        &self.0[i][j]
+    }
+}
+
+//====================== STensor IndexMut ======================
+impl<T, const R: usize, const C: usize> IndexMut<[usize; 2]>
+    for STensor<T, R, C>
+{
+    fn index_mut(&mut self, [i, j]: [usize; 2]) -> &mut Self::Output {
+        &mut self.0[i][j]
+    }
+}
+
+//====================== STensor Mul ======================
+impl<T, const R: usize, const C: usize, const P: usize> Mul<STensor<T, C, P>>
+    for STensor<T, R, C>
+where
+    T: Mul<Output = T>,
+    T: Sum<T>,
+    T: Clone + Copy,
+{
+    type Output = STensor<T, R, P>;
+    fn mul(self, rhs: STensor<T, C, P>) -> Self::Output {
+        self.matmul(&rhs)
+    }
+}
+
+//====================== STensor Add ======================
+impl<T, const R: usize, const C: usize> Add for STensor<T, R, C>
+where
+    T: Add<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.matadd(&rhs)
+    }
+}
+
+//====================== STensor Sub ======================
+impl<T, const R: usize, const C: usize> Sub for STensor<T, R, C>
+where
+    T: Sub<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_fn(|i, j| self.0[i][j] - rhs.0[i][j])
+    }
+}
+
+//====================== STensor Neg ======================
+impl<T, const R: usize, const C: usize> Neg for STensor<T, R, C>
+where
+    T: Neg<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::from_fn(|i, j| -self.0[i][j])
+    }
+}
+
+//====================== STensor From<[[T; C]; R]> ======================
+impl<T, const R: usize, const C: usize> From<[[T; C]; R]> for STensor<T, R, C> {
+    #[inline]
+    fn from(value: [[T; C]; R]) -> Self {
+        Self::new(value)
+    }
+}
+
+//====================== Tensor From<STensor> ======================
+impl<T, const R: usize, const C: usize> From<STensor<T, R, C>> for Tensor<T>
+where
+    T: Clone + Copy,
+{
+    fn from(value: STensor<T, R, C>) -> Self {
+        Tensor::new(value.flat_slice(), ShapeDescriptor(Box::new([R, C])))
+    }
+}
+
+//====================== Arr2 From<STensor> ======================
+impl<T, const R: usize, const C: usize> From<STensor<T, R, C>> for Arr2<T>
+where
+    T: Clone + Copy,
+{
+    fn from(value: STensor<T, R, C>) -> Self {
+        Arr2::new(value.flat_slice(), (R, C))
+    }
+}
+
+//====================== STensor TryFrom<Tensor> ======================
+/// The tensor is handed back untouched when its logical shape isn't `[R, C]`,
+/// the same way `<[T; N]>::try_from(Vec<T>)` does.
+impl<T, const R: usize, const C: usize> TryFrom<Tensor<T>> for STensor<T, R, C>
+where
+    T: Clone + Copy,
+{
+    type Error = Tensor<T>;
+
+    fn try_from(value: Tensor<T>) -> Result<Self, Self::Error> {
+        if *value.shape() != ShapeDescriptor(Box::new([R, C])) {
+            return Err(value);
+        }
+        Ok(Self::from_fn(|i, j| value[&[i, j][..]]))
+    }
+}
+
+//====================== STensor TryFrom<Arr2> ======================
+impl<T, const R: usize, const C: usize> TryFrom<Arr2<T>> for STensor<T, R, C>
+where
+    T: Clone + Copy,
+{
+    type Error = Arr2<T>;
+
+    fn try_from(value: Arr2<T>) -> Result<Self, Self::Error> {
+        if value.nrows() != R || value.ncols() != C {
+            return Err(value);
+        }
+        Ok(Self::from_fn(|i, j| value[[i, j]]))
+    }
+}
@@ -1,34 +1,504 @@
-use super::transform::compute_flat_index;
+use super::{
+    transform::{
+        compute_flat_index, compute_logical_index,
+        concrete_transformers::ReshapeTransform, slice_from_fn_uninit,
+    },
+    view::{AxisSplit, TensorView, TensorViewMut, without_axis},
+};
 pub(crate) use super::transform::Transform;
-use crate::shape::{Shape, ShapeDescriptor};
-use std::{borrow::Cow, marker::PhantomData, ops::{Index, IndexMut}};
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{
+    borrow::Cow,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+    sync::Arc,
+};
 
 // ======================= Container =======================
 /// .
 #[derive(Clone)]
-pub struct Tensor<'a, T> {
+pub struct Tensor<T> {
     dtype: PhantomData<T>,
-    transform: Option<&'a dyn Transform>,
+    transform: Option<Arc<dyn Transform>>,
     data: Box<[T]>,
     shape: ShapeDescriptor,
     strides: Box<[usize]>,
 }
 
-impl<'a, T> Tensor<'a, T> {
+impl<T> Tensor<T> {
     pub fn new(data: Box<[T]>, shape: ShapeDescriptor) -> Self {
+        unwrap_or_panic(Self::try_new(data, shape))
+    }
+
+    pub fn try_new(
+        data: Box<[T]>,
+        shape: ShapeDescriptor,
+    ) -> Result<Self, LinalgError> {
+        if data.len() != shape.hypervolume() {
+            return Err(LinalgError::DataLength {
+                expected: shape.hypervolume(),
+                found: data.len(),
+            });
+        }
         let strides = shape.compute_strides();
-        Self {
+        Ok(Self {
             dtype: PhantomData::<T>,
             transform: None,
             data,
             shape,
             strides,
+        })
+    }
+
+    /// maps a logical index to the flat buffer index, checking it against
+    /// the logical shape first.
+    fn try_flat_index(&self, logical: &[usize]) -> Result<usize, LinalgError> {
+        let shape = self.shape();
+        let in_bounds = logical.len() == shape.len()
+            && logical.iter().zip(shape.iter()).all(|(i, axis)| i < axis);
+        if !in_bounds {
+            return Err(LinalgError::IndexOutOfBounds {
+                index: logical.into(),
+                shape: shape.into_owned(),
+            });
         }
+
+        Ok(self.flat_index(logical))
+    }
+
+    /// unchecked logical to flat buffer index.
+    #[inline]
+    fn flat_index(&self, logical: &[usize]) -> usize {
+        match &self.transform {
+            Some(t) => t.to_flat(logical),
+            None => compute_flat_index(logical, self.strides()),
+        }
+    }
+
+    pub fn try_get(&self, logical: &[usize]) -> Result<&T, LinalgError> {
+        let flat = self.try_flat_index(logical)?;
+        Ok(&self.data[flat])
+    }
+
+    pub fn try_get_mut(
+        &mut self,
+        logical: &[usize],
+    ) -> Result<&mut T, LinalgError> {
+        let flat = self.try_flat_index(logical)?;
+        Ok(&mut self.data[flat])
+    }
+
+    /// elementwise addition over the logical shape.
+    pub fn try_add(&self, rhs: &Tensor<T>) -> Result<Tensor<T>, LinalgError>
+    where
+        T: std::ops::Add<Output = T>,
+        T: Clone + Copy,
+    {
+        let shape = self.shape();
+        if shape != rhs.shape() {
+            return Err(LinalgError::ShapeMismatch {
+                op: "add",
+                lhs: shape.into_owned(),
+                rhs: rhs.shape().into_owned(),
+            });
+        }
+
+        // both buffers already are in logical order
+        if self.is_contiguous() && rhs.is_contiguous() {
+            let data = self
+                .data
+                .iter()
+                .zip(rhs.data.iter())
+                .map(|(&l, &r)| l + r)
+                .collect();
+            return Tensor::try_new(data, shape.into_owned());
+        }
+
+        let strides = shape.compute_strides();
+        let data = slice_from_fn_uninit(shape.hypervolume(), |flat| {
+            let logical = compute_logical_index(flat, &strides, &shape);
+            self[&logical[..]] + rhs[&logical[..]]
+        });
+        Tensor::try_new(data, shape.into_owned())
+    }
+}
+
+// ======================= Tensor Layout =======================
+/// Once a transform is set the raw buffer (what `Deref` hands out) no longer
+/// matches the logical layout. These bring the two back in line.
+impl<T> Tensor<T> {
+    /// whether the raw buffer holds the logical elements in row-major order,
+    /// i.e. whether iterating `Deref` visits them in logical order.
+    pub fn is_contiguous(&self) -> bool {
+        match &self.transform {
+            None => *self.strides == *self.shape.compute_strides(),
+            // strides alone say nothing about how `to_flat` maps, so the
+            // transform has to vouch for it
+            Some(t) => {
+                t.is_row_major()
+                    && t.out_shape().hypervolume() == self.data.len()
+            }
+        }
+    }
+
+    /// a fresh row-major copy of the logical elements, without a transform.
+    pub fn contiguous(&self) -> Self
+    where
+        T: Clone,
+    {
+        let data = self.iter_logical().cloned().collect();
+        Tensor::new(data, self.shape().into_owned())
+    }
+
+    /// like [`Tensor::contiguous`] but reuses the buffer when it's already
+    /// in logical order.
+    pub fn to_owned_layout(mut self) -> Self
+    where
+        T: Clone,
+    {
+        if !self.is_contiguous() {
+            return self.contiguous();
+        }
+        if let Some(t) = self.transform.take() {
+            self.shape = t.out_shape().into_owned();
+            self.strides = t.out_strides().into_owned().into();
+        }
+        self
+    }
+}
+
+// ======================= Tensor Iteration =======================
+/// Iteration in logical row-major order, i.e. through the active transform,
+/// as opposed to iterating the raw buffer through `Deref`.
+impl<T> Tensor<T> {
+    /// maps the row-major position of a logical element to its buffer
+    /// index, without holding on to the tensor.
+    fn flat_mapper(&self) -> impl Fn(usize) -> usize + use<T> {
+        let shape = self.shape().into_owned();
+        let logical_strides = shape.compute_strides();
+        let contiguous = self.is_contiguous();
+        let transform = self.transform.clone();
+        let strides = self.strides.clone();
+        move |pos| {
+            if contiguous {
+                return pos;
+            }
+            let logical = compute_logical_index(pos, &logical_strides, &shape);
+            match &transform {
+                Some(t) => t.to_flat(&logical),
+                None => compute_flat_index(&logical, &strides),
+            }
+        }
+    }
+
+    /// the buffer index of every logical element, in logical order.
+    fn logical_flat_indices(
+        &self,
+    ) -> impl ExactSizeIterator<Item = usize> + use<T> {
+        (0..self.shape().hypervolume()).map(self.flat_mapper())
+    }
+
+    /// mutable references to the logical elements in logical order.
+    ///
+    /// Panics if the transform maps two logical indices onto the same
+    /// element, since handing out both would alias.
+    fn logical_refs_mut(&mut self) -> Vec<&mut T> {
+        let flats: Vec<usize> = self.logical_flat_indices().collect();
+        let mut slots: Vec<Option<&mut T>> =
+            self.data.iter_mut().map(Some).collect();
+        flats
+            .into_iter()
+            .map(|flat| {
+                slots[flat].take().expect(
+                    "[[linalg]] transform maps several logical indices onto \
+                     one element, mutable iteration would alias",
+                )
+            })
+            .collect()
+    }
+
+    /// panics if the transform maps two logical indices onto the same
+    /// element, in which case mutable access would alias.
+    fn assert_unaliased(&self) {
+        let mut seen = vec![false; self.data.len()];
+        for flat in self.logical_flat_indices() {
+            assert!(
+                !std::mem::replace(&mut seen[flat], true),
+                "[[linalg]] transform maps several logical indices onto \
+                 one element, mutable iteration would alias",
+            );
+        }
+    }
+
+    pub fn iter_logical(&self) -> impl ExactSizeIterator<Item = &T> + '_ {
+        self.logical_flat_indices().map(|flat| &self.data[flat])
+    }
+
+    pub fn iter_logical_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = &mut T> + '_ {
+        self.logical_refs_mut().into_iter()
+    }
+
+    pub fn indexed_iter(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (Box<[usize]>, &T)> + '_ {
+        let shape = self.shape().into_owned();
+        let strides = shape.compute_strides();
+        self.iter_logical().enumerate().map(move |(flat, elem)| {
+            (compute_logical_index(flat, &strides, &shape), elem)
+        })
+    }
+
+    pub fn indexed_iter_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = (Box<[usize]>, &mut T)> + '_ {
+        let shape = self.shape().into_owned();
+        let strides = shape.compute_strides();
+        self.logical_refs_mut().into_iter().enumerate().map(
+            move |(flat, elem)| {
+                (compute_logical_index(flat, &strides, &shape), elem)
+            },
+        )
+    }
+
+    /// one view per index along `axis`, each with `axis` removed.
+    pub fn axis_iter(
+        &self,
+        axis: usize,
+    ) -> impl ExactSizeIterator<Item = TensorView<'_, T>> {
+        unwrap_or_panic(self.try_axis_iter(axis))
+    }
+
+    pub fn try_axis_iter(
+        &self,
+        axis: usize,
+    ) -> Result<impl ExactSizeIterator<Item = TensorView<'_, T>>, LinalgError>
+    {
+        let shape = self.shape();
+        let split = AxisSplit::new(&shape, axis)?;
+        let sub_shape = without_axis(&shape, axis);
+        let flat = self.flat_mapper();
+        // each view gathers its references only once it's reached
+        Ok((0..split.len).map(move |index| {
+            let elems = split
+                .positions(index)
+                .map(|pos| &self.data[flat(pos)])
+                .collect();
+            TensorView::new(elems, sub_shape.clone())
+        }))
+    }
+
+    pub fn axis_iter_mut(
+        &mut self,
+        axis: usize,
+    ) -> impl ExactSizeIterator<Item = TensorViewMut<'_, T>> {
+        unwrap_or_panic(self.try_axis_iter_mut(axis))
+    }
+
+    pub fn try_axis_iter_mut(
+        &mut self,
+        axis: usize,
+    ) -> Result<
+        impl ExactSizeIterator<Item = TensorViewMut<'_, T>>,
+        LinalgError,
+    > {
+        let shape = self.shape().into_owned();
+        let split = AxisSplit::new(&shape, axis)?;
+        let sub_shape = without_axis(&shape, axis);
+        let flat = self.flat_mapper();
+        if !self.is_contiguous() {
+            self.assert_unaliased();
+        }
+        let data = self.data.as_mut_ptr();
+        Ok((0..split.len).map(move |index| {
+            let elems = split
+                .positions(index)
+                // SAFETY: `data` stays mutably borrowed for as long as the
+                // views live, and distinct logical indices map to distinct
+                // elements, so no two views or refs ever alias
+                .map(|pos| unsafe { &mut *data.add(flat(pos)) })
+                .collect();
+            TensorViewMut::new(elems, sub_shape.clone())
+        }))
+    }
+}
+
+// ======================= Tensor Reshaping =======================
+/// Every reshape consumes the tensor and hands back one whose shape and
+/// strides already have the [`ReshapeTransform`] baked in, so no transform
+/// has to be carried around for plain row-major data.
+///
+/// A single `-1` in the requested dimensions is inferred from the others.
+impl<T> Tensor<T> {
+    /// zero-copy reshape, errors when the data isn't laid out row-major.
+    pub fn view(self, dims: &[isize]) -> Self {
+        unwrap_or_panic(self.try_view(dims))
+    }
+
+    pub fn try_view(self, dims: &[isize]) -> Result<Self, LinalgError> {
+        if !self.is_contiguous() {
+            return Err(LinalgError::NotContiguous { op: "view" });
+        }
+        let len = self.shape().hypervolume();
+        let dst = ShapeDescriptor::try_infer(dims, len)?;
+        self.apply_reshape(dst)
+    }
+
+    /// reshapes in place when possible, copying into a contiguous buffer
+    /// otherwise.
+    pub fn reshape(self, dims: &[isize]) -> Self
+    where
+        T: Clone,
+    {
+        unwrap_or_panic(self.try_reshape(dims))
+    }
+
+    pub fn try_reshape(self, dims: &[isize]) -> Result<Self, LinalgError>
+    where
+        T: Clone,
+    {
+        let len = self.shape().hypervolume();
+        let dst = ShapeDescriptor::try_infer(dims, len)?;
+        self.to_owned_layout().apply_reshape(dst)
+    }
+
+    /// collapses every axis into one.
+    pub fn flatten(self) -> Self
+    where
+        T: Clone,
+    {
+        self.reshape(&[-1])
+    }
+
+    /// drops every axis of length 1.
+    pub fn squeeze(self) -> Self
+    where
+        T: Clone,
+    {
+        let dims: Box<[isize]> = self
+            .shape()
+            .iter()
+            .filter(|&&axis| axis != 1)
+            .map(|&axis| axis as isize)
+            .collect();
+        self.reshape(&dims)
+    }
+
+    /// drops `axis` which must have length 1.
+    pub fn squeeze_axis(self, axis: usize) -> Self
+    where
+        T: Clone,
+    {
+        unwrap_or_panic(self.try_squeeze_axis(axis))
+    }
+
+    pub fn try_squeeze_axis(self, axis: usize) -> Result<Self, LinalgError>
+    where
+        T: Clone,
+    {
+        let shape = self.shape().into_owned();
+        if axis >= shape.len() {
+            return Err(LinalgError::AxisOutOfBounds {
+                axis,
+                rank: shape.len(),
+            });
+        }
+        if shape[axis] != 1 {
+            return Err(LinalgError::NotSqueezable {
+                axis,
+                len: shape[axis],
+            });
+        }
+        let dims: Box<[isize]> = without_axis(&shape, axis)
+            .iter()
+            .map(|&d| d as isize)
+            .collect();
+        self.try_reshape(&dims)
+    }
+
+    /// inserts an axis of length 1 before `axis`.
+    pub fn unsqueeze(self, axis: usize) -> Self
+    where
+        T: Clone,
+    {
+        unwrap_or_panic(self.try_unsqueeze(axis))
+    }
+
+    pub fn try_unsqueeze(self, axis: usize) -> Result<Self, LinalgError>
+    where
+        T: Clone,
+    {
+        let shape = self.shape();
+        if axis > shape.len() {
+            return Err(LinalgError::AxisOutOfBounds {
+                axis,
+                rank: shape.len(),
+            });
+        }
+        let mut dims: Vec<isize> = shape.iter().map(|&d| d as isize).collect();
+        dims.insert(axis, 1);
+        self.try_reshape(&dims)
+    }
+
+    /// the data has to be contiguous row-major by the time this is called.
+    fn apply_reshape(
+        mut self,
+        dst: ShapeDescriptor,
+    ) -> Result<Self, LinalgError> {
+        let transform = ReshapeTransform::new(&self.shape, dst)?;
+        self.strides = transform.out_strides().into_owned().into();
+        self.shape = transform.out_shape().into_owned();
+        self.transform = None;
+        Ok(self)
+    }
+}
+
+// ======================= Tensor Joining =======================
+impl<T> Tensor<T> {
+    /// stacks equally shaped tensors along a new leading axis.
+    pub fn stack(tensors: &[Tensor<T>]) -> Self
+    where
+        T: Clone,
+    {
+        unwrap_or_panic(Self::try_stack(tensors))
+    }
+
+    /// `n` tensors of shape `s` become one of shape `[n, ..s]`, nothing
+    /// stacks into shape `[0]`.
+    pub fn try_stack(tensors: &[Tensor<T>]) -> Result<Self, LinalgError>
+    where
+        T: Clone,
+    {
+        let Some(first) = tensors.first() else {
+            return Tensor::try_new(
+                Box::new([]),
+                ShapeDescriptor(Box::new([0])),
+            );
+        };
+        let shape = first.shape();
+        let mut data = Vec::with_capacity(tensors.len() * shape.hypervolume());
+        for tensor in tensors {
+            if tensor.shape() != shape {
+                return Err(LinalgError::ShapeMismatch {
+                    op: "stack",
+                    lhs: shape.into_owned(),
+                    rhs: tensor.shape().into_owned(),
+                });
+            }
+            data.extend(tensor.iter_logical().cloned());
+        }
+
+        let dims = [&[tensors.len()][..], &shape].concat();
+        Tensor::try_new(data.into(), ShapeDescriptor(dims.into()))
     }
 }
 
 // ======================= trait TensorAccess =======================
-pub trait TensorAccess<'a, T> {
+pub trait TensorAccess<T> {
     fn data(&self) -> &[T];
     fn strides(&self) -> &[usize];
 
@@ -41,10 +511,16 @@ pub trait TensorAccess<'a, T> {
     /// This means the a transform can technically be used on another tensor
     /// without needing a tensor to start with. Also meaning that you can
     /// do arbirary shaping and manipulation without a Tensor.
-    fn set_transform(&mut self, transform: &'a dyn Transform); 
+    ///
+    /// The tensor only holds a shared handle, so the same transform can be
+    /// set on as many tensors as needed and outlives whichever drops first.
+    fn set_transform(&mut self, transform: Arc<dyn Transform>);
+
+    /// drops the active transform, going back to the raw layout.
+    fn clear_transform(&mut self) -> Option<Arc<dyn Transform>>;
 }
 
-impl<'a, T> TensorAccess<'a, T> for Tensor<'a, T> {
+impl<T> TensorAccess<T> for Tensor<T> {
     fn data(&self) -> &[T] {
         &self.data
     }
@@ -54,22 +530,26 @@ impl<'a, T> TensorAccess<'a, T> for Tensor<'a, T> {
     }
 
     fn transform(&self) -> Option<&dyn Transform> {
-        self.transform
+        self.transform.as_deref()
     }
- 
-    fn set_transform(&mut self, transform: &'a dyn Transform) {
+
+    fn set_transform(&mut self, transform: Arc<dyn Transform>) {
         self.transform = Some(transform);
     }
+
+    fn clear_transform(&mut self) -> Option<Arc<dyn Transform>> {
+        self.transform.take()
+    }
 }
 
 // ======================= impl Shape =======================
-impl<T> Shape for Tensor<'_, T> {
+impl<T> Shape for Tensor<T> {
     fn rank(&self) -> usize {
-        self.shape.rank()
+        self.shape().rank()
     }
 
     fn shape(&self) -> Cow<ShapeDescriptor> {
-        if let Some(transform) = self.transform {
+        if let Some(transform) = &self.transform {
             transform.out_shape()
         } else {
             Cow::Borrowed(&self.shape)
@@ -82,7 +562,9 @@ impl<T> Shape for Tensor<'_, T> {
 }
 
 // ======================= impl Deref =======================
-impl<T> std::ops::Deref for Tensor<'_, T> {
+/// derefs to the raw buffer, which is only in logical order when
+/// [`Tensor::is_contiguous`] holds.
+impl<T> std::ops::Deref for Tensor<T> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
@@ -91,7 +573,7 @@ impl<T> std::ops::Deref for Tensor<'_, T> {
 }
 
 // ======================= impl DerefMut =======================
-impl<T> std::ops::DerefMut for Tensor<'_, T> {
+impl<T> std::ops::DerefMut for Tensor<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.data
     }
@@ -99,28 +581,32 @@ impl<T> std::ops::DerefMut for Tensor<'_, T> {
 
 
 
+// ======================= impl Add =======================
+impl<T> std::ops::Add for &Tensor<T>
+where
+    T: std::ops::Add<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Tensor<T>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        unwrap_or_panic(self.try_add(rhs))
+    }
+}
+
 // ======================= impl Index =======================
-impl<'a, T> Index<&[usize]> for Tensor<'a, T> {
+impl<T> Index<&[usize]> for Tensor<T> {
     type Output = T;
 
     fn index(&self, logical: &[usize]) -> &Self::Output {
-        let flat = match self.transform {
-            Some(t) => t.to_flat(logical),
-            None => compute_flat_index(logical, self.strides()),
-        };
-        &self.data[flat]
+        unwrap_or_panic(self.try_get(logical))
     }
 }
 
 // ======================= impl IndexMut =======================
-impl<'a, T> IndexMut<&[usize]> for Tensor<'a, T> {
+impl<T> IndexMut<&[usize]> for Tensor<T> {
     fn index_mut(&mut self, logical: &[usize]) -> &mut Self::Output {
-        // TODO remove dups
-        let flat = match self.transform {
-            Some(t) => t.to_flat(logical),
-            None => compute_flat_index(logical, self.strides()),
-        };
-        &mut self.data[flat]
+        unwrap_or_panic(self.try_get_mut(logical))
     }
 }
 
@@ -1,9 +1,11 @@
 // conrete_transform.rs
-use super::{
-    Transform, TransformError, compute_flat_index, matching_hypervolume,
+use super::{Transform, compute_flat_index, matching_hypervolume};
+use crate::{
+    error::LinalgError,
+    ndarr::tensor::TensorAccess,
+    shape::{Shape, ShapeDescriptor},
 };
-use crate::{ndarr::tensor::TensorAccess, shape::{Shape, ShapeDescriptor}};
-use std::{borrow::Cow, ops::Index};
+use std::{borrow::Cow, ops::Index, sync::Arc};
 
 // ======================= IdentityTransform =======================
 ///
@@ -26,16 +28,20 @@ impl Transform for IdentityTransform {
     fn out_strides(&self) -> Cow<[usize]> {
         Cow::Borrowed(&self.1)
     }
+
+    fn is_row_major(&self) -> bool {
+        *self.1 == *self.0.compute_strides()
+    }
 }
 
 // ======================= ChainedTransforms =======================
 /// .
-pub struct ChainedTransforms<'a> {
-    pub stages: Vec<&'a dyn Transform>,
+pub struct ChainedTransforms {
+    pub stages: Vec<Arc<dyn Transform>>,
 }
 
 // ======================= ChainedTransforms Transform =======================
-impl Transform for ChainedTransforms<'_> {
+impl Transform for ChainedTransforms {
     fn to_flat(&self, logical: &[usize]) -> usize {
         todo!()
     }
@@ -64,11 +70,8 @@ impl ReshapeTransform {
     pub fn new(
         src: &ShapeDescriptor,
         dst: ShapeDescriptor,
-    ) -> Result<Self, TransformError> {
-        assert!(
-            matching_hypervolume(&dst, src).is_ok(),
-            "Hypervolume Mismatch while reshaping"
-        );
+    ) -> Result<Self, LinalgError> {
+        matching_hypervolume(src, &dst)?;
         let out_strides = dst.compute_strides();
         Ok(Self {
             dst_shape: dst,
@@ -80,7 +83,7 @@ impl ReshapeTransform {
 // ======================= ReshapeTransform Transform =======================
 impl Transform for ReshapeTransform {
     fn to_flat(&self, logical: &[usize]) -> usize {
-        compute_flat_index(logical, &self.dst_shape)
+        compute_flat_index(logical, &self.out_strides)
     }
 
     fn out_shape(&self) -> Cow<ShapeDescriptor> {
@@ -90,4 +93,9 @@ impl Transform for ReshapeTransform {
     fn out_strides(&self) -> Cow<[usize]> {
         Cow::Borrowed(&self.out_strides)
     }
+
+    /// the strides are always the row-major ones of `dst_shape`.
+    fn is_row_major(&self) -> bool {
+        true
+    }
 }
@@ -18,7 +18,10 @@ pub mod concrete_transformers;
 /// // but in memory its still the same.
 /// tensor.reshape(StructureShape::<3>::from([1,2,3]));
 /// ```
-use crate::shape::{Shape, ShapeDescriptor};
+use crate::{
+    error::LinalgError,
+    shape::{Shape, ShapeDescriptor},
+};
 use std::borrow::Cow;
 
 // ======================= boxed_slice_from_fn_uninit =======================
@@ -75,30 +78,38 @@ pub fn compute_flat_index(logical: &[usize], strides: &[usize]) -> usize {
         .sum()
 }
 
+// ======================= compute_logical_index =======================
+/// the inverse of [`compute_flat_index`] for row-major `strides` of `shape`.
+pub fn compute_logical_index(
+    flat_index: usize,
+    strides: &[usize],
+    shape: &[usize],
+) -> Box<[usize]> {
+    slice_from_fn_uninit(strides.len(), |i| {
+        (flat_index / strides[i]) % shape[i]
+    })
+}
+
 // ======================= matching_hypervolume =======================
 /// .
 pub fn matching_hypervolume(
     first: &impl Shape,
     second: &impl Shape,
-) -> Result<(), TransformError> {
+) -> Result<(), LinalgError> {
     if first.hypervolume() == second.hypervolume() {
         Ok(())
     } else {
-        Err(TransformError::MisMatchHypervolume)
+        Err(LinalgError::HypervolumeMismatch {
+            src: first.shape().into_owned(),
+            dst: second.shape().into_owned(),
+        })
     }
 }
 
-// ======================= TransformError =======================
-/// .
-pub enum TransformError {
-    ReshapeError,
-    MisMatchHypervolume,
-    Error,
-}
-
 // ======================= Transform =======================
-/// .
-pub trait Transform {
+/// Tensors hold transforms behind an `Arc`, so they have to be shareable
+/// across threads for the tensor to be.
+pub trait Transform: Send + Sync {
     /// Maps a logical multi-dimensional index (e.g., [i, j, k]) to a flat index into the data buffer.
     fn to_flat(&self, logical: &[usize]) -> usize;
 
@@ -124,6 +135,13 @@ pub trait Transform {
 
     /// Returns the logical strides used for index computation.
     fn out_strides(&self) -> Cow<[usize]>;
+
+    /// whether `to_flat` is exactly the row-major index of `out_shape`,
+    /// i.e. the buffer already holds the logical elements in order. Only
+    /// claim this when the mapping itself guarantees it.
+    fn is_row_major(&self) -> bool {
+        false
+    }
 }
 
 // ======================= Transform =======================
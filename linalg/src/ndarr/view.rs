@@ -0,0 +1,234 @@
+use super::tensor::Tensor;
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    shape::{Shape, ShapeDescriptor},
+};
+use std::{
+    borrow::Cow,
+    ops::{Index, IndexMut},
+};
+
+// ======================= TensorView =======================
+/// A borrowed window into some of a tensor's elements, e.g. one row.
+///
+/// The view keeps references to the elements in its own logical order, so
+/// it stays valid for whatever [`super::transform::Transform`] the tensor
+/// had when it was taken.
+pub struct TensorView<'a, T> {
+    elems: Box<[&'a T]>,
+    shape: ShapeDescriptor,
+    strides: Box<[usize]>,
+}
+
+impl<'a, T> TensorView<'a, T> {
+    pub(crate) fn new(elems: Box<[&'a T]>, shape: ShapeDescriptor) -> Self {
+        debug_assert_eq!(elems.len(), shape.hypervolume());
+        let strides = shape.compute_strides();
+        Self {
+            elems,
+            shape,
+            strides,
+        }
+    }
+
+    pub fn try_get(&self, logical: &[usize]) -> Result<&'a T, LinalgError> {
+        let pos = checked_position(logical, &self.shape, &self.strides)?;
+        Ok(self.elems[pos])
+    }
+
+    /// the elements in logical order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a T> + '_ {
+        self.elems.iter().copied()
+    }
+
+    /// copies the view out into its own row-major tensor.
+    pub fn to_tensor(&self) -> Tensor<T>
+    where
+        T: Clone,
+    {
+        let data = self.elems.iter().map(|&e| e.clone()).collect();
+        Tensor::new(data, self.shape.clone())
+    }
+}
+
+// ======================= TensorViewMut =======================
+/// The mutable counterpart of [`TensorView`].
+pub struct TensorViewMut<'a, T> {
+    elems: Box<[&'a mut T]>,
+    shape: ShapeDescriptor,
+    strides: Box<[usize]>,
+}
+
+impl<'a, T> TensorViewMut<'a, T> {
+    pub(crate) fn new(elems: Box<[&'a mut T]>, shape: ShapeDescriptor) -> Self {
+        debug_assert_eq!(elems.len(), shape.hypervolume());
+        let strides = shape.compute_strides();
+        Self {
+            elems,
+            shape,
+            strides,
+        }
+    }
+
+    pub fn try_get(&self, logical: &[usize]) -> Result<&T, LinalgError> {
+        let pos = checked_position(logical, &self.shape, &self.strides)?;
+        Ok(self.elems[pos])
+    }
+
+    pub fn try_get_mut(
+        &mut self,
+        logical: &[usize],
+    ) -> Result<&mut T, LinalgError> {
+        let pos = checked_position(logical, &self.shape, &self.strides)?;
+        Ok(self.elems[pos])
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> + '_ {
+        self.elems.iter().map(|e| &**e)
+    }
+
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = &mut T> + '_ {
+        self.elems.iter_mut().map(|e| &mut **e)
+    }
+
+    /// overwrites every element with `value`.
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.iter_mut().for_each(|e| *e = value.clone());
+    }
+}
+
+// ======================= checked_position =======================
+/// position of `logical` inside a row-major element list of `shape`.
+fn checked_position(
+    logical: &[usize],
+    shape: &ShapeDescriptor,
+    strides: &[usize],
+) -> Result<usize, LinalgError> {
+    let in_bounds = logical.len() == shape.len()
+        && logical.iter().zip(shape.iter()).all(|(i, axis)| i < axis);
+    if !in_bounds {
+        return Err(LinalgError::IndexOutOfBounds {
+            index: logical.into(),
+            shape: shape.clone(),
+        });
+    }
+    Ok(logical.iter().zip(strides).map(|(i, s)| i * s).sum())
+}
+
+// ======================= without_axis =======================
+/// `shape` with `axis` left out.
+pub(crate) fn without_axis(
+    shape: &ShapeDescriptor,
+    axis: usize,
+) -> ShapeDescriptor {
+    ShapeDescriptor(
+        shape
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != axis)
+            .map(|(_, &d)| d)
+            .collect(),
+    )
+}
+
+// ======================= AxisSplit =======================
+/// The slices of a row-major `shape` along one axis, each one shaped like
+/// [`without_axis`]. Only positions are computed, so callers can build one
+/// slice at a time.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AxisSplit {
+    /// the length of the axis, i.e. the number of slices.
+    pub(crate) len: usize,
+    /// the elements per step along the axis.
+    inner: usize,
+    /// the elements per slice.
+    volume: usize,
+}
+
+impl AxisSplit {
+    pub(crate) fn new(
+        shape: &ShapeDescriptor,
+        axis: usize,
+    ) -> Result<Self, LinalgError> {
+        if axis >= shape.len() {
+            return Err(LinalgError::AxisOutOfBounds {
+                axis,
+                rank: shape.len(),
+            });
+        }
+        Ok(Self {
+            len: shape[axis],
+            inner: shape[axis + 1..].iter().product(),
+            volume: without_axis(shape, axis).hypervolume(),
+        })
+    }
+
+    /// the row-major positions within `shape` of slice `index`, in the
+    /// slice's own row-major order.
+    pub(crate) fn positions(
+        self,
+        index: usize,
+    ) -> impl ExactSizeIterator<Item = usize> {
+        let Self { len, inner, volume } = self;
+        // `inner` is only 0 when the slice is empty too
+        (0..volume).map(move |p| (p / inner * len + index) * inner + p % inner)
+    }
+}
+
+// ======================= TensorView Shape =======================
+impl<T> Shape for TensorView<'_, T> {
+    fn rank(&self) -> usize {
+        self.shape.rank()
+    }
+
+    fn shape(&self) -> Cow<'_, ShapeDescriptor> {
+        Cow::Borrowed(&self.shape)
+    }
+
+    fn hypervolume(&self) -> usize {
+        self.elems.len()
+    }
+}
+
+// ======================= TensorViewMut Shape =======================
+impl<T> Shape for TensorViewMut<'_, T> {
+    fn rank(&self) -> usize {
+        self.shape.rank()
+    }
+
+    fn shape(&self) -> Cow<'_, ShapeDescriptor> {
+        Cow::Borrowed(&self.shape)
+    }
+
+    fn hypervolume(&self) -> usize {
+        self.elems.len()
+    }
+}
+
+// ======================= TensorView Index =======================
+impl<T> Index<&[usize]> for TensorView<'_, T> {
+    type Output = T;
+
+    fn index(&self, logical: &[usize]) -> &Self::Output {
+        unwrap_or_panic(self.try_get(logical))
+    }
+}
+
+// ======================= TensorViewMut Index =======================
+impl<T> Index<&[usize]> for TensorViewMut<'_, T> {
+    type Output = T;
+
+    fn index(&self, logical: &[usize]) -> &Self::Output {
+        unwrap_or_panic(self.try_get(logical))
+    }
+}
+
+// ======================= TensorViewMut IndexMut =======================
+impl<T> IndexMut<&[usize]> for TensorViewMut<'_, T> {
+    fn index_mut(&mut self, logical: &[usize]) -> &mut Self::Output {
+        unwrap_or_panic(self.try_get_mut(logical))
+    }
+}
@@ -0,0 +1,517 @@
+//! Convolutions with stride, padding, dilation and groups, lowered to
+//! matrix products by im2col.
+//!
+//! For every sample and group the input patches are unrolled into a
+//! `[in_channels / groups * kernel, out_positions]` matrix, so the forward
+//! pass is `weight x cols` and the backward pass is `grad x colsᵀ` for the
+//! weight and `weightᵀ x grad` scattered back through the same patches for
+//! the input. All three go through [`CpuDevice::matmul`].
+
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    ndarr::{device::cpu::CpuDevice, logical_data, tensor::Tensor, unravel},
+    number::Real,
+    rng::Rng,
+    shape::{Shape, ShapeDescriptor},
+};
+
+fn invalid(reason: impl Into<String>) -> LinalgError {
+    LinalgError::InvalidLayer {
+        layer: "conv",
+        reason: reason.into(),
+    }
+}
+
+// ======================= ConvConfig =======================
+/// The hyperparameters of a convolution over `D` spatial axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConvConfig<const D: usize> {
+    in_channels: usize,
+    out_channels: usize,
+    kernel: [usize; D],
+    stride: [usize; D],
+    padding: [usize; D],
+    dilation: [usize; D],
+    groups: usize,
+    bias: bool,
+}
+
+impl<const D: usize> ConvConfig<D> {
+    /// stride and dilation of 1, no padding, a single group and a bias.
+    pub fn new(
+        in_channels: usize,
+        out_channels: usize,
+        kernel: [usize; D],
+    ) -> Self {
+        Self {
+            in_channels,
+            out_channels,
+            kernel,
+            stride: [1; D],
+            padding: [0; D],
+            dilation: [1; D],
+            groups: 1,
+            bias: true,
+        }
+    }
+
+    pub fn with_stride(mut self, stride: [usize; D]) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// zeros added to both sides of every spatial axis.
+    pub fn with_padding(mut self, padding: [usize; D]) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// the spacing between kernel taps, 1 being a dense kernel.
+    pub fn with_dilation(mut self, dilation: [usize; D]) -> Self {
+        self.dilation = dilation;
+        self
+    }
+
+    /// splits the channels into `groups` independent convolutions, both
+    /// channel counts have to be divisible by it.
+    pub fn with_groups(mut self, groups: usize) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    pub fn with_bias(mut self, bias: bool) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    #[inline]
+    pub fn in_channels(&self) -> usize {
+        self.in_channels
+    }
+
+    #[inline]
+    pub fn out_channels(&self) -> usize {
+        self.out_channels
+    }
+
+    #[inline]
+    pub fn kernel(&self) -> [usize; D] {
+        self.kernel
+    }
+
+    #[inline]
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    /// `[out_channels, in_channels / groups, kernel..]`
+    pub fn weight_shape(&self) -> ShapeDescriptor {
+        let head = [self.out_channels, self.in_channels / self.groups.max(1)];
+        ShapeDescriptor(head.iter().chain(&self.kernel).copied().collect())
+    }
+
+    fn validate(&self) -> Result<(), LinalgError> {
+        if self.groups == 0
+            || !self.in_channels.is_multiple_of(self.groups)
+            || !self.out_channels.is_multiple_of(self.groups)
+        {
+            return Err(invalid(format!(
+                "{} in and {} out channels can't be split in {} groups",
+                self.in_channels, self.out_channels, self.groups
+            )));
+        }
+        if self.in_channels == 0 || self.out_channels == 0 {
+            return Err(invalid("no channels"));
+        }
+        if self.kernel.contains(&0)
+            || self.stride.contains(&0)
+            || self.dilation.contains(&0)
+        {
+            return Err(invalid(
+                "kernel, stride and dilation have to be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    /// the spatial size of the output for an input of spatial size `input`.
+    pub fn output_size(
+        &self,
+        input: [usize; D],
+    ) -> Result<[usize; D], LinalgError> {
+        // padding alone could fit the kernel, but there'd be nothing to
+        // convolve
+        if input.contains(&0) {
+            return Err(invalid("empty spatial axis"));
+        }
+        let mut out = [0; D];
+        for d in 0..D {
+            let padded = input[d] + 2 * self.padding[d];
+            let span = self.dilation[d] * (self.kernel[d] - 1) + 1;
+            if padded < span {
+                return Err(invalid(format!(
+                    "kernel spans {span} but axis {d} is only {padded} wide \
+                     padded"
+                )));
+            }
+            out[d] = (padded - span) / self.stride[d] + 1;
+        }
+        Ok(out)
+    }
+}
+
+// ======================= Conv =======================
+/// A convolution over `[batch, channels, spatial..]` tensors.
+pub struct Conv<T, const D: usize> {
+    config: ConvConfig<D>,
+    /// `[out_channels, in_channels / groups, kernel..]`
+    pub weight: Tensor<T>,
+    /// `[out_channels]`, present when the config has a bias.
+    pub bias: Option<Tensor<T>>,
+}
+
+/// over `[batch, channels, length]`
+pub type Conv1d<T> = Conv<T, 1>;
+/// over `[batch, channels, height, width]`
+pub type Conv2d<T> = Conv<T, 2>;
+
+/// The gradients of a loss with respect to a convolution's input and
+/// parameters, shaped like them.
+pub struct ConvGrads<T> {
+    pub input: Tensor<T>,
+    pub weight: Tensor<T>,
+    pub bias: Option<Tensor<T>>,
+}
+
+impl<T: Real, const D: usize> Conv<T, D> {
+    pub fn new(config: ConvConfig<D>, rng: &mut Rng) -> Self {
+        unwrap_or_panic(Self::try_new(config, rng))
+    }
+
+    /// Draws the weight and bias from `U(-1/√fan_in, 1/√fan_in)` where
+    /// `fan_in = in_channels / groups * kernel`.
+    pub fn try_new(
+        config: ConvConfig<D>,
+        rng: &mut Rng,
+    ) -> Result<Self, LinalgError> {
+        config.validate()?;
+        let shape = config.weight_shape();
+        let fan_in: usize = shape[1..].iter().product();
+        let bound = 1.0 / (fan_in as f64).sqrt();
+        let mut uniform = |n: usize| -> Box<[T]> {
+            (0..n)
+                .map(|_| T::from_f64((2.0 * rng.next_f64() - 1.0) * bound))
+                .collect()
+        };
+        let weight = Tensor::try_new(uniform(shape.hypervolume()), shape)?;
+        let bias = config.bias.then(|| {
+            let data = uniform(config.out_channels);
+            Tensor::new(data, ShapeDescriptor(Box::new([config.out_channels])))
+        });
+        Ok(Self {
+            config,
+            weight,
+            bias,
+        })
+    }
+
+    /// Uses the given parameters, the config's bias flag follows `bias`.
+    pub fn try_from_weights(
+        config: ConvConfig<D>,
+        weight: Tensor<T>,
+        bias: Option<Tensor<T>>,
+    ) -> Result<Self, LinalgError> {
+        let config = config.with_bias(bias.is_some());
+        config.validate()?;
+        let conv = Self {
+            config,
+            weight,
+            bias,
+        };
+        conv.check_params()?;
+        Ok(conv)
+    }
+
+    #[inline]
+    pub fn config(&self) -> &ConvConfig<D> {
+        &self.config
+    }
+
+    fn check_params(&self) -> Result<(), LinalgError> {
+        let expected = self.config.weight_shape();
+        if *self.weight.shape() != expected {
+            return Err(LinalgError::ShapeMismatch {
+                op: "conv weight",
+                lhs: expected,
+                rhs: self.weight.shape().into_owned(),
+            });
+        }
+        if let Some(bias) = &self.bias
+            && bias.shape()[..] != [self.config.out_channels]
+        {
+            return Err(LinalgError::ShapeMismatch {
+                op: "conv bias",
+                lhs: ShapeDescriptor(Box::new([self.config.out_channels])),
+                rhs: bias.shape().into_owned(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks `input` against the config and maps out the patches.
+    fn plan(&self, input: &[usize]) -> Result<Im2Col<D>, LinalgError> {
+        self.check_params()?;
+        if input.len() != D + 2 {
+            return Err(LinalgError::RankMismatch {
+                op: "conv",
+                expected: D + 2,
+                found: input.len(),
+            });
+        }
+        if input[1] != self.config.in_channels {
+            return Err(LinalgError::ShapeMismatch {
+                op: "conv",
+                lhs: ShapeDescriptor(input.into()),
+                rhs: self.weight.shape().into_owned(),
+            });
+        }
+        let in_size: [usize; D] = input[2..].try_into().unwrap();
+        let out_size = self.config.output_size(in_size)?;
+        Ok(Im2Col::new(&self.config, input[0], in_size, out_size))
+    }
+
+    pub fn forward(&self, device: &CpuDevice, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(device, input))
+    }
+
+    /// `[batch, in_channels, in..]` to `[batch, out_channels, out..]`
+    pub fn try_forward(
+        &self,
+        device: &CpuDevice,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let plan = self.plan(&input.shape())?;
+        let x = logical_data(input);
+        let w = logical_data(&self.weight);
+        let Dims { ig, og, n, l, s } = plan.dims;
+        let (cin, cout) = (self.config.in_channels, self.config.out_channels);
+
+        let mut out = vec![T::ZERO; plan.batch * cout * l];
+        let mut cols = vec![T::ZERO; n * l];
+        for b in 0..plan.batch {
+            for g in 0..self.config.groups {
+                plan.im2col(&x[(b * cin + g * ig) * s..][..ig * s], &mut cols);
+                let w_g = &w[g * og * n..][..og * n];
+                let out_bg = &mut out[(b * cout + g * og) * l..][..og * l];
+                device.matmul(w_g, &cols, out_bg, (og, n, l));
+            }
+        }
+        if let Some(bias) = &self.bias {
+            let bias = logical_data(bias);
+            for (i, plane) in out.chunks_mut(l).enumerate() {
+                let b = bias[i % cout];
+                plane.iter_mut().for_each(|y| *y = *y + b);
+            }
+        }
+        Tensor::try_new(out.into(), plan.output_shape(cout))
+    }
+
+    pub fn backward(
+        &self,
+        device: &CpuDevice,
+        input: &Tensor<T>,
+        grad_output: &Tensor<T>,
+    ) -> ConvGrads<T> {
+        unwrap_or_panic(self.try_backward(device, input, grad_output))
+    }
+
+    /// The gradients given the `input` of the forward pass and the gradient
+    /// of the loss with respect to its output.
+    pub fn try_backward(
+        &self,
+        device: &CpuDevice,
+        input: &Tensor<T>,
+        grad_output: &Tensor<T>,
+    ) -> Result<ConvGrads<T>, LinalgError> {
+        let plan = self.plan(&input.shape())?;
+        let (cin, cout) = (self.config.in_channels, self.config.out_channels);
+        let out_shape = plan.output_shape(cout);
+        if *grad_output.shape() != out_shape {
+            return Err(LinalgError::ShapeMismatch {
+                op: "conv backward",
+                lhs: out_shape,
+                rhs: grad_output.shape().into_owned(),
+            });
+        }
+        let x = logical_data(input);
+        let dy = logical_data(grad_output);
+        let Dims { ig, og, n, l, s } = plan.dims;
+        let groups = self.config.groups;
+
+        // weightᵀ of every group, [n, og] each
+        let w = logical_data(&self.weight);
+        let mut w_t = vec![T::ZERO; w.len()];
+        for g in 0..groups {
+            let range = g * og * n..(g + 1) * og * n;
+            transpose(&w[range.clone()], og, n, &mut w_t[range]);
+        }
+
+        let mut dx = vec![T::ZERO; x.len()];
+        let mut dw = vec![T::ZERO; w.len()];
+        let mut cols = vec![T::ZERO; n * l];
+        let mut cols_t = vec![T::ZERO; n * l];
+        let mut dcols = vec![T::ZERO; n * l];
+        let mut dw_bg = vec![T::ZERO; og * n];
+        for b in 0..plan.batch {
+            for g in 0..groups {
+                let x_range =
+                    (b * cin + g * ig) * s..(b * cin + (g + 1) * ig) * s;
+                let dy_bg = &dy[(b * cout + g * og) * l..][..og * l];
+
+                plan.im2col(&x[x_range.clone()], &mut cols);
+                transpose(&cols, n, l, &mut cols_t);
+                device.matmul(dy_bg, &cols_t, &mut dw_bg, (og, l, n));
+                device.axpy(T::ONE, &dw_bg, &mut dw[g * og * n..][..og * n]);
+
+                let w_t_g = &w_t[g * og * n..][..og * n];
+                device.matmul(w_t_g, dy_bg, &mut dcols, (n, og, l));
+                plan.col2im(&dcols, &mut dx[x_range]);
+            }
+        }
+
+        let bias = self.bias.as_ref().map(|_| {
+            let mut db = vec![T::ZERO; cout];
+            for (i, plane) in dy.chunks(l).enumerate() {
+                db[i % cout] = db[i % cout] + plane.iter().copied().sum();
+            }
+            Tensor::new(db.into(), ShapeDescriptor(Box::new([cout])))
+        });
+        Ok(ConvGrads {
+            input: Tensor::try_new(dx.into(), input.shape().into_owned())?,
+            weight: Tensor::try_new(
+                dw.into(),
+                self.weight.shape().into_owned(),
+            )?,
+            bias,
+        })
+    }
+}
+
+/// `dst[j, i] = src[i, j]` for a row-major `[rows, cols]` source.
+fn transpose<T: Copy>(src: &[T], rows: usize, cols: usize, dst: &mut [T]) {
+    for i in 0..rows {
+        for j in 0..cols {
+            dst[j * rows + i] = src[i * cols + j];
+        }
+    }
+}
+
+// ======================= Im2Col =======================
+/// the matrix sizes of one sample and group.
+#[derive(Clone, Copy)]
+struct Dims {
+    /// input channels per group.
+    ig: usize,
+    /// output channels per group.
+    og: usize,
+    /// rows of the patch matrix, `ig * kernel`.
+    n: usize,
+    /// output positions.
+    l: usize,
+    /// input positions.
+    s: usize,
+}
+
+/// Where every kernel tap reads the input, shared by all samples and
+/// channels of one input shape.
+struct Im2Col<const D: usize> {
+    batch: usize,
+    out_size: [usize; D],
+    dims: Dims,
+    /// `taps[k * l + p]` is the input position kernel tap `k` reads for
+    /// output position `p`, `None` when it falls in the padding.
+    taps: Vec<Option<usize>>,
+}
+
+impl<const D: usize> Im2Col<D> {
+    fn new(
+        config: &ConvConfig<D>,
+        batch: usize,
+        in_size: [usize; D],
+        out_size: [usize; D],
+    ) -> Self {
+        let taps_per_channel: usize = config.kernel.iter().product();
+        let l: usize = out_size.iter().product();
+        let s: usize = in_size.iter().product();
+        let mut taps = Vec::with_capacity(taps_per_channel * l);
+        let (mut tap, mut pos) = ([0; D], [0; D]);
+        for k in 0..taps_per_channel {
+            unravel(k, &config.kernel, &mut tap);
+            for p in 0..l {
+                unravel(p, &out_size, &mut pos);
+                let mut offset = 0;
+                let mut inside = true;
+                for d in 0..D {
+                    let at = (pos[d] * config.stride[d]
+                        + tap[d] * config.dilation[d])
+                        .checked_sub(config.padding[d])
+                        .filter(|&at| at < in_size[d]);
+                    match at {
+                        Some(at) => offset = offset * in_size[d] + at,
+                        None => inside = false,
+                    }
+                }
+                taps.push(inside.then_some(offset));
+            }
+        }
+
+        let ig = config.in_channels / config.groups;
+        Self {
+            batch,
+            out_size,
+            dims: Dims {
+                ig,
+                og: config.out_channels / config.groups,
+                n: ig * taps_per_channel,
+                l,
+                s,
+            },
+            taps,
+        }
+    }
+
+    fn output_shape(&self, channels: usize) -> ShapeDescriptor {
+        let head = [self.batch, channels];
+        ShapeDescriptor(head.iter().chain(&self.out_size).copied().collect())
+    }
+
+    /// unrolls `x`, `[ig, s]`, into `cols`, `[n, l]`.
+    fn im2col<T: Real>(&self, x: &[T], cols: &mut [T]) {
+        let Dims { l, s, .. } = self.dims;
+        let mut rows = cols.chunks_mut(l);
+        for channel in x.chunks(s) {
+            for taps in self.taps.chunks(l) {
+                let row = rows.next().unwrap();
+                for (c, tap) in row.iter_mut().zip(taps) {
+                    *c = tap.map_or(T::ZERO, |t| channel[t]);
+                }
+            }
+        }
+    }
+
+    /// the adjoint of `im2col`, adding `cols` back onto `dx`.
+    fn col2im<T: Real>(&self, cols: &[T], dx: &mut [T]) {
+        let Dims { l, s, .. } = self.dims;
+        let mut rows = cols.chunks(l);
+        for channel in dx.chunks_mut(s) {
+            for taps in self.taps.chunks(l) {
+                let row = rows.next().unwrap();
+                for (&c, tap) in row.iter().zip(taps) {
+                    if let Some(t) = *tap {
+                        channel[t] = channel[t] + c;
+                    }
+                }
+            }
+        }
+    }
+}
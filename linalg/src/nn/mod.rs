@@ -0,0 +1,9 @@
+//! Layers for convolutional networks over `[batch, channels, spatial..]`
+//! tensors, each with its own backward pass.
+//!
+//! Layers keep no autograd graph, `backward` takes whatever the forward pass
+//! saw and returns the gradients with respect to the input and parameters.
+//! Max pools are the exception, they cache their argmax in `forward`.
+
+pub mod conv;
+pub mod pool;
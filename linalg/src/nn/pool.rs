@@ -0,0 +1,658 @@
+//! Max, average, adaptive and global pooling over the spatial axes of
+//! `[batch, channels, spatial..]` tensors.
+//!
+//! Every output element reduces a window of input positions. The windows
+//! only depend on the spatial sizes, so they're mapped out once per call
+//! and applied to every `(batch, channel)` plane. Max pooling keeps the flat
+//! input index of every maximum from its forward pass, the backward pass
+//! routes each gradient straight back to it.
+
+use crate::{
+    error::{LinalgError, unwrap_or_panic},
+    ndarr::{logical_data, tensor::Tensor, unravel},
+    number::Real,
+    shape::{Shape, ShapeDescriptor},
+};
+
+fn invalid(layer: &'static str, reason: impl Into<String>) -> LinalgError {
+    LinalgError::InvalidLayer {
+        layer,
+        reason: reason.into(),
+    }
+}
+
+/// the spatial size of a `[batch, channels, spatial..]` shape.
+fn spatial_size<const D: usize>(
+    layer: &'static str,
+    shape: &[usize],
+) -> Result<[usize; D], LinalgError> {
+    if shape.len() != D + 2 {
+        return Err(LinalgError::RankMismatch {
+            op: layer,
+            expected: D + 2,
+            found: shape.len(),
+        });
+    }
+    Ok(shape[2..].try_into().unwrap())
+}
+
+fn output_shape(input: &[usize], spatial: &[usize]) -> ShapeDescriptor {
+    ShapeDescriptor(input[..2].iter().chain(spatial).copied().collect())
+}
+
+// ======================= PoolConfig =======================
+/// The window of a max or average pool over `D` spatial axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig<const D: usize> {
+    kernel: [usize; D],
+    stride: [usize; D],
+    padding: [usize; D],
+}
+
+impl<const D: usize> PoolConfig<D> {
+    /// non-overlapping windows, i.e. the stride is the kernel.
+    pub fn new(kernel: [usize; D]) -> Self {
+        Self {
+            kernel,
+            stride: kernel,
+            padding: [0; D],
+        }
+    }
+
+    pub fn with_stride(mut self, stride: [usize; D]) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// implicit padding on both sides, at most half the kernel.
+    pub fn with_padding(mut self, padding: [usize; D]) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    #[inline]
+    pub fn kernel(&self) -> [usize; D] {
+        self.kernel
+    }
+
+    /// the spatial size of the output for an input of spatial size `input`.
+    pub fn output_size(
+        &self,
+        input: [usize; D],
+    ) -> Result<[usize; D], LinalgError> {
+        if self.kernel.contains(&0) || self.stride.contains(&0) {
+            return Err(invalid(
+                "pool",
+                "kernel and stride have to be positive",
+            ));
+        }
+        if input.contains(&0) {
+            return Err(invalid("pool", "empty spatial axis"));
+        }
+        let mut out = [0; D];
+        for d in 0..D {
+            if 2 * self.padding[d] > self.kernel[d] {
+                return Err(invalid(
+                    "pool",
+                    "padding can be at most half the kernel",
+                ));
+            }
+            let padded = input[d] + 2 * self.padding[d];
+            if padded < self.kernel[d] {
+                return Err(invalid(
+                    "pool",
+                    format!(
+                        "kernel of {} doesn't fit axis {d} of {padded} padded",
+                        self.kernel[d]
+                    ),
+                ));
+            }
+            out[d] = (padded - self.kernel[d]) / self.stride[d] + 1;
+        }
+        Ok(out)
+    }
+
+    fn windows(&self, in_size: [usize; D]) -> Result<Windows, LinalgError> {
+        let out_size = self.output_size(in_size)?;
+        let taps: usize = self.kernel.iter().product();
+        let mut windows = Windows::new(&in_size, &out_size);
+        let (mut pos, mut tap) = ([0; D], [0; D]);
+        for p in 0..windows.out_len {
+            unravel(p, &out_size, &mut pos);
+            for k in 0..taps {
+                unravel(k, &self.kernel, &mut tap);
+                let mut offset = 0;
+                let mut inside = true;
+                for d in 0..D {
+                    let at = (pos[d] * self.stride[d] + tap[d])
+                        .checked_sub(self.padding[d])
+                        .filter(|&at| at < in_size[d]);
+                    match at {
+                        Some(at) => offset = offset * in_size[d] + at,
+                        None => inside = false,
+                    }
+                }
+                if inside {
+                    windows.positions.push(offset);
+                }
+            }
+            windows.close(taps);
+        }
+        Ok(windows)
+    }
+}
+
+/// Splits every axis of `in_size` into `out_size` bins, bin `i` covering
+/// `floor(i * in / out)..ceil((i + 1) * in / out)`.
+fn adaptive_windows<const D: usize>(
+    in_size: [usize; D],
+    out_size: [usize; D],
+) -> Result<Windows, LinalgError> {
+    if in_size.contains(&0) || out_size.contains(&0) {
+        return Err(invalid(
+            "adaptive pool",
+            "input and output sizes have to be positive",
+        ));
+    }
+    let mut windows = Windows::new(&in_size, &out_size);
+    let (mut pos, mut tap) = ([0; D], [0; D]);
+    for p in 0..windows.out_len {
+        unravel(p, &out_size, &mut pos);
+        let mut start = [0; D];
+        let mut len = [0; D];
+        for d in 0..D {
+            let (n, m) = (in_size[d], out_size[d]);
+            start[d] = pos[d] * n / m;
+            len[d] = ((pos[d] + 1) * n).div_ceil(m) - start[d];
+        }
+        let count: usize = len.iter().product();
+        for k in 0..count {
+            unravel(k, &len, &mut tap);
+            let offset = (0..D)
+                .fold(0, |offset, d| offset * in_size[d] + start[d] + tap[d]);
+            windows.positions.push(offset);
+        }
+        windows.close(count);
+    }
+    Ok(windows)
+}
+
+// ======================= Windows =======================
+/// The input positions every output position reduces, within one plane.
+struct Windows {
+    in_len: usize,
+    out_len: usize,
+    /// window `p` is `positions[starts[p]..starts[p + 1]]`.
+    starts: Vec<usize>,
+    positions: Vec<usize>,
+    /// the window sizes counting the padding.
+    padded: Vec<usize>,
+}
+
+impl Windows {
+    fn new(in_size: &[usize], out_size: &[usize]) -> Self {
+        let out_len = out_size.iter().product();
+        let mut starts = Vec::with_capacity(out_len + 1);
+        starts.push(0);
+        Self {
+            in_len: in_size.iter().product(),
+            out_len,
+            starts,
+            positions: Vec::new(),
+            padded: Vec::with_capacity(out_len),
+        }
+    }
+
+    /// ends the window being pushed.
+    fn close(&mut self, padded: usize) {
+        self.starts.push(self.positions.len());
+        self.padded.push(padded);
+    }
+
+    fn get(&self, p: usize) -> &[usize] {
+        &self.positions[self.starts[p]..self.starts[p + 1]]
+    }
+
+    /// the maximum of every window and the flat index it was read from.
+    fn max<T: Real>(&self, x: &[T]) -> (Vec<T>, Vec<usize>) {
+        let planes = x.len() / self.in_len;
+        let mut out = Vec::with_capacity(planes * self.out_len);
+        let mut argmax = Vec::with_capacity(planes * self.out_len);
+        for plane in 0..planes {
+            let base = plane * self.in_len;
+            for p in 0..self.out_len {
+                let window = self.get(p);
+                let mut best = base + window[0];
+                for &at in &window[1..] {
+                    // NaNs win so they propagate like they would in a sum
+                    let y = x[base + at];
+                    if y > x[best] || y.partial_cmp(&y).is_none() {
+                        best = base + at;
+                    }
+                }
+                out.push(x[best]);
+                argmax.push(best);
+            }
+        }
+        (out, argmax)
+    }
+
+    /// the mean of every window, over its padded size if `include_pad`.
+    fn mean<T: Real>(&self, x: &[T], include_pad: bool) -> Vec<T> {
+        let planes = x.len() / self.in_len;
+        let mut out = Vec::with_capacity(planes * self.out_len);
+        for plane in x.chunks(self.in_len).take(planes) {
+            for p in 0..self.out_len {
+                let window = self.get(p);
+                let sum: T = window.iter().map(|&at| plane[at]).sum();
+                out.push(sum / self.divisor(p, include_pad));
+            }
+        }
+        out
+    }
+
+    /// the adjoint of `mean`.
+    fn mean_backward<T: Real>(&self, dy: &[T], include_pad: bool) -> Vec<T> {
+        let planes = dy.len() / self.out_len;
+        let mut dx = vec![T::ZERO; planes * self.in_len];
+        for (plane, dx) in dx.chunks_mut(self.in_len).enumerate() {
+            for p in 0..self.out_len {
+                let g =
+                    dy[plane * self.out_len + p] / self.divisor(p, include_pad);
+                for &at in self.get(p) {
+                    dx[at] = dx[at] + g;
+                }
+            }
+        }
+        dx
+    }
+
+    fn divisor<T: Real>(&self, p: usize, include_pad: bool) -> T {
+        let count = if include_pad {
+            self.padded[p]
+        } else {
+            self.starts[p + 1] - self.starts[p]
+        };
+        T::from_usize(count)
+    }
+}
+
+// ======================= Argmax =======================
+/// What a max pool remembers of its last forward pass.
+#[derive(Debug, Clone)]
+struct Argmax {
+    input: ShapeDescriptor,
+    output: ShapeDescriptor,
+    /// the flat input index every output was taken from.
+    indices: Vec<usize>,
+}
+
+impl Argmax {
+    fn backward<T: Real>(
+        cache: Option<&Self>,
+        layer: &'static str,
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let cache = cache
+            .ok_or_else(|| invalid(layer, "backward before any forward"))?;
+        if *grad_output.shape() != cache.output {
+            return Err(LinalgError::ShapeMismatch {
+                op: layer,
+                lhs: cache.output.clone(),
+                rhs: grad_output.shape().into_owned(),
+            });
+        }
+        let mut dx = vec![T::ZERO; cache.input.hypervolume()];
+        for (&at, &g) in cache.indices.iter().zip(grad_output.iter_logical()) {
+            dx[at] = dx[at] + g;
+        }
+        Tensor::try_new(dx.into(), cache.input.clone())
+    }
+}
+
+/// max pools `input` over `windows`, remembering where the maxima were.
+fn max_forward<T: Real>(
+    windows: &Windows,
+    input: &Tensor<T>,
+    out_size: &[usize],
+) -> Result<(Tensor<T>, Argmax), LinalgError> {
+    let x = logical_data(input);
+    let (out, indices) = windows.max(&x);
+    let output = output_shape(&input.shape(), out_size);
+    let cache = Argmax {
+        input: input.shape().into_owned(),
+        output: output.clone(),
+        indices,
+    };
+    Ok((Tensor::try_new(out.into(), output)?, cache))
+}
+
+// ======================= MaxPool =======================
+/// Max pooling over `D` spatial axes.
+#[derive(Debug, Clone)]
+pub struct MaxPool<const D: usize> {
+    config: PoolConfig<D>,
+    cache: Option<Argmax>,
+}
+
+/// over `[batch, channels, length]`
+pub type MaxPool1d = MaxPool<1>;
+/// over `[batch, channels, height, width]`
+pub type MaxPool2d = MaxPool<2>;
+
+impl<const D: usize> MaxPool<D> {
+    pub fn new(config: PoolConfig<D>) -> Self {
+        Self {
+            config,
+            cache: None,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &PoolConfig<D> {
+        &self.config
+    }
+
+    /// the flat input index of every output of the last forward pass.
+    pub fn argmax(&self) -> Option<&[usize]> {
+        self.cache.as_ref().map(|c| c.indices.as_slice())
+    }
+
+    pub fn forward<T: Real>(&mut self, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(input))
+    }
+
+    /// Pools `input` and caches the argmax for [`MaxPool::backward`].
+    pub fn try_forward<T: Real>(
+        &mut self,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let in_size = spatial_size::<D>("max pool", &input.shape())?;
+        let out_size = self.config.output_size(in_size)?;
+        let windows = self.config.windows(in_size)?;
+        let (output, cache) = max_forward(&windows, input, &out_size)?;
+        self.cache = Some(cache);
+        Ok(output)
+    }
+
+    pub fn backward<T: Real>(&self, grad_output: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_backward(grad_output))
+    }
+
+    /// Routes every output gradient to the input it was the maximum of in
+    /// the last forward pass.
+    pub fn try_backward<T: Real>(
+        &self,
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        Argmax::backward(self.cache.as_ref(), "max pool", grad_output)
+    }
+}
+
+// ======================= AvgPool =======================
+/// Average pooling over `D` spatial axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AvgPool<const D: usize> {
+    config: PoolConfig<D>,
+    include_pad: bool,
+}
+
+/// over `[batch, channels, length]`
+pub type AvgPool1d = AvgPool<1>;
+/// over `[batch, channels, height, width]`
+pub type AvgPool2d = AvgPool<2>;
+
+impl<const D: usize> AvgPool<D> {
+    /// windows overlapping the padding are divided by the full kernel size.
+    pub fn new(config: PoolConfig<D>) -> Self {
+        Self {
+            config,
+            include_pad: true,
+        }
+    }
+
+    /// whether the padding counts towards the divisor of each window.
+    pub fn with_count_include_pad(mut self, include_pad: bool) -> Self {
+        self.include_pad = include_pad;
+        self
+    }
+
+    #[inline]
+    pub fn config(&self) -> &PoolConfig<D> {
+        &self.config
+    }
+
+    pub fn forward<T: Real>(&self, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(input))
+    }
+
+    pub fn try_forward<T: Real>(
+        &self,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let shape = input.shape();
+        let in_size = spatial_size::<D>("avg pool", &shape)?;
+        let out_size = self.config.output_size(in_size)?;
+        let windows = self.config.windows(in_size)?;
+        let x = logical_data(input);
+        let out = windows.mean(&x, self.include_pad);
+        Tensor::try_new(out.into(), output_shape(&shape, &out_size))
+    }
+
+    pub fn backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Tensor<T> {
+        unwrap_or_panic(self.try_backward(input_shape, grad_output))
+    }
+
+    /// Spreads every output gradient evenly over its window of an input of
+    /// shape `input_shape`.
+    pub fn try_backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let in_size = spatial_size::<D>("avg pool", input_shape)?;
+        let out_size = self.config.output_size(in_size)?;
+        let windows = self.config.windows(in_size)?;
+        let include_pad = self.include_pad;
+        mean_backward(
+            &windows,
+            input_shape,
+            &out_size,
+            grad_output,
+            include_pad,
+        )
+    }
+}
+
+fn mean_backward<T: Real>(
+    windows: &Windows,
+    input_shape: &[usize],
+    out_size: &[usize],
+    grad_output: &Tensor<T>,
+    include_pad: bool,
+) -> Result<Tensor<T>, LinalgError> {
+    let expected = output_shape(input_shape, out_size);
+    if *grad_output.shape() != expected {
+        return Err(LinalgError::ShapeMismatch {
+            op: "pool backward",
+            lhs: expected,
+            rhs: grad_output.shape().into_owned(),
+        });
+    }
+    let dy = logical_data(grad_output);
+    let dx = windows.mean_backward(&dy, include_pad);
+    Tensor::try_new(dx.into(), ShapeDescriptor(input_shape.into()))
+}
+
+// ======================= Adaptive =======================
+/// Average pooling to a fixed output size whatever the input size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveAvgPool<const D: usize> {
+    output_size: [usize; D],
+}
+
+/// over `[batch, channels, height, width]`
+pub type AdaptiveAvgPool2d = AdaptiveAvgPool<2>;
+
+impl<const D: usize> AdaptiveAvgPool<D> {
+    pub fn new(output_size: [usize; D]) -> Self {
+        Self { output_size }
+    }
+
+    pub fn forward<T: Real>(&self, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(input))
+    }
+
+    pub fn try_forward<T: Real>(
+        &self,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let shape = input.shape();
+        let in_size = spatial_size::<D>("adaptive avg pool", &shape)?;
+        let windows = adaptive_windows(in_size, self.output_size)?;
+        let x = logical_data(input);
+        let out = windows.mean(&x, false);
+        Tensor::try_new(out.into(), output_shape(&shape, &self.output_size))
+    }
+
+    pub fn backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Tensor<T> {
+        unwrap_or_panic(self.try_backward(input_shape, grad_output))
+    }
+
+    pub fn try_backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let in_size = spatial_size::<D>("adaptive avg pool", input_shape)?;
+        let windows = adaptive_windows(in_size, self.output_size)?;
+        let out_size = self.output_size;
+        mean_backward(&windows, input_shape, &out_size, grad_output, false)
+    }
+}
+
+/// Max pooling to a fixed output size whatever the input size.
+#[derive(Debug, Clone)]
+pub struct AdaptiveMaxPool<const D: usize> {
+    output_size: [usize; D],
+    cache: Option<Argmax>,
+}
+
+/// over `[batch, channels, height, width]`
+pub type AdaptiveMaxPool2d = AdaptiveMaxPool<2>;
+
+impl<const D: usize> AdaptiveMaxPool<D> {
+    pub fn new(output_size: [usize; D]) -> Self {
+        Self {
+            output_size,
+            cache: None,
+        }
+    }
+
+    /// the flat input index of every output of the last forward pass.
+    pub fn argmax(&self) -> Option<&[usize]> {
+        self.cache.as_ref().map(|c| c.indices.as_slice())
+    }
+
+    pub fn forward<T: Real>(&mut self, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(input))
+    }
+
+    pub fn try_forward<T: Real>(
+        &mut self,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let in_size = spatial_size::<D>("adaptive max pool", &input.shape())?;
+        let windows = adaptive_windows(in_size, self.output_size)?;
+        let (output, cache) = max_forward(&windows, input, &self.output_size)?;
+        self.cache = Some(cache);
+        Ok(output)
+    }
+
+    pub fn backward<T: Real>(&self, grad_output: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_backward(grad_output))
+    }
+
+    pub fn try_backward<T: Real>(
+        &self,
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        Argmax::backward(self.cache.as_ref(), "adaptive max pool", grad_output)
+    }
+}
+
+// ======================= GlobalAvgPool =======================
+/// Averages `[batch, channels, spatial..]` over every spatial axis, of any
+/// number, into `[batch, channels]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GlobalAvgPool;
+
+impl GlobalAvgPool {
+    fn check(shape: &[usize]) -> Result<usize, LinalgError> {
+        if shape.len() < 3 {
+            return Err(LinalgError::RankMismatch {
+                op: "global avg pool",
+                expected: 3,
+                found: shape.len(),
+            });
+        }
+        let area: usize = shape[2..].iter().product();
+        if area == 0 {
+            return Err(invalid("global avg pool", "empty spatial axes"));
+        }
+        Ok(area)
+    }
+
+    pub fn forward<T: Real>(&self, input: &Tensor<T>) -> Tensor<T> {
+        unwrap_or_panic(self.try_forward(input))
+    }
+
+    pub fn try_forward<T: Real>(
+        &self,
+        input: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let shape = input.shape();
+        let area = Self::check(&shape)?;
+        let x = logical_data(input);
+        let n = T::from_usize(area);
+        let out = x.chunks(area).map(|p| p.iter().copied().sum::<T>() / n);
+        Tensor::try_new(out.collect(), ShapeDescriptor(shape[..2].into()))
+    }
+
+    pub fn backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Tensor<T> {
+        unwrap_or_panic(self.try_backward(input_shape, grad_output))
+    }
+
+    pub fn try_backward<T: Real>(
+        &self,
+        input_shape: &[usize],
+        grad_output: &Tensor<T>,
+    ) -> Result<Tensor<T>, LinalgError> {
+        let area = Self::check(input_shape)?;
+        if grad_output.shape()[..] != input_shape[..2] {
+            return Err(LinalgError::ShapeMismatch {
+                op: "global avg pool backward",
+                lhs: ShapeDescriptor(input_shape[..2].into()),
+                rhs: grad_output.shape().into_owned(),
+            });
+        }
+        let n = T::from_usize(area);
+        let dx = grad_output
+            .iter_logical()
+            .flat_map(|&g| std::iter::repeat_n(g / n, area))
+            .collect();
+        Tensor::try_new(dx, ShapeDescriptor(input_shape.into()))
+    }
+}
@@ -1,5 +1,21 @@
 #![allow(dead_code)]
 
+use crate::simd::SimdElement;
+use std::ops::{Div, Neg};
+
+/// The plain-old-data element types that device kernels operate on.
+pub trait Scalar: SimdElement + Send + Sync + 'static {}
+
+macro_rules! impl_scalar {
+    ($($tt:ty),+) => {
+        $(
+            impl Scalar for $tt {}
+        )*
+    }
+}
+
+impl_scalar![f32, f64, i32, i64, i128, u8, u32, u64, usize];
+
 pub trait NaturalFuncs<T> {
     fn abs(self) -> T;
     fn signum(self) -> T;
@@ -24,6 +40,13 @@ pub trait RealFuncs<T>: NaturalFuncs<T> {
     fn mul_add(self, a: T, b: T) -> T;
     fn max(self, other: T) -> T;
     fn min(self, other: T) -> T;
+    fn sin(self) -> T;
+    fn cos(self) -> T;
+    fn tan(self) -> T;
+    fn asin(self) -> T;
+    fn acos(self) -> T;
+    fn atan(self) -> T;
+    fn atan2(self, other: T) -> T;
 }
 
 macro_rules! impl_natural {
@@ -58,7 +81,7 @@ macro_rules! impl_reals {
                     <$tt>::powi(self, n)
                 }
 
-                fn powf(self, n: f64) -> $tt {
+                fn powf(self, n: $tt) -> $tt {
                     <$tt>::powf(self, n)
                 }
 
@@ -74,7 +97,7 @@ macro_rules! impl_reals {
                     <$tt>::ln(self)
                 }
 
-                fn log(self, base: f64) -> $tt {
+                fn log(self, base: $tt) -> $tt {
                     <$tt>::log(self, base)
                 }
 
@@ -117,6 +140,34 @@ macro_rules! impl_reals {
                 fn min(self, other: $tt) -> $tt {
                     <$tt>::min(self, other)
                 }
+
+                fn sin(self) -> $tt {
+                    <$tt>::sin(self)
+                }
+
+                fn cos(self) -> $tt {
+                    <$tt>::cos(self)
+                }
+
+                fn tan(self) -> $tt {
+                    <$tt>::tan(self)
+                }
+
+                fn asin(self) -> $tt {
+                    <$tt>::asin(self)
+                }
+
+                fn acos(self) -> $tt {
+                    <$tt>::acos(self)
+                }
+
+                fn atan(self) -> $tt {
+                    <$tt>::atan(self)
+                }
+
+                fn atan2(self, other: $tt) -> $tt {
+                    <$tt>::atan2(self, other)
+                }
             }
         )*
     };
@@ -132,7 +183,44 @@ macro_rules! impl_real_and_natural {
 }
 
 // both
-impl_real_and_natural![f64];
+impl_real_and_natural![f32, f64];
 
 // natural only
 impl_natural![i32, i64, i128];
+
+/// Floating point scalars, what the decompositions need on top of
+/// [`RealFuncs`].
+pub trait Real:
+    Scalar + RealFuncs<Self> + Neg<Output = Self> + Div<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// the gap between `1` and the next representable value.
+    const EPSILON: Self;
+
+    fn from_usize(value: usize) -> Self;
+    /// rounds to the nearest representable value.
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_real {
+    ($($tt:ty),+) => {
+        $(
+            impl Real for $tt {
+                const ZERO: $tt = 0.0;
+                const ONE: $tt = 1.0;
+                const EPSILON: $tt = <$tt>::EPSILON;
+
+                fn from_usize(value: usize) -> $tt {
+                    value as $tt
+                }
+
+                fn from_f64(value: f64) -> $tt {
+                    value as $tt
+                }
+            }
+        )*
+    }
+}
+
+impl_real![f32, f64];
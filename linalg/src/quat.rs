@@ -0,0 +1,149 @@
+use crate::{ndarr::arr2::Arr2, number::Real, vec_r3::Vec3};
+
+///====================== Quat ======================
+/// A quaternion `w + xi + yj + zk`, unit ones describe 3-d rotations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quat<T>([T; 4]);
+
+impl<T: Copy> Quat<T> {
+    pub fn new(w: T, x: T, y: T, z: T) -> Self {
+        Self([w, x, y, z])
+    }
+
+    #[inline]
+    pub fn w(&self) -> T {
+        self.0[0]
+    }
+
+    #[inline]
+    pub fn x(&self) -> T {
+        self.0[1]
+    }
+
+    #[inline]
+    pub fn y(&self) -> T {
+        self.0[2]
+    }
+
+    #[inline]
+    pub fn z(&self) -> T {
+        self.0[3]
+    }
+
+    /// the imaginary part.
+    #[inline]
+    pub fn vector(&self) -> Vec3<T> {
+        Vec3::new(self.x(), self.y(), self.z())
+    }
+}
+
+///====================== Quat Real ======================
+impl<T: Real> Quat<T> {
+    /// the rotation by nothing.
+    pub fn identity() -> Self {
+        Self::new(T::ONE, T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    /// the rotation by `angle` radians around `axis`, right handed. The
+    /// axis doesn't need to be unit length.
+    pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+        let half = angle / (T::ONE + T::ONE);
+        let v = axis.normalize() * half.sin();
+        Self::new(half.cos(), v.x(), v.y(), v.z())
+    }
+
+    pub fn dot(self, rhs: Self) -> T {
+        self.0.iter().zip(rhs.0).map(|(&a, b)| a * b).sum()
+    }
+
+    pub fn norm(self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    /// `self` scaled to unit length.
+    pub fn normalize(self) -> Self {
+        let norm = self.norm();
+        Self(self.0.map(|c| c / norm))
+    }
+
+    pub fn conjugate(self) -> Self {
+        Self::new(self.w(), -self.x(), -self.y(), -self.z())
+    }
+
+    /// the rotation undoing `self`, for unit quaternions the conjugate.
+    pub fn inverse(self) -> Self {
+        let norm_sq = self.dot(self);
+        Self(self.conjugate().0.map(|c| c / norm_sq))
+    }
+
+    /// Rotates `v` by the unit quaternion `self`, i.e. `q v q*` without
+    /// forming the intermediate products.
+    pub fn rotate(self, v: Vec3<T>) -> Vec3<T> {
+        let u = self.vector();
+        let t = u.cross(v) * (T::ONE + T::ONE);
+        v + t * self.w() + u.cross(t)
+    }
+
+    /// the 3x3 matrix of the rotation described by the unit `self`.
+    pub fn to_rotation_matrix(self) -> Arr2<T> {
+        let [w, x, y, z] = self.0;
+        let two = T::ONE + T::ONE;
+        let one = T::ONE;
+        let data = [
+            one - two * (y * y + z * z),
+            two * (x * y - w * z),
+            two * (x * z + w * y),
+            two * (x * y + w * z),
+            one - two * (x * x + z * z),
+            two * (y * z - w * x),
+            two * (x * z - w * y),
+            two * (y * z + w * x),
+            one - two * (x * x + y * y),
+        ];
+        Arr2::new(Box::new(data), (3, 3))
+    }
+
+    /// Spherical interpolation from `self` at `t = 0` to `rhs` at `t = 1`
+    /// along the shorter arc, both unit length.
+    pub fn slerp(self, rhs: Self, t: T) -> Self {
+        let mut cos = self.dot(rhs);
+        let mut rhs = rhs;
+        // q and -q are the same rotation, take the closer one
+        if cos < T::ZERO {
+            cos = -cos;
+            rhs = Self(rhs.0.map(|c| -c));
+        }
+
+        let (a, b) = if cos > T::ONE - T::EPSILON.sqrt() {
+            // nearly parallel, sin(θ) ≈ 0 so fall back to a plain lerp
+            (T::ONE - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((T::ONE - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        let mut out = [T::ZERO; 4];
+        for (o, (&p, q)) in out.iter_mut().zip(self.0.iter().zip(rhs.0)) {
+            *o = a * p + b * q;
+        }
+        Self(out).normalize()
+    }
+}
+
+///====================== Quat Mul ======================
+impl<T: Real> std::ops::Mul for Quat<T> {
+    type Output = Self;
+
+    /// the Hamilton product, `(a * b).rotate(v) == a.rotate(b.rotate(v))`.
+    fn mul(self, rhs: Self) -> Self::Output {
+        let [aw, ax, ay, az] = self.0;
+        let [bw, bx, by, bz] = rhs.0;
+        Self::new(
+            aw * bw - ax * bx - ay * by - az * bz,
+            aw * bx + ax * bw + ay * bz - az * by,
+            aw * by - ax * bz + ay * bw + az * bx,
+            aw * bz + ax * by - ay * bx + az * bw,
+        )
+    }
+}
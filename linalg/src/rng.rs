@@ -0,0 +1,99 @@
+//! A small seedable generator so shuffling and initialisation are
+//! reproducible without pulling in a dependency.
+
+// ======================= splitmix64 =======================
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// the first output of a splitmix64 stream started at `x`. Every bit of
+/// `x` affects every bit of the result, which makes it a cheap way to
+/// combine seeds.
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(GOLDEN_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// ======================= Rng =======================
+/// xoshiro256** seeded through splitmix64.
+///
+/// Not cryptographically secure, but fast, well distributed and with a
+/// state small enough to store next to a model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Expands `seed` into the full state, equal seeds give equal streams.
+    pub fn seed_from_u64(seed: u64) -> Self {
+        let mut sm = seed;
+        let mut next = || {
+            let z = splitmix64(sm);
+            sm = sm.wrapping_add(GOLDEN_GAMMA);
+            z
+        };
+        Self {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// Resumes from a state taken with [`Rng::state`]. An all zero state
+    /// would only ever produce zeros and is reseeded instead.
+    pub fn from_state(state: [u64; 4]) -> Self {
+        if state == [0; 4] {
+            return Self::seed_from_u64(0);
+        }
+        Self { state }
+    }
+
+    #[inline]
+    pub fn state(&self) -> [u64; 4] {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let out = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        out
+    }
+
+    /// uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill the mantissa exactly
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// uniform in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Uniform in `0..n` without modulo bias, `n` must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "[[linalg]] cannot sample below 0");
+        let n = n as u64;
+        // Lemire's multiply and reject
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let wide = (self.next_u64() as u128) * (n as u128);
+            if (wide as u64) >= threshold {
+                return (wide >> 64) as usize;
+            }
+        }
+    }
+
+    /// Fisher-Yates, every permutation is equally likely.
+    pub fn shuffle<T>(&mut self, xs: &mut [T]) {
+        for i in (1..xs.len()).rev() {
+            xs.swap(i, self.below(i + 1));
+        }
+    }
+}
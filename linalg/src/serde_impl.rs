@@ -0,0 +1,157 @@
+//! `Serialize`/`Deserialize` for the containers, behind the `serde` feature.
+//!
+//! Every tensor-like container is written as `{ "shape": [..], "data": [..] }`
+//! with the data in logical row-major order, so a tensor viewed through a
+//! transform is stored as its materialized layout. `ShapeDescriptor`,
+//! `Vec2`, `Vec3` and `Quat` derive their impls and are plain sequences.
+
+use crate::{
+    error::LinalgError,
+    ndarr::{arr1::Arr1, arr2::Arr2, stensor::STensor, tensor::Tensor},
+    shape::{Shape, ShapeDescriptor},
+};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer, de, ser::SerializeStruct,
+};
+
+// ======================= Tensor =======================
+/// the logical elements of a tensor as a sequence.
+struct Logical<'a, T>(&'a Tensor<T>);
+
+impl<T: Serialize> Serialize for Logical<'_, T> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter_logical())
+    }
+}
+
+impl<T: Serialize> Serialize for Tensor<T> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Tensor", 2)?;
+        state.serialize_field("shape", &*self.shape().0)?;
+        state.serialize_field("data", &Logical(self))?;
+        state.end()
+    }
+}
+
+/// what every container deserializes through before it's validated.
+#[derive(Deserialize)]
+#[serde(rename = "Tensor")]
+struct Raw<T> {
+    shape: Box<[usize]>,
+    data: Vec<T>,
+}
+
+impl<T> Raw<T> {
+    fn into_tensor<E: de::Error>(self) -> Result<Tensor<T>, E> {
+        Tensor::try_new(self.data.into(), ShapeDescriptor(self.shape))
+            .map_err(E::custom)
+    }
+
+    fn expect_rank<E: de::Error>(
+        &self,
+        op: &'static str,
+        rank: usize,
+    ) -> Result<(), E> {
+        if self.shape.len() == rank {
+            return Ok(());
+        }
+        Err(E::custom(LinalgError::RankMismatch {
+            op,
+            expected: rank,
+            found: self.shape.len(),
+        }))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Tensor<T> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Raw::deserialize(deserializer)?.into_tensor()
+    }
+}
+
+// ======================= Arr1 / Arr2 =======================
+impl<T: Serialize> Serialize for Arr1<T> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arr1<T> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+        raw.expect_rank("Arr1", 1)?;
+        if raw.shape[0] != raw.data.len() {
+            return Err(de::Error::custom(LinalgError::DataLength {
+                expected: raw.shape[0],
+                found: raw.data.len(),
+            }));
+        }
+        Ok(Arr1::new(raw.data.into()))
+    }
+}
+
+impl<T: Serialize> Serialize for Arr2<T> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arr2<T> {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+        raw.expect_rank("Arr2", 2)?;
+        let shape = (raw.shape[0], raw.shape[1]);
+        Arr2::try_new(raw.data.into(), shape).map_err(de::Error::custom)
+    }
+}
+
+// ======================= STensor =======================
+impl<T: Serialize, const R: usize, const C: usize> Serialize
+    for STensor<T, R, C>
+{
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Tensor", 2)?;
+        state.serialize_field("shape", &[R, C])?;
+        state.serialize_field("data", &self.as_array().as_flattened())?;
+        state.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy, const R: usize, const C: usize>
+    Deserialize<'de> for STensor<T, R, C>
+{
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let raw = Raw::deserialize(deserializer)?;
+        let tensor = raw.into_tensor::<D::Error>()?;
+        STensor::try_from(tensor).map_err(|t| {
+            de::Error::custom(LinalgError::ShapeMismatch {
+                op: "STensor",
+                lhs: ShapeDescriptor(Box::new([R, C])),
+                rhs: t.shape().into_owned(),
+            })
+        })
+    }
+}
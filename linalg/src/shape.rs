@@ -1,6 +1,6 @@
 use std::{borrow::Cow, ops::Deref};
 
-use crate::ndarr::transform::default_slice;
+use crate::{error::LinalgError, ndarr::transform::default_slice};
 
 // ======================= Shape =======================
 pub trait Shape {
@@ -30,8 +30,61 @@ pub trait Shape {
 
 // ======================= ShapeDescriptor =======================
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeDescriptor(pub Box<[usize]>);
 
+impl ShapeDescriptor {
+    /// builds a shape from `dims` where a single `-1` is inferred s.t. the
+    /// result holds exactly `hypervolume` elements.
+    pub fn try_infer(
+        dims: &[isize],
+        hypervolume: usize,
+    ) -> Result<Self, LinalgError> {
+        let invalid = || LinalgError::InvalidDimensions { dims: dims.into() };
+        let inferred = dims.iter().filter(|&&d| d == -1).count();
+        if inferred > 1 || dims.iter().any(|&d| d < -1) {
+            return Err(invalid());
+        }
+
+        let known: usize = dims
+            .iter()
+            .filter(|&&d| d != -1)
+            .map(|&d| d as usize)
+            .product();
+        let mut shape: Box<[usize]> = dims
+            .iter()
+            .map(|&d| if d == -1 { 0 } else { d as usize })
+            .collect();
+
+        if inferred == 1 {
+            if known == 0 || !hypervolume.is_multiple_of(known) {
+                return Err(invalid());
+            }
+            let slot = dims.iter().position(|&d| d == -1).unwrap();
+            shape[slot] = hypervolume / known;
+        }
+        Ok(Self(shape))
+    }
+
+    /// The shape both `self` and `rhs` stretch to under numpy style
+    /// broadcasting: aligned from the right, a `1` or a missing dimension
+    /// takes on the other one. `None` when two dimensions disagree.
+    pub fn broadcast(&self, rhs: &Self) -> Option<Self> {
+        let rank = self.len().max(rhs.len());
+        let dim = |s: &Self, i: usize| {
+            (i + s.len()).checked_sub(rank).map_or(1, |i| s[i])
+        };
+        (0..rank)
+            .map(|i| match (dim(self, i), dim(rhs, i)) {
+                (l, r) if l == r || r == 1 => Some(l),
+                (1, r) => Some(r),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+}
+
 /// the structure of a shape is infact a shape.
 impl Shape for ShapeDescriptor {
     fn shape(&self) -> Cow<ShapeDescriptor> {
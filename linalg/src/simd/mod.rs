@@ -0,0 +1,321 @@
+//! Vectorized slice kernels for the hot loops.
+//!
+//! Every kernel exists in a portable form that works for any element type,
+//! `f32` and `f64` additionally get hand written `std::arch` versions that
+//! are picked at runtime from what the cpu supports.
+
+mod portable;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+use std::{
+    any::TypeId,
+    iter::Sum,
+    ops::{Add, Mul, Sub},
+    slice,
+    sync::OnceLock,
+};
+
+// ======================= SimdLevel =======================
+/// The instruction sets a kernel may use, ordered from least to most capable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SimdLevel {
+    /// plain loops, left to the compiler to vectorize.
+    Portable,
+    Sse2,
+    Avx2,
+    Avx512,
+}
+
+impl SimdLevel {
+    /// the best level this cpu supports, probed once.
+    pub fn detect() -> Self {
+        static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+        *LEVEL.get_or_init(Self::probe)
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn probe() -> Self {
+        if is_x86_feature_detected!("avx512f") {
+            Self::Avx512
+        } else if is_x86_feature_detected!("avx2") {
+            Self::Avx2
+        } else if is_x86_feature_detected!("sse2") {
+            Self::Sse2
+        } else {
+            Self::Portable
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn probe() -> Self {
+        Self::Portable
+    }
+
+    /// `self` lowered to what the cpu actually supports.
+    pub fn supported(self) -> Self {
+        self.min(Self::detect())
+    }
+
+    /// every level up to [`SimdLevel::detect`], lowest first.
+    pub fn available() -> impl Iterator<Item = Self> {
+        [Self::Portable, Self::Sse2, Self::Avx2, Self::Avx512]
+            .into_iter()
+            .filter(|&level| level <= Self::detect())
+    }
+}
+
+impl Default for SimdLevel {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+// ======================= SimdElement =======================
+/// Slice kernels specialised per element type.
+///
+/// The defaults are the portable loops, types with vector kernels override
+/// them. A `level` above what the cpu supports is lowered first, so any
+/// level is safe to pass. The binary kernels expect slices of equal length.
+pub trait SimdElement:
+    Copy
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Sum<Self>
+{
+    /// `Σ lhs[i] * rhs[i]`
+    fn dot(level: SimdLevel, lhs: &[Self], rhs: &[Self]) -> Self {
+        let _ = level;
+        portable::dot(lhs, rhs)
+    }
+
+    /// `y[i] += alpha * x[i]`
+    fn axpy(level: SimdLevel, alpha: Self, x: &[Self], y: &mut [Self]) {
+        let _ = level;
+        portable::axpy(alpha, x, y)
+    }
+
+    /// `out[i] = lhs[i] + rhs[i]`
+    fn add_slices(
+        level: SimdLevel,
+        lhs: &[Self],
+        rhs: &[Self],
+        out: &mut [Self],
+    ) {
+        let _ = level;
+        portable::add_slices(lhs, rhs, out)
+    }
+
+    /// `out[i] = lhs[i] - rhs[i]`
+    fn sub_slices(
+        level: SimdLevel,
+        lhs: &[Self],
+        rhs: &[Self],
+        out: &mut [Self],
+    ) {
+        let _ = level;
+        portable::sub_slices(lhs, rhs, out)
+    }
+
+    /// `out[i] = lhs[i] * rhs[i]`
+    fn mul_slices(
+        level: SimdLevel,
+        lhs: &[Self],
+        rhs: &[Self],
+        out: &mut [Self],
+    ) {
+        let _ = level;
+        portable::mul_slices(lhs, rhs, out)
+    }
+
+    fn sum_slice(level: SimdLevel, xs: &[Self]) -> Self {
+        let _ = level;
+        portable::sum_slice(xs)
+    }
+
+    /// the largest element, `None` when `xs` is empty. NaNs are skipped like
+    /// `f64::max` does, only an all NaN slice gives NaN.
+    fn max_slice(level: SimdLevel, xs: &[Self]) -> Option<Self> {
+        let _ = level;
+        portable::max_slice(xs)
+    }
+
+    /// `Σ (lhs[i] - rhs[i])²`
+    fn sq_dist(level: SimdLevel, lhs: &[Self], rhs: &[Self]) -> Self {
+        let _ = level;
+        portable::sq_dist(lhs, rhs)
+    }
+
+    /// `Σ |lhs[i] - rhs[i]|`
+    fn l1_dist(level: SimdLevel, lhs: &[Self], rhs: &[Self]) -> Self {
+        let _ = level;
+        portable::l1_dist(lhs, rhs)
+    }
+}
+
+impl SimdElement for i32 {}
+impl SimdElement for i64 {}
+impl SimdElement for i128 {}
+impl SimdElement for u8 {}
+impl SimdElement for u32 {}
+impl SimdElement for u64 {}
+impl SimdElement for usize {}
+
+/// Calls `$kernel` from the module of the best supported level in
+/// `[$sse2, $avx2, $avx512]`, falling back to the portable one.
+macro_rules! dispatch {
+    ($level:expr, [$sse2:ident, $avx2:ident, $avx512:ident],
+     $kernel:ident($($arg:expr),*)) => {{
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            // SAFETY: `supported` never returns a level the cpu lacks, so the
+            // target features of the chosen kernel are available
+            match $level.supported() {
+                SimdLevel::Avx512 => {
+                    return unsafe { x86::$avx512::$kernel($($arg),*) };
+                }
+                SimdLevel::Avx2 => {
+                    return unsafe { x86::$avx2::$kernel($($arg),*) };
+                }
+                SimdLevel::Sse2 => {
+                    return unsafe { x86::$sse2::$kernel($($arg),*) };
+                }
+                SimdLevel::Portable => {}
+            }
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        let _ = $level;
+        portable::$kernel($($arg),*)
+    }};
+}
+
+macro_rules! impl_simd_float {
+    ($tt:ty, $mods:tt) => {
+        impl SimdElement for $tt {
+            fn dot(level: SimdLevel, lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                debug_assert_eq!(lhs.len(), rhs.len());
+                dispatch!(level, $mods, dot(lhs, rhs))
+            }
+
+            fn axpy(level: SimdLevel, alpha: $tt, x: &[$tt], y: &mut [$tt]) {
+                debug_assert_eq!(x.len(), y.len());
+                dispatch!(level, $mods, axpy(alpha, x, y))
+            }
+
+            fn add_slices(
+                level: SimdLevel,
+                lhs: &[$tt],
+                rhs: &[$tt],
+                out: &mut [$tt],
+            ) {
+                debug_assert!(lhs.len() == out.len() && rhs.len() == out.len());
+                dispatch!(level, $mods, add_slices(lhs, rhs, out))
+            }
+
+            fn sub_slices(
+                level: SimdLevel,
+                lhs: &[$tt],
+                rhs: &[$tt],
+                out: &mut [$tt],
+            ) {
+                debug_assert!(lhs.len() == out.len() && rhs.len() == out.len());
+                dispatch!(level, $mods, sub_slices(lhs, rhs, out))
+            }
+
+            fn mul_slices(
+                level: SimdLevel,
+                lhs: &[$tt],
+                rhs: &[$tt],
+                out: &mut [$tt],
+            ) {
+                debug_assert!(lhs.len() == out.len() && rhs.len() == out.len());
+                dispatch!(level, $mods, mul_slices(lhs, rhs, out))
+            }
+
+            fn sum_slice(level: SimdLevel, xs: &[$tt]) -> $tt {
+                dispatch!(level, $mods, sum_slice(xs))
+            }
+
+            fn max_slice(level: SimdLevel, xs: &[$tt]) -> Option<$tt> {
+                dispatch!(level, $mods, max_slice(xs))
+            }
+
+            fn sq_dist(level: SimdLevel, lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                debug_assert_eq!(lhs.len(), rhs.len());
+                dispatch!(level, $mods, sq_dist(lhs, rhs))
+            }
+
+            fn l1_dist(level: SimdLevel, lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                debug_assert_eq!(lhs.len(), rhs.len());
+                dispatch!(level, $mods, l1_dist(lhs, rhs))
+            }
+        }
+    };
+}
+
+impl_simd_float!(f32, [sse2_f32, avx2_f32, avx512_f32]);
+impl_simd_float!(f64, [sse2_f64, avx2_f64, avx512_f64]);
+
+// ======================= float fast paths =======================
+// Code generic over plain operators can't require `SimdElement` without
+// shutting out every other element type, so it checks at runtime whether
+// `T` is one of the types with vector kernels and falls back to its own
+// loop when it isn't.
+
+/// `xs` as a slice of `E`, if `T` is `E`.
+fn downcast_slice<T: 'static, E: 'static>(xs: &[T]) -> Option<&[E]> {
+    // SAFETY: `T` and `E` are the same type
+    (TypeId::of::<T>() == TypeId::of::<E>())
+        .then(|| unsafe { slice::from_raw_parts(xs.as_ptr().cast(), xs.len()) })
+}
+
+fn downcast_slice_mut<T: 'static, E: 'static>(
+    xs: &mut [T],
+) -> Option<&mut [E]> {
+    // SAFETY: `T` and `E` are the same type
+    (TypeId::of::<T>() == TypeId::of::<E>()).then(|| unsafe {
+        slice::from_raw_parts_mut(xs.as_mut_ptr().cast(), xs.len())
+    })
+}
+
+/// `kernel(lhs, rhs)` when `T` is `E`, `None` otherwise.
+pub(crate) fn reduce_as<T, E>(
+    lhs: &[T],
+    rhs: &[T],
+    kernel: fn(SimdLevel, &[E], &[E]) -> E,
+) -> Option<T>
+where
+    T: Copy + 'static,
+    E: SimdElement + 'static,
+{
+    let (lhs, rhs) = (downcast_slice(lhs)?, downcast_slice(rhs)?);
+    let out = kernel(SimdLevel::detect(), lhs, rhs);
+    downcast_slice(slice::from_ref(&out)).map(|out| out[0])
+}
+
+/// `kernel(lhs, rhs, out)` when `T` is `E`, returns whether it ran.
+pub(crate) fn zip_as<T, E>(
+    lhs: &[T],
+    rhs: &[T],
+    out: &mut [T],
+    kernel: fn(SimdLevel, &[E], &[E], &mut [E]),
+) -> bool
+where
+    T: 'static,
+    E: SimdElement + 'static,
+{
+    match (
+        downcast_slice(lhs),
+        downcast_slice(rhs),
+        downcast_slice_mut(out),
+    ) {
+        (Some(lhs), Some(rhs), Some(out)) => {
+            kernel(SimdLevel::detect(), lhs, rhs, out);
+            true
+        }
+        _ => false,
+    }
+}
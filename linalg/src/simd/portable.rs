@@ -0,0 +1,60 @@
+use super::SimdElement;
+
+// ======================= portable kernels =======================
+// written as plain zips so the compiler is free to vectorize them
+
+pub(super) fn dot<T: SimdElement>(lhs: &[T], rhs: &[T]) -> T {
+    lhs.iter().zip(rhs).map(|(&l, &r)| l * r).sum()
+}
+
+pub(super) fn axpy<T: SimdElement>(alpha: T, x: &[T], y: &mut [T]) {
+    for (y, &x) in y.iter_mut().zip(x) {
+        *y = *y + alpha * x;
+    }
+}
+
+fn zip_into<T, F>(lhs: &[T], rhs: &[T], out: &mut [T], f: F)
+where
+    T: SimdElement,
+    F: Fn(T, T) -> T,
+{
+    for (o, (&l, &r)) in out.iter_mut().zip(lhs.iter().zip(rhs)) {
+        *o = f(l, r);
+    }
+}
+
+pub(super) fn add_slices<T: SimdElement>(lhs: &[T], rhs: &[T], out: &mut [T]) {
+    zip_into(lhs, rhs, out, |l, r| l + r)
+}
+
+pub(super) fn sub_slices<T: SimdElement>(lhs: &[T], rhs: &[T], out: &mut [T]) {
+    zip_into(lhs, rhs, out, |l, r| l - r)
+}
+
+pub(super) fn mul_slices<T: SimdElement>(lhs: &[T], rhs: &[T], out: &mut [T]) {
+    zip_into(lhs, rhs, out, |l, r| l * r)
+}
+
+pub(super) fn sum_slice<T: SimdElement>(xs: &[T]) -> T {
+    xs.iter().copied().sum()
+}
+
+pub(super) fn max_slice<T: SimdElement>(xs: &[T]) -> Option<T> {
+    // `a` is only unordered with itself when it's a NaN
+    let nan = |a: T| a.partial_cmp(&a).is_none();
+    xs.iter()
+        .copied()
+        .reduce(|a, b| if b > a || nan(a) { b } else { a })
+}
+
+pub(super) fn sq_dist<T: SimdElement>(lhs: &[T], rhs: &[T]) -> T {
+    lhs.iter().zip(rhs).map(|(&l, &r)| (l - r) * (l - r)).sum()
+}
+
+pub(super) fn l1_dist<T: SimdElement>(lhs: &[T], rhs: &[T]) -> T {
+    // larger minus smaller keeps unsigned types from underflowing
+    lhs.iter()
+        .zip(rhs)
+        .map(|(&l, &r)| if l > r { l - r } else { r - l })
+        .sum()
+}
@@ -0,0 +1,220 @@
+//! `std::arch` kernels, one module per element type and instruction set.
+//!
+//! Every function is compiled with its target features enabled and is only
+//! safe to call once [`super::SimdLevel::detect`] has seen them.
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Expands the kernels for one vector type. The loops run over whole
+/// vectors and finish the remainder with scalar code.
+macro_rules! x86_kernels {
+    (
+        $name:ident, $feature:literal, $tt:ty, $vec:ty, $lanes:literal,
+        load: $load:ident, store: $store:ident, set1: $set1:ident,
+        zero: $zero:ident, add: $add:ident, sub: $sub:ident,
+        mul: $mul:ident, max: $max:ident, abs: $abs:ident
+    ) => {
+        pub(super) mod $name {
+            use super::*;
+
+            const LANES: usize = $lanes;
+
+            #[inline]
+            #[target_feature(enable = $feature)]
+            fn load(xs: &[$tt], at: usize) -> $vec {
+                debug_assert!(at + LANES <= xs.len());
+                // SAFETY: callers only load whole vectors inside `xs`
+                unsafe { $load(xs.as_ptr().add(at)) }
+            }
+
+            #[inline]
+            #[target_feature(enable = $feature)]
+            fn store(xs: &mut [$tt], at: usize, v: $vec) {
+                debug_assert!(at + LANES <= xs.len());
+                // SAFETY: callers only store whole vectors inside `xs`
+                unsafe { $store(xs.as_mut_ptr().add(at), v) }
+            }
+
+            #[inline]
+            #[target_feature(enable = $feature)]
+            fn lanes(v: $vec) -> [$tt; LANES] {
+                let mut out = [0.0; LANES];
+                store(&mut out, 0, v);
+                out
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn dot(lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                let n = lhs.len().min(rhs.len());
+                let body = n - n % LANES;
+                let mut acc = $zero();
+                for i in (0..body).step_by(LANES) {
+                    acc = $add(acc, $mul(load(lhs, i), load(rhs, i)));
+                }
+                let tail: $tt =
+                    (body..n).map(|i| lhs[i] * rhs[i]).sum();
+                lanes(acc).iter().sum::<$tt>() + tail
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn axpy(alpha: $tt, x: &[$tt], y: &mut [$tt]) {
+                let n = x.len().min(y.len());
+                let body = n - n % LANES;
+                let a = $set1(alpha);
+                for i in (0..body).step_by(LANES) {
+                    let v = $add(load(y, i), $mul(a, load(x, i)));
+                    store(y, i, v);
+                }
+                for i in body..n {
+                    y[i] += alpha * x[i];
+                }
+            }
+
+            x86_kernels!(@binary $feature, $tt, add_slices, $add, +);
+            x86_kernels!(@binary $feature, $tt, sub_slices, $sub, -);
+            x86_kernels!(@binary $feature, $tt, mul_slices, $mul, *);
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn sum_slice(xs: &[$tt]) -> $tt {
+                let body = xs.len() - xs.len() % LANES;
+                let mut acc = $zero();
+                for i in (0..body).step_by(LANES) {
+                    acc = $add(acc, load(xs, i));
+                }
+                lanes(acc).iter().sum::<$tt>() + xs[body..].iter().sum::<$tt>()
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn max_slice(xs: &[$tt]) -> Option<$tt> {
+                // `max` returns its second operand when either is a NaN,
+                // so an accumulator seeded with a number skips them all
+                let Some(&first) = xs.iter().find(|x| !x.is_nan()) else {
+                    return xs.first().copied();
+                };
+                let body = xs.len() - xs.len() % LANES;
+                let mut acc = $set1(first);
+                for i in (0..body).step_by(LANES) {
+                    acc = $max(load(xs, i), acc);
+                }
+                let max = |a: $tt, b: $tt| if b > a { b } else { a };
+                Some(
+                    lanes(acc)
+                        .into_iter()
+                        .chain(xs[body..].iter().copied())
+                        .fold(first, max),
+                )
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn sq_dist(lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                let n = lhs.len().min(rhs.len());
+                let body = n - n % LANES;
+                let mut acc = $zero();
+                for i in (0..body).step_by(LANES) {
+                    let d = $sub(load(lhs, i), load(rhs, i));
+                    acc = $add(acc, $mul(d, d));
+                }
+                let tail: $tt = (body..n)
+                    .map(|i| (lhs[i] - rhs[i]) * (lhs[i] - rhs[i]))
+                    .sum();
+                lanes(acc).iter().sum::<$tt>() + tail
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) fn l1_dist(lhs: &[$tt], rhs: &[$tt]) -> $tt {
+                let n = lhs.len().min(rhs.len());
+                let body = n - n % LANES;
+                let mut acc = $zero();
+                for i in (0..body).step_by(LANES) {
+                    let d = $sub(load(lhs, i), load(rhs, i));
+                    acc = $add(acc, $abs(d));
+                }
+                let tail: $tt =
+                    (body..n).map(|i| (lhs[i] - rhs[i]).abs()).sum();
+                lanes(acc).iter().sum::<$tt>() + tail
+            }
+        }
+    };
+
+    (@binary $feature:literal, $tt:ty, $name:ident, $op:ident, $scalar:tt) => {
+        #[target_feature(enable = $feature)]
+        pub(crate) fn $name(lhs: &[$tt], rhs: &[$tt], out: &mut [$tt]) {
+            let n = out.len().min(lhs.len()).min(rhs.len());
+            let body = n - n % LANES;
+            for i in (0..body).step_by(LANES) {
+                store(out, i, $op(load(lhs, i), load(rhs, i)));
+            }
+            for i in body..n {
+                out[i] = lhs[i] $scalar rhs[i];
+            }
+        }
+    };
+}
+
+// ======================= abs =======================
+// clearing the sign bit, avx512f has its own abs
+
+#[inline]
+#[target_feature(enable = "sse2")]
+fn abs_sse2_f32(v: __m128) -> __m128 {
+    _mm_andnot_ps(_mm_set1_ps(-0.0), v)
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+fn abs_sse2_f64(v: __m128d) -> __m128d {
+    _mm_andnot_pd(_mm_set1_pd(-0.0), v)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+fn abs_avx2_f32(v: __m256) -> __m256 {
+    _mm256_andnot_ps(_mm256_set1_ps(-0.0), v)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+fn abs_avx2_f64(v: __m256d) -> __m256d {
+    _mm256_andnot_pd(_mm256_set1_pd(-0.0), v)
+}
+
+// ======================= kernels =======================
+x86_kernels!(
+    sse2_f32, "sse2", f32, __m128, 4,
+    load: _mm_loadu_ps, store: _mm_storeu_ps, set1: _mm_set1_ps,
+    zero: _mm_setzero_ps, add: _mm_add_ps, sub: _mm_sub_ps,
+    mul: _mm_mul_ps, max: _mm_max_ps, abs: abs_sse2_f32
+);
+x86_kernels!(
+    sse2_f64, "sse2", f64, __m128d, 2,
+    load: _mm_loadu_pd, store: _mm_storeu_pd, set1: _mm_set1_pd,
+    zero: _mm_setzero_pd, add: _mm_add_pd, sub: _mm_sub_pd,
+    mul: _mm_mul_pd, max: _mm_max_pd, abs: abs_sse2_f64
+);
+x86_kernels!(
+    avx2_f32, "avx2", f32, __m256, 8,
+    load: _mm256_loadu_ps, store: _mm256_storeu_ps, set1: _mm256_set1_ps,
+    zero: _mm256_setzero_ps, add: _mm256_add_ps, sub: _mm256_sub_ps,
+    mul: _mm256_mul_ps, max: _mm256_max_ps, abs: abs_avx2_f32
+);
+x86_kernels!(
+    avx2_f64, "avx2", f64, __m256d, 4,
+    load: _mm256_loadu_pd, store: _mm256_storeu_pd, set1: _mm256_set1_pd,
+    zero: _mm256_setzero_pd, add: _mm256_add_pd, sub: _mm256_sub_pd,
+    mul: _mm256_mul_pd, max: _mm256_max_pd, abs: abs_avx2_f64
+);
+x86_kernels!(
+    avx512_f32, "avx512f", f32, __m512, 16,
+    load: _mm512_loadu_ps, store: _mm512_storeu_ps, set1: _mm512_set1_ps,
+    zero: _mm512_setzero_ps, add: _mm512_add_ps, sub: _mm512_sub_ps,
+    mul: _mm512_mul_ps, max: _mm512_max_ps, abs: _mm512_abs_ps
+);
+x86_kernels!(
+    avx512_f64, "avx512f", f64, __m512d, 8,
+    load: _mm512_loadu_pd, store: _mm512_storeu_pd, set1: _mm512_set1_pd,
+    zero: _mm512_setzero_pd, add: _mm512_add_pd, sub: _mm512_sub_pd,
+    mul: _mm512_mul_pd, max: _mm512_max_pd, abs: _mm512_abs_pd
+);
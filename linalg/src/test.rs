@@ -1,6 +1,49 @@
 use super::vec_r2::Vec2;
+use super::vec_r3::Vec3;
+use crate::assert_tensor_close;
+use crate::data::{
+    Dataset, TensorDataset,
+    csv::{CsvReader, Missing},
+    idx::Idx,
+    loader::{Batch, DataLoader},
+};
+use crate::error::LinalgError;
+use crate::gradcheck::GradCheck;
+use crate::io::{
+    checkpoint::Checkpoint,
+    npy::{read_npy, write_npy},
+    npz::{Npz, NpzWriter},
+    safetensors::{self, SafeTensors},
+};
 use crate::ndarr::arr1::Arr1;
+use crate::ndarr::arr2::Arr2;
+use crate::ndarr::compare::Mismatch;
+use crate::ndarr::contract::{einsum, try_einsum};
+use crate::ndarr::device::{
+    Device, DeviceBackend, DeviceError, Kernel, KernelOp,
+    cpu::CpuDevice,
+    simulated::{DeviceStats, FailurePoint, SimulatedDevice},
+};
+use crate::ndarr::stensor::STensor;
+use crate::ndarr::tensor::Tensor;
+use crate::ndarr::tensor::TensorAccess;
+use crate::ndarr::transform::Transform;
+use crate::ndarr::transform::concrete_transformers::{
+    IdentityTransform, ReshapeTransform,
+};
+use crate::nn::conv::{Conv1d, Conv2d, ConvConfig};
+use crate::nn::pool::{
+    AdaptiveAvgPool, AdaptiveAvgPool2d, AdaptiveMaxPool, AdaptiveMaxPool2d,
+    AvgPool2d, GlobalAvgPool, MaxPool, MaxPool2d, PoolConfig,
+};
+use crate::quat::Quat;
+use crate::rng::Rng;
+use crate::shape::{Shape, ShapeDescriptor};
+use crate::simd::{SimdElement, SimdLevel};
 use crate::slice;
+use std::borrow::Cow;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
 
 #[test]
 pub(self) fn test_add() {
@@ -26,7 +69,7 @@ pub(self) fn test_mul() {
 #[test]
 pub(self) fn test_arr1_deref() {
     let arr1 = Arr1::new(slice![0.0; 10]);
-    assert_eq!(&0.0, &(*arr1)[0]);
+    assert_eq!(&0.0, &(**arr1)[0]);
     // test deref coercion as well
     assert_eq!(&0.0, &arr1[[0]]);
 
@@ -57,3 +100,2244 @@ pub(self) fn test_arr1_distance() {
     let dist = arr_0.distance(&arr_1);
     assert_eq!(40.0, dist);
 }
+
+#[test]
+fn test_stensor_matmul() {
+    let a = STensor::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b = STensor::new([[7.0, 8.0], [9.0, 10.0], [11.0, 12.0]]);
+    let c: STensor<f64, 2, 2> = a * b;
+    assert_eq!(c, STensor::new([[58.0, 64.0], [139.0, 154.0]]));
+    assert_eq!(a.transpose().transpose(), a);
+    assert_eq!(a.transpose()[[2, 1]], 6.0);
+}
+
+#[test]
+fn test_stensor_tensor_roundtrip() {
+    let s = STensor::new([[1, 2, 3], [4, 5, 6]]);
+    let tensor: Tensor<i32> = s.into();
+    assert_eq!(*tensor.shape(), ShapeDescriptor(Box::new([2, 3])));
+    assert_eq!(&*tensor, &[1, 2, 3, 4, 5, 6]);
+
+    let back = STensor::<i32, 2, 3>::try_from(tensor).ok().unwrap();
+    assert_eq!(back, s);
+
+    // wrong static shape hands the tensor back
+    let tensor: Tensor<i32> = s.into();
+    let err = STensor::<i32, 3, 2>::try_from(tensor).err().unwrap();
+    assert_eq!(&*err, &[1, 2, 3, 4, 5, 6]);
+
+    let arr: Arr2<i32> = s.into();
+    assert_eq!(arr[[1, 0]], 4);
+    assert_eq!(STensor::<i32, 2, 3>::try_from(arr).ok().unwrap(), s);
+}
+
+#[test]
+fn test_arr2_try_matmul() {
+    let a = Arr2::new(slice![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], (2, 3));
+    let b = Arr2::new(slice![7.0, 8.0, 9.0, 10.0, 11.0, 12.0], (3, 2));
+    let c = a.try_matmul(&b).ok().unwrap();
+    assert_eq!(&**c, &[58.0, 64.0, 139.0, 154.0]);
+
+    let err = a.try_matmul(&a).err().unwrap();
+    assert_eq!(
+        err,
+        LinalgError::ShapeMismatch {
+            op: "matmul",
+            lhs: ShapeDescriptor(Box::new([2, 3])),
+            rhs: ShapeDescriptor(Box::new([2, 3])),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "shape mismatch in matmul: lhs [2, 3] is incompatible with rhs [2, 3]"
+    );
+    assert!(a.try_matadd(&c).is_err());
+}
+
+#[test]
+#[should_panic(expected = "[[linalg]] shape mismatch in matmul")]
+fn test_arr2_matmul_panics() {
+    let a = Arr2::new(slice![1.0; 6], (2, 3));
+    let b = Arr2::new(slice![1.0; 6], (2, 3));
+    let _ = a * b;
+}
+
+#[test]
+fn test_linalg_errors() {
+    let src = ShapeDescriptor(Box::new([2, 3]));
+    let err = ReshapeTransform::new(&src, ShapeDescriptor(Box::new([4])))
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "cannot reshape [2, 3] (6 elements) into [4] (4 elements)"
+    );
+
+    let tensor = Tensor::new(slice![0; 6], src);
+    assert!(tensor.try_get(&[1, 2]).is_ok());
+    assert!(matches!(
+        tensor.try_get(&[2, 0]),
+        Err(LinalgError::IndexOutOfBounds { .. })
+    ));
+    assert!(matches!(
+        Tensor::try_new(slice![0; 5], ShapeDescriptor(Box::new([2, 3]))),
+        Err(LinalgError::DataLength {
+            expected: 6,
+            found: 5
+        })
+    ));
+
+    let a = Arr1::new(slice![1.0, 2.0]);
+    let b = Arr1::new(slice![1.0, 2.0, 3.0]);
+    assert!(a.try_inner_product(&b).is_err());
+    assert!(a.try_distance(&b).is_err());
+}
+
+#[test]
+fn test_tensor_reshape() {
+    let tensor =
+        Tensor::new(slice![0, 1, 2, 3, 4, 5], ShapeDescriptor(slice![2, 3]));
+    let tensor = tensor.reshape(&[3, -1]);
+    assert_eq!(*tensor.shape(), ShapeDescriptor(Box::new([3, 2])));
+    assert_eq!(tensor[&[2, 0]], 4);
+
+    let tensor = tensor.unsqueeze(0).unsqueeze(3);
+    assert_eq!(*tensor.shape(), ShapeDescriptor(Box::new([1, 3, 2, 1])));
+    assert_eq!(tensor[&[0, 1, 1, 0]], 3);
+
+    assert!(matches!(
+        tensor.clone().try_squeeze_axis(1),
+        Err(LinalgError::NotSqueezable { axis: 1, len: 3 })
+    ));
+    let tensor = tensor.squeeze_axis(3);
+    assert_eq!(tensor.rank(), 3);
+    let tensor = tensor.squeeze();
+    assert_eq!(*tensor.shape(), ShapeDescriptor(Box::new([3, 2])));
+
+    let tensor = tensor.view(&[6]);
+    assert_eq!(&*tensor, &[0, 1, 2, 3, 4, 5]);
+
+    assert!(matches!(
+        tensor.clone().try_reshape(&[4, -1]),
+        Err(LinalgError::InvalidDimensions { .. })
+    ));
+    assert!(matches!(
+        tensor.clone().try_reshape(&[-1, -1]),
+        Err(LinalgError::InvalidDimensions { .. })
+    ));
+    assert!(matches!(
+        tensor.clone().try_reshape(&[4, 2]),
+        Err(LinalgError::HypervolumeMismatch { .. })
+    ));
+    assert!(matches!(
+        tensor.try_unsqueeze(2),
+        Err(LinalgError::AxisOutOfBounds { axis: 2, rank: 1 })
+    ));
+}
+
+#[test]
+fn test_tensor_reshape_transformed() {
+    // a 2x3 buffer read as its 3x2 transpose
+    let transpose = Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![3, 2]),
+        slice![1, 3],
+    ));
+    let mut tensor =
+        Tensor::new(slice![0, 1, 2, 3, 4, 5], ShapeDescriptor(slice![2, 3]));
+    tensor.set_transform(transpose);
+    assert_eq!(tensor[&[1, 0]], 1);
+
+    assert!(matches!(
+        tensor.clone().try_view(&[6]),
+        Err(LinalgError::NotContiguous { op: "view" })
+    ));
+
+    // falls back to copying the logical order
+    let flat = tensor.flatten();
+    assert!(flat.transform().is_none());
+    assert_eq!(&*flat, &[0, 3, 1, 4, 2, 5]);
+
+    // the -1 is inferred from the logical shape, not the raw buffer
+    let mut every_third =
+        Tensor::new(slice![10, 11, 12, 13, 14, 15], ShapeDescriptor(slice![6]));
+    every_third.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![2]),
+        slice![3],
+    )));
+    let column = every_third.reshape(&[-1, 1]);
+    assert_eq!(*column.shape(), ShapeDescriptor(slice![2, 1]));
+    assert_eq!(&*column, &[10, 13]);
+}
+
+/// builds a transposed view whose transform only lives inside the tensor.
+fn transposed(rows: usize, cols: usize) -> Tensor<usize> {
+    let data = (0..rows * cols).collect::<Vec<_>>().into_boxed_slice();
+    let mut tensor = Tensor::new(data, ShapeDescriptor(slice![rows, cols]));
+    tensor.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![cols, rows]),
+        slice![1, cols],
+    )));
+    tensor
+}
+
+#[test]
+fn test_tensor_owned_transform() {
+    let tensor = transposed(2, 3);
+    assert_eq!(*tensor.shape(), ShapeDescriptor(slice![3, 2]));
+
+    let clone = tensor.clone();
+    let handle = std::thread::spawn(move || clone[&[2, 1]]);
+    assert_eq!(handle.join().unwrap(), 5);
+
+    // one transform shared by two tensors
+    let mut other = Tensor::new(slice![9; 6], ShapeDescriptor(slice![2, 3]));
+    let mut first = tensor;
+    let shared = first.clear_transform().unwrap();
+    other.set_transform(shared.clone());
+    first.set_transform(shared);
+    assert_eq!(first[&[1, 0]], 1);
+    assert_eq!(*other.shape(), ShapeDescriptor(slice![3, 2]));
+}
+
+#[test]
+fn test_tensor_contiguous() {
+    let tensor = transposed(2, 3);
+    assert!(!tensor.is_contiguous());
+
+    let copy = tensor.contiguous();
+    assert!(copy.is_contiguous() && copy.transform().is_none());
+    assert_eq!(*copy.shape(), ShapeDescriptor(slice![3, 2]));
+    assert_eq!(&*copy, &[0, 3, 1, 4, 2, 5]);
+    assert_eq!(&*tensor.to_owned_layout(), &[0, 3, 1, 4, 2, 5]);
+
+    // a reshape transform keeps the buffer in logical order
+    let src = ShapeDescriptor(slice![2, 3]);
+    let reshape =
+        ReshapeTransform::new(&src, ShapeDescriptor(slice![3, 1, 2])).unwrap();
+    let mut tensor = Tensor::new(slice![0, 1, 2, 3, 4, 5], src);
+    tensor.set_transform(Arc::new(reshape));
+    assert!(tensor.is_contiguous());
+    assert_eq!(tensor[&[2, 0, 1]], 5);
+
+    let ptr = tensor.as_ptr();
+    let owned = tensor.to_owned_layout();
+    assert_eq!(owned.as_ptr(), ptr);
+    assert!(owned.transform().is_none());
+    assert_eq!(owned.strides(), &[2, 2, 1]);
+
+    // row-major strides don't make a mapping that isn't built from them
+    // contiguous, whether it starts past the head or swaps interior items
+    struct Permuted(Box<[usize]>);
+    impl Transform for Permuted {
+        fn to_flat(&self, logical: &[usize]) -> usize {
+            self.0[logical[0]]
+        }
+        fn out_shape(&self) -> Cow<'_, ShapeDescriptor> {
+            Cow::Owned(ShapeDescriptor(slice![self.0.len()]))
+        }
+        fn out_strides(&self) -> Cow<'_, [usize]> {
+            Cow::Owned(vec![1])
+        }
+    }
+    for (perm, expected) in
+        [([2, 1, 0], [30, 20, 10]), ([0, 2, 1], [10, 30, 20])]
+    {
+        let mut tensor =
+            Tensor::new(slice![10, 20, 30], ShapeDescriptor(slice![3]));
+        tensor.set_transform(Arc::new(Permuted(Box::new(perm))));
+        assert!(!tensor.is_contiguous());
+        assert!(tensor.iter_logical().copied().eq(expected));
+        assert_eq!(&*tensor.to_owned_layout(), &expected);
+    }
+}
+
+#[test]
+fn test_tensor_iter_logical() {
+    let mut tensor = transposed(2, 3);
+    let logical: Vec<_> = tensor.iter_logical().copied().collect();
+    assert_eq!(logical, [0, 3, 1, 4, 2, 5]);
+
+    let (index, elem) = tensor.indexed_iter().nth(3).unwrap();
+    assert_eq!((&*index, *elem), (&[1, 1][..], 4));
+
+    tensor.iter_logical_mut().for_each(|e| *e *= 10);
+    assert_eq!(&*tensor, &[0, 10, 20, 30, 40, 50]);
+    for (index, elem) in tensor.indexed_iter_mut() {
+        *elem = index[0];
+    }
+    assert_eq!(&*tensor, &[0, 1, 2, 0, 1, 2]);
+}
+
+#[test]
+fn test_tensor_axis_iter() {
+    let mut tensor = transposed(2, 3);
+    let firsts: Vec<_> = tensor.axis_iter(0).map(|row| row[&[0]]).collect();
+    assert_eq!(firsts, [0, 1, 2]);
+
+    let last = tensor.axis_iter(1).last().unwrap();
+    assert_eq!(*last.shape(), ShapeDescriptor(slice![3]));
+    assert_eq!(&*last.to_tensor(), &[3, 4, 5]);
+    assert!(tensor.try_axis_iter(2).is_err());
+
+    tensor.axis_iter_mut(0).nth(1).unwrap().fill(7);
+    assert_eq!(&*tensor, &[0, 7, 2, 3, 7, 5]);
+
+    // a middle axis, with every mutable view alive at once
+    let data = (0..12).collect();
+    let mut cube = Tensor::new(data, ShapeDescriptor(slice![2, 3, 2]));
+    let mut views: Vec<_> = cube.axis_iter_mut(1).collect();
+    assert_eq!(views.len(), 3);
+    for (index, view) in views.iter_mut().enumerate() {
+        assert_eq!(*view.shape(), ShapeDescriptor(slice![2, 2]));
+        view.fill(index * 100);
+    }
+    assert_eq!(
+        &*cube,
+        &[0, 0, 100, 100, 200, 200, 0, 0, 100, 100, 200, 200]
+    );
+    let middle = cube.axis_iter(1).nth(1).unwrap().to_tensor();
+    assert_eq!(&*middle, &[100; 4]);
+}
+
+#[test]
+fn test_arr2_rows_cols() {
+    let mut arr = Arr2::new(slice![1, 2, 3, 4, 5, 6], (2, 3));
+    assert_eq!((arr.nrows(), arr.ncols()), (2, 3));
+
+    let sums: Vec<i32> = arr.rows().map(|row| row.iter().sum()).collect();
+    assert_eq!(sums, [6, 15]);
+    let sums: Vec<i32> = arr.cols().map(|col| col.iter().sum()).collect();
+    assert_eq!(sums, [5, 7, 9]);
+
+    for (j, mut col) in arr.cols_mut().enumerate() {
+        col[&[1]] = j as i32;
+    }
+    arr.rows_mut()
+        .next()
+        .unwrap()
+        .iter_mut()
+        .for_each(|e| *e = -*e);
+    assert_eq!(&**arr, &[-1, -2, -3, 0, 1, 2]);
+}
+
+/// deterministic filler so parallel and serial results can be compared.
+fn ramp(len: usize, modulo: usize) -> Box<[f64]> {
+    (0..len).map(|i| (i % modulo) as f64 - 3.0).collect()
+}
+
+#[test]
+fn test_cpu_device_matmul() {
+    let (m, n, p) = (37, 19, 23);
+    let a = Arr2::new(ramp(m * n, 7), (m, n));
+    let b = Arr2::new(ramp(n * p, 5), (n, p));
+    let expected = a.try_matmul(&b).unwrap();
+
+    // a grain of 1 forces every launch onto several threads
+    let device = CpuDevice::with_threads(4).with_grain(1);
+    let lhs = device.upload(&a).unwrap();
+    let rhs = device.upload(&b).unwrap();
+    let out = device.alloc(ShapeDescriptor(slice![m, p])).unwrap();
+    device
+        .launch_kernel(&Kernel::matmul(&lhs, &rhs, &out))
+        .unwrap();
+    let result = device.download(&out).unwrap();
+    assert_eq!(&*result, &**expected);
+
+    let err = device.launch_kernel(&Kernel::matmul(&rhs, &rhs, &out));
+    assert!(matches!(
+        err,
+        Err(DeviceError::ShapeMismatch {
+            kernel: KernelOp::MatMul,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_cpu_device_elementwise_and_reduce() {
+    let device = CpuDevice::with_threads(3).with_grain(1);
+    let shape = ShapeDescriptor(slice![10, 10]);
+    let a = Tensor::new(ramp(100, 11), shape.clone());
+    let b = Tensor::new(ramp(100, 3), shape.clone());
+
+    let lhs = device.upload(&a).unwrap();
+    let rhs = device.upload(&b).unwrap();
+    let out = device.alloc(shape).unwrap();
+    device
+        .launch_kernel(&Kernel::add(&lhs, &rhs, &out))
+        .unwrap();
+    assert_eq!(&*device.download(&out).unwrap(), &*(&a + &b));
+
+    device
+        .launch_kernel(&Kernel::mul(&lhs, &rhs, &out))
+        .unwrap();
+    let product = device.download(&out).unwrap();
+    assert_eq!(product[&[4, 2]], a[&[4, 2]] * b[&[4, 2]]);
+
+    device
+        .launch_kernel(&Kernel::sub(&lhs, &rhs, &out))
+        .unwrap();
+    let difference = device.download(&out).unwrap();
+    assert_eq!(difference[&[9, 9]], a[&[9, 9]] - b[&[9, 9]]);
+
+    let total = device.alloc(ShapeDescriptor(slice![1])).unwrap();
+    device.launch_kernel(&Kernel::sum(&lhs, &total)).unwrap();
+    let expected: f64 = a.iter().sum();
+    assert_eq!(device.download(&total).unwrap()[&[0]], expected);
+
+    device.launch_kernel(&Kernel::max(&rhs, &total)).unwrap();
+    assert_eq!(device.download(&total).unwrap()[&[0]], -1.0);
+}
+
+#[test]
+fn test_cpu_device_roundtrip() {
+    let device = CpuDevice::with_threads(2);
+    assert_eq!(device.backend(), DeviceBackend::CPU);
+
+    // uploads go through the logical layout
+    let tensor = transposed(3, 4);
+    let handle = device.upload(&tensor).unwrap();
+    assert_eq!(handle.shape(), &ShapeDescriptor(slice![4, 3]));
+    let back = device.download(&handle).unwrap();
+    assert!(back.is_contiguous());
+    assert_eq!(
+        back.iter().collect::<Vec<_>>(),
+        tensor.iter_logical().collect::<Vec<_>>()
+    );
+
+    // handles share memory, freeing through one invalidates the other
+    let alias = handle.clone();
+    assert_eq!(device.live_buffers(), 1);
+    device.free(handle).unwrap();
+    assert_eq!(device.live_buffers(), 0);
+    assert_eq!(
+        device.download(&alias).err(),
+        Some(DeviceError::UnknownHandle { handle: alias.id() })
+    );
+
+    let other = CpuDevice::with_threads(1);
+    let foreign = other.upload(&tensor).unwrap();
+    assert_eq!(
+        device.download(&foreign).err(),
+        Some(DeviceError::WrongDevice {
+            expected: device.id(),
+            found: other.id(),
+        })
+    );
+}
+
+#[test]
+fn test_kernel_validate() {
+    let device = CpuDevice::with_threads(1);
+    let shape = ShapeDescriptor(slice![2, 2]);
+    let a = device
+        .upload(&Tensor::new(slice![1, 2, 3, 4], shape))
+        .unwrap();
+    let out = device.alloc::<i32>(ShapeDescriptor(slice![1])).unwrap();
+
+    assert_eq!(
+        device.launch_kernel(&Kernel::add(&a, &a, &a)),
+        Err(DeviceError::AliasedOutput {
+            kernel: KernelOp::Add
+        })
+    );
+    assert_eq!(
+        device.launch_kernel(&Kernel::new(KernelOp::Sum, &[&a, &a], &out)),
+        Err(DeviceError::Arity {
+            kernel: KernelOp::Sum,
+            expected: 1,
+            found: 2,
+        })
+    );
+    device.launch_kernel(&Kernel::sum(&a, &out)).unwrap();
+    assert_eq!(device.download(&out).unwrap()[&[0]], 10);
+
+    let empty = device.alloc::<i32>(ShapeDescriptor(slice![0])).unwrap();
+    let err = device
+        .launch_kernel(&Kernel::max(&empty, &out))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "the Max kernel failed: empty input");
+}
+
+#[test]
+fn test_simulated_device_transfers() {
+    let device = SimulatedDevice::new(DeviceBackend::CUDA);
+    assert_eq!(device.backend(), DeviceBackend::CUDA);
+    let shape = ShapeDescriptor(slice![2, 2]);
+    let a = Tensor::new(slice![1.0f32, 2.0, 3.0, 4.0], shape.clone());
+
+    let lhs = device.upload(&a).unwrap();
+    let rhs = device.upload(&a).unwrap();
+    let out = device.alloc::<f32>(shape).unwrap();
+    device
+        .launch_kernel(&Kernel::matmul(&lhs, &rhs, &out))
+        .unwrap();
+    device
+        .launch_kernel(&Kernel::add(&lhs, &rhs, &lhs))
+        .unwrap_err();
+    let result = device.download(&out).unwrap();
+    assert_eq!(&*result, &[7.0, 10.0, 15.0, 22.0]);
+
+    assert_eq!(
+        device.stats(),
+        DeviceStats {
+            uploads: 2,
+            downloads: 1,
+            bytes_uploaded: 32,
+            bytes_downloaded: 16,
+            kernel_launches: 1,
+            peak_bytes: 48,
+        }
+    );
+    device.free(out).unwrap();
+    assert_eq!(device.allocated_bytes(), 32);
+    device.reset_stats();
+    assert_eq!(device.stats().peak_bytes, 32);
+
+    // handles from the host have to be uploaded first
+    let host = CpuDevice::with_threads(1);
+    let on_host = host.upload(&a).unwrap();
+    assert_eq!(
+        device.download(&on_host).err(),
+        Some(DeviceError::HostMemory {
+            handle: on_host.id()
+        })
+    );
+    let other = SimulatedDevice::new(DeviceBackend::CUDA);
+    assert!(matches!(
+        other.download(&lhs),
+        Err(DeviceError::WrongDevice { .. })
+    ));
+
+    // only the elements a view exposes are sent, not its whole buffer
+    let data = slice![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let mut every_third = Tensor::new(data, ShapeDescriptor(slice![6]));
+    every_third.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![2]),
+        slice![3],
+    )));
+    let handle = other.upload(&every_third).unwrap();
+    assert_eq!(other.stats().bytes_uploaded, 8);
+    assert_eq!(&*other.download(&handle).unwrap(), &[1.0, 4.0]);
+}
+
+#[test]
+fn test_simulated_device_failures() {
+    let device = SimulatedDevice::new(DeviceBackend::VULKAN).with_capacity(64);
+    let a = Tensor::new(slice![1i64, 2, 3, 4], ShapeDescriptor(slice![4]));
+
+    device.inject_failure_after(FailurePoint::Upload, 1);
+    let handle = device.upload(&a).unwrap();
+    assert_eq!(
+        device.upload(&a).err(),
+        Some(DeviceError::Injected {
+            point: FailurePoint::Upload
+        })
+    );
+    // the failure only fires once
+    let second = device.upload(&a).unwrap();
+    assert_eq!(device.stats().uploads, 2);
+
+    assert_eq!(
+        device.alloc::<i64>(ShapeDescriptor(slice![1])).err(),
+        Some(DeviceError::OutOfMemory {
+            requested: 8,
+            available: 0,
+        })
+    );
+    device.free(second).unwrap();
+
+    let out = device.alloc::<i64>(ShapeDescriptor(slice![1])).unwrap();
+    device.inject_failure(FailurePoint::Launch);
+    let sum = Kernel::sum(&handle, &out);
+    assert!(device.launch_kernel(&sum).is_err());
+    device.launch_kernel(&sum).unwrap();
+    assert_eq!(device.download(&out).unwrap()[&[0]], 10);
+    assert_eq!(device.stats().kernel_launches, 1);
+
+    device.inject_failure(FailurePoint::Download);
+    device.clear_failures();
+    assert!(device.download(&out).is_ok());
+
+    // room for exactly three of `a`, however the uploads interleave
+    let device = SimulatedDevice::new(DeviceBackend::VULKAN).with_capacity(96);
+    let uploaded = std::thread::scope(|s| {
+        let uploads: Vec<_> = (0..8)
+            .map(|_| s.spawn(|| device.upload(&a).is_ok()))
+            .collect();
+        uploads
+            .into_iter()
+            .map(|u| u.join().unwrap())
+            .filter(|&ok| ok)
+            .count()
+    });
+    assert_eq!(uploaded, 3);
+    assert_eq!(device.allocated_bytes(), 96);
+    assert!(matches!(
+        device.alloc::<i64>(ShapeDescriptor(slice![usize::MAX])),
+        Err(DeviceError::OutOfMemory {
+            requested: usize::MAX,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_simd_levels_agree() {
+    // lengths that leave a remainder behind every vector width
+    for len in [0, 1, 7, 33, 131] {
+        let a: Box<[f64]> = (0..len).map(|i| (i as f64 * 0.37).sin()).collect();
+        let b: Box<[f64]> = (0..len).map(|i| (i as f64 * 0.11).cos()).collect();
+        let af: Box<[f32]> = a.iter().map(|&x| x as f32).collect();
+        let bf: Box<[f32]> = b.iter().map(|&x| x as f32).collect();
+        let p = SimdLevel::Portable;
+
+        for level in SimdLevel::available() {
+            let close = |x: f64, y: f64| (x - y).abs() < 1e-9;
+            assert!(close(f64::dot(level, &a, &b), f64::dot(p, &a, &b)));
+            assert!(close(f64::sum_slice(level, &a), f64::sum_slice(p, &a)));
+            assert!(close(
+                f64::sq_dist(level, &a, &b),
+                f64::sq_dist(p, &a, &b)
+            ));
+            assert!(close(
+                f64::l1_dist(level, &a, &b),
+                f64::l1_dist(p, &a, &b)
+            ));
+            assert_eq!(f64::max_slice(level, &a), f64::max_slice(p, &a));
+            assert_eq!(f32::max_slice(level, &af), f32::max_slice(p, &af));
+            let dot = f32::dot(level, &af, &bf) - f32::dot(p, &af, &bf);
+            assert!(dot.abs() < 1e-3);
+
+            let mut out = vec![0.0; len];
+            let mut expected = vec![0.0; len];
+            f64::mul_slices(level, &a, &b, &mut out);
+            f64::mul_slices(p, &a, &b, &mut expected);
+            assert_eq!(out, expected);
+
+            let mut y = b.to_vec();
+            let mut y_expected = b.to_vec();
+            f64::axpy(level, 2.5, &a, &mut y);
+            f64::axpy(p, 2.5, &a, &mut y_expected);
+            assert_eq!(y, y_expected);
+        }
+    }
+
+    // NaNs are skipped the same way at every level
+    let mut xs: Vec<f64> = (0..37).map(|i| (i as f64 * 0.37).sin()).collect();
+    let max = f64::max_slice(SimdLevel::Portable, &xs);
+    for i in [0, 5, 36] {
+        xs[i] = f64::NAN;
+    }
+    for level in SimdLevel::available() {
+        assert_eq!(f64::max_slice(level, &xs), max);
+        assert!(f64::max_slice(level, &[f64::NAN; 9]).unwrap().is_nan());
+    }
+}
+
+#[test]
+fn test_cpu_device_simd_selection() {
+    let device = CpuDevice::with_threads(2).with_grain(1);
+    assert_eq!(device.simd(), SimdLevel::detect());
+    let portable = CpuDevice::with_threads(2)
+        .with_grain(1)
+        .with_simd(SimdLevel::Portable);
+    assert_eq!(portable.simd(), SimdLevel::Portable);
+    assert_eq!(
+        CpuDevice::new().with_simd(SimdLevel::Avx512).simd(),
+        SimdLevel::detect()
+    );
+
+    let a: Box<[f32]> = ramp(50, 7).iter().map(|&x| x as f32).collect();
+    let b: Box<[f32]> = a.iter().rev().copied().collect();
+    assert_eq!(device.dot(&a, &b), portable.dot(&a, &b));
+    assert_eq!(device.sum(&a), portable.sum(&a));
+    assert_eq!(device.max(&a), Some(3.0));
+    assert_eq!(device.max::<f32>(&[]), None);
+
+    let mut out = vec![0.0; a.len()];
+    device.add(&a, &b, &mut out);
+    device.axpy(-1.0, &a, &mut out);
+    assert_eq!(&out[..], &b[..]);
+
+    let lhs = device
+        .upload(&Tensor::new(a.clone(), ShapeDescriptor(slice![5, 10])))
+        .unwrap();
+    let rhs = device
+        .upload(&Tensor::new(b.clone(), ShapeDescriptor(slice![50])))
+        .unwrap();
+    let total = device.alloc(ShapeDescriptor(slice![1])).unwrap();
+    device
+        .launch_kernel(&Kernel::dot(&lhs, &rhs, &total))
+        .unwrap();
+    assert_eq!(device.download(&total).unwrap()[&[0]], portable.dot(&a, &b));
+
+    let u = Arr1::new(slice![1.0, -2.0, 3.0, 4.0, 5.0]);
+    let v = Arr1::new(slice![2.0, 2.0, 2.0, 2.0, 2.0]);
+    assert_eq!(u.inner_product(&v), 22.0);
+    assert_eq!(u.manhattan(&v), 1.0 + 4.0 + 1.0 + 2.0 + 3.0);
+    assert_eq!(
+        Arr1::new(slice![3u32, 1]).manhattan(&Arr1::new(slice![1, 4])),
+        5
+    );
+
+    // types without vector kernels take the plain loops
+    let p = Arr1::new(slice![1i16, -2, 3]);
+    let q = Arr1::new(slice![4i16, 2, -1]);
+    assert_eq!(p.inner_product(&q), -3);
+    assert_eq!(p.manhattan(&q), 11);
+    let a = Arr2::new(slice![1i8, 2, 3, 4], (2, 2));
+    let b = Arr2::new(slice![10i8, 20, 30, 40], (2, 2));
+    assert_eq!(&**a.matadd(b), &[11, 22, 33, 44]);
+}
+
+#[test]
+fn test_bmm_broadcasting() {
+    let lhs = Tensor::new(ramp(2 * 3 * 4, 5), ShapeDescriptor(slice![2, 3, 4]));
+    let rhs = Tensor::new(ramp(4 * 2, 3), ShapeDescriptor(slice![4, 2]));
+    let out = lhs.bmm(&rhs);
+    assert_eq!(&**out.shape(), &[2, 3, 2]);
+
+    // every batch entry is a plain 2-d product
+    for (b, batch) in out.axis_iter(0).enumerate() {
+        let lhs2 = lhs.axis_iter(0).nth(b).unwrap().to_tensor();
+        let lhs2 = Arr2::new(lhs2.to_vec().into(), (3, 4));
+        let expected = lhs2.matmul(Arr2::new(rhs.to_vec().into(), (4, 2)));
+        assert_eq!(&*batch.to_tensor(), &**expected);
+    }
+
+    // [2, 1, m, n] x [3, n, p] -> [2, 3, m, p]
+    let lhs =
+        Tensor::new(ramp(2 * 2 * 3, 12), ShapeDescriptor(slice![2, 1, 2, 3]));
+    let rhs = Tensor::new(ramp(3 * 3 * 2, 4), ShapeDescriptor(slice![3, 3, 2]));
+    let out = lhs.bmm(&rhs);
+    assert_eq!(&**out.shape(), &[2, 3, 2, 2]);
+    let expected = (0..3)
+        .map(|k| lhs[&[1, 0, 1, k]] * rhs[&[2, k, 1]])
+        .sum::<f64>();
+    assert_eq!(out[&[1, 2, 1, 1]], expected);
+
+    assert_eq!(
+        lhs.try_bmm(&lhs).err(),
+        Some(LinalgError::ShapeMismatch {
+            op: "bmm",
+            lhs: lhs.shape().into_owned(),
+            rhs: lhs.shape().into_owned(),
+        })
+    );
+    let flat = Tensor::new(slice![1.0, 2.0], ShapeDescriptor(slice![2]));
+    assert!(matches!(
+        flat.try_bmm(&rhs),
+        Err(LinalgError::RankMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_einsum() {
+    let a = Tensor::new(ramp(2 * 3 * 4, 5), ShapeDescriptor(slice![2, 3, 4]));
+    let b = Tensor::new(ramp(2 * 4 * 5, 7), ShapeDescriptor(slice![2, 4, 5]));
+    let batched = einsum("bij,bjk->bik", &[&a, &b]);
+    assert_eq!(&**batched.shape(), &[2, 3, 5]);
+    assert_eq!(&*batched, &*a.bmm(&b));
+
+    // implicit output, transposes, traces and outer products
+    let m = Tensor::new(
+        slice![1, 2, 3, 4, 5, 6, 7, 8, 9],
+        ShapeDescriptor(slice![3, 3]),
+    );
+    assert_eq!(
+        &*einsum("ij,jk", &[&m, &m]),
+        &*einsum("ij,jk->ik", &[&m, &m])
+    );
+    assert_eq!(&*einsum("ij->ji", &[&m]), &[1, 4, 7, 2, 5, 8, 3, 6, 9]);
+    assert_eq!(einsum("ii->", &[&m])[&[]], 15);
+    assert_eq!(&*einsum("ii->i", &[&m]), &[1, 5, 9]);
+    let v = Tensor::new(slice![1, 2], ShapeDescriptor(slice![2]));
+    assert_eq!(&*einsum("i,j", &[&v, &v]), &[1, 2, 2, 4]);
+
+    // operands with a transform are read in logical order
+    let t = transposed(2, 3);
+    assert_eq!(&*einsum("ij->ij", &[&t]), &*t.contiguous());
+
+    assert!(matches!(
+        try_einsum("ij,jk->il", &[&m, &m]),
+        Err(LinalgError::InvalidSubscripts { .. })
+    ));
+    assert!(matches!(
+        try_einsum("ij", &[&m, &m]),
+        Err(LinalgError::InvalidSubscripts { .. })
+    ));
+    assert_eq!(
+        try_einsum(
+            "i,i->",
+            &[
+                &v,
+                &Tensor::new(slice![1, 2, 3], ShapeDescriptor(slice![3]))
+            ]
+        )
+        .err(),
+        Some(LinalgError::ShapeMismatch {
+            op: "einsum",
+            lhs: ShapeDescriptor(slice![2]),
+            rhs: ShapeDescriptor(slice![3]),
+        })
+    );
+    assert_eq!(
+        try_einsum("ijk", &[&m]).err().unwrap().to_string(),
+        "einsum expects a rank 3 tensor but got rank 2"
+    );
+}
+
+/// max elementwise distance of two equally shaped matrices.
+fn max_diff(a: &Arr2<f64>, b: &Arr2<f64>) -> f64 {
+    assert_eq!(a.shape(), b.shape());
+    a.iter_logical()
+        .zip(b.iter_logical())
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+fn arr2(rows: &[&[f64]]) -> Arr2<f64> {
+    let data: Box<[f64]> =
+        rows.iter().flat_map(|r| r.iter().copied()).collect();
+    Arr2::new(data, (rows.len(), rows[0].len()))
+}
+
+fn transpose(a: &Arr2<f64>) -> Arr2<f64> {
+    let data = a.cols().flat_map(|c| c.iter().copied().collect::<Vec<_>>());
+    Arr2::new(data.collect(), (a.ncols(), a.nrows()))
+}
+
+fn diag(values: &[f64]) -> Arr2<f64> {
+    let n = values.len();
+    let data =
+        (0..n * n).map(|f| if f / n == f % n { values[f / n] } else { 0.0 });
+    Arr2::new(data.collect(), (n, n))
+}
+
+#[test]
+fn test_lu_det_inv_solve() {
+    let a = arr2(&[&[2.0, 1.0, 1.0], &[4.0, -6.0, 0.0], &[-2.0, 7.0, 2.0]]);
+    let lu = a.lu();
+    let pa: Box<[f64]> = lu
+        .permutation()
+        .iter()
+        .flat_map(|&r| {
+            a.rows().nth(r).unwrap().iter().copied().collect::<Vec<_>>()
+        })
+        .collect();
+    let lu_product = lu.l().matmul(lu.u());
+    assert!(max_diff(&Arr2::new(pa, (3, 3)), &lu_product) < 1e-12);
+    assert!((a.det() - -16.0).abs() < 1e-12);
+
+    let inv = a.inv();
+    assert!(
+        max_diff(
+            &inv.matmul(arr2(&[
+                &[2.0, 1.0, 1.0],
+                &[4.0, -6.0, 0.0],
+                &[-2.0, 7.0, 2.0]
+            ])),
+            &Arr2::identity(3)
+        ) < 1e-12
+    );
+    let b = arr2(&[&[5.0], &[-2.0], &[9.0]]);
+    let x = a.solve(&b);
+    assert!(max_diff(&x, &arr2(&[&[1.0], &[1.0], &[2.0]])) < 1e-12);
+
+    let singular = arr2(&[&[1.0, 2.0], &[2.0, 4.0]]);
+    assert_eq!(singular.det(), 0.0);
+    assert_eq!(
+        singular.try_inv().err(),
+        Some(LinalgError::Singular { op: "inv" })
+    );
+    assert!(matches!(
+        arr2(&[&[1.0, 2.0]]).try_det(),
+        Err(LinalgError::NotSquare { op: "det", .. })
+    ));
+}
+
+#[test]
+fn test_qr_and_cholesky() {
+    for a in [
+        arr2(&[
+            &[12.0, -51.0, 4.0],
+            &[6.0, 167.0, -68.0],
+            &[-4.0, 24.0, -41.0],
+            &[1.0, 2.0, 3.0],
+        ]),
+        arr2(&[&[1.0, 2.0, 3.0, 4.0], &[0.0, 1.0, 0.0, 2.0]]),
+    ] {
+        let (m, n) = (a.nrows(), a.ncols());
+        let qr = a.qr();
+        let k = m.min(n);
+        assert_eq!((qr.q.nrows(), qr.q.ncols(), qr.r.nrows()), (m, k, k));
+        let qtq =
+            transpose(&qr.q).matmul(Arr2::new(qr.q.to_vec().into(), (m, k)));
+        assert!(max_diff(&qtq, &Arr2::identity(k)) < 1e-12);
+        assert!(qr.r.indexed_iter().all(|(i, &x)| i[1] >= i[0] || x == 0.0));
+        assert!(max_diff(&qr.q.matmul(qr.r), &a) < 1e-10);
+    }
+
+    let b = arr2(&[&[1.0, 2.0, 0.0], &[0.0, 1.0, 3.0], &[2.0, 0.0, 1.0]]);
+    let spd = b.matmul(transpose(&arr2(&[
+        &[1.0, 2.0, 0.0],
+        &[0.0, 1.0, 3.0],
+        &[2.0, 0.0, 1.0],
+    ])));
+    let l = spd.cholesky().l;
+    assert!(l.indexed_iter().all(|(i, &x)| i[1] <= i[0] || x == 0.0));
+    let llt = Arr2::new(l.to_vec().into(), (3, 3)).matmul(transpose(&l));
+    assert!(max_diff(&llt, &spd) < 1e-12);
+    assert_eq!(
+        arr2(&[&[1.0, 2.0], &[2.0, 1.0]]).try_cholesky().err(),
+        Some(LinalgError::NotPositiveDefinite)
+    );
+}
+
+#[test]
+fn test_symmetric_eigen() {
+    let a = arr2(&[&[4.0, 1.0, 2.0], &[1.0, 3.0, 0.0], &[2.0, 0.0, 5.0]]);
+    let eig = a.symmetric_eigen();
+    assert!(eig.values.windows(2).all(|w| w[0] >= w[1]));
+    assert!((eig.values.iter().sum::<f64>() - 12.0).abs() < 1e-12);
+
+    // A = V diag(λ) Vᵀ with orthonormal V
+    let v = &eig.vectors;
+    let rebuilt = Arr2::new(v.to_vec().into(), (3, 3))
+        .matmul(diag(&eig.values))
+        .matmul(transpose(v));
+    assert!(max_diff(&rebuilt, &a) < 1e-12);
+    let vtv = transpose(v).matmul(Arr2::new(v.to_vec().into(), (3, 3)));
+    assert!(max_diff(&vtv, &Arr2::identity(3)) < 1e-12);
+
+    let a32 = a.iter_logical().map(|&x| x as f32).collect();
+    let values = Arr2::new(a32, (3, 3)).symmetric_eigen().values;
+    let close = |(&x, &y): (&f32, &f64)| (x as f64 - y).abs() < 1e-4;
+    assert!(values.iter().zip(&eig.values).all(close));
+
+    assert_eq!(
+        arr2(&[&[1.0, 2.0], &[0.0, 1.0]])
+            .try_symmetric_eigen()
+            .err(),
+        Some(LinalgError::NotSymmetric {
+            op: "symmetric_eigen"
+        })
+    );
+}
+
+#[test]
+fn test_svd_rank_pinv_lstsq() {
+    for a in [
+        arr2(&[&[3.0, 2.0, 2.0], &[2.0, 3.0, -2.0]]),
+        arr2(&[&[1.0, 0.0], &[1.0, 1.0], &[1.0, 2.0], &[1.0, 3.0]]),
+    ] {
+        let svd = a.svd();
+        assert!(svd.s.windows(2).all(|w| w[0] >= w[1]));
+        let k = svd.s.len();
+        let usv = Arr2::new(svd.u.to_vec().into(), (a.nrows(), k))
+            .matmul(diag(&svd.s))
+            .matmul(Arr2::new(svd.vt.to_vec().into(), (k, a.ncols())));
+        assert!(max_diff(&usv, &a) < 1e-12);
+        assert_eq!(a.rank(), k);
+    }
+    let wide = arr2(&[&[3.0, 2.0, 2.0], &[2.0, 3.0, -2.0]]).svd();
+    assert!((wide.s[0] - 5.0).abs() < 1e-12 && (wide.s[1] - 3.0).abs() < 1e-12);
+
+    // rank deficient: A pinv(A) A = A
+    let a = arr2(&[&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0], &[1.0, 0.0, 1.0]]);
+    assert_eq!(a.rank(), 2);
+    let pinv = a.pinv();
+    let apa = Arr2::new(a.to_vec().into(), (3, 3))
+        .matmul(pinv)
+        .matmul(Arr2::new(a.to_vec().into(), (3, 3)));
+    assert!(max_diff(&apa, &a) < 1e-12);
+
+    // fits y = 1 + 2x exactly
+    let design = arr2(&[&[1.0, 0.0], &[1.0, 1.0], &[1.0, 2.0], &[1.0, 3.0]]);
+    let y = arr2(&[&[1.0], &[3.0], &[5.0], &[7.0]]);
+    assert!(max_diff(&design.lstsq(&y), &arr2(&[&[1.0], &[2.0]])) < 1e-12);
+
+    let single = Arr2::new(slice![4.0f32, 0.0, 0.0, 9.0], (2, 2));
+    assert_eq!(&*single.svd().s, &[9.0, 4.0]);
+    assert!((single.det() - 36.0).abs() < 1e-5);
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-12
+}
+
+fn close3(a: Vec3<f64>, b: Vec3<f64>) -> bool {
+    (a - b).length() < 1e-12
+}
+
+#[test]
+fn test_vec2_geometry() {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+    let v = Vec2::new(3.0, 4.0);
+    assert_eq!((v.x(), v.y()), (3.0, 4.0));
+    assert_eq!(v.length(), 5.0);
+    assert_eq!(v.normalize(), Vec2::new(0.6, 0.8));
+    assert_eq!(Vec2::new(0.0, 0.0).try_normalize(), None);
+    assert_eq!(v * 2.0, Vec2::new(6.0, 8.0));
+    assert_eq!(v / 2.0, Vec2::new(1.5, 2.0));
+    assert_eq!(v.lerp(Vec2::new(5.0, 0.0), 0.5), Vec2::new(4.0, 2.0));
+    assert_eq!(Vec2::new(1, 0).cross(Vec2::new(0, 1)), 1);
+
+    let (ex, ey) = (Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0));
+    assert!(close(ex.angle(ey), FRAC_PI_2));
+    assert!(close(ex.angle(Vec2::new(1.0, 1.0)), FRAC_PI_4));
+    assert_eq!(v.project(ex), Vec2::new(3.0, 0.0));
+    assert_eq!(v.reflect(ey), Vec2::new(3.0, -4.0));
+
+    let r = v.rotate(FRAC_PI_2);
+    assert!(close(r.x(), -4.0) && close(r.y(), 3.0));
+    let m = Vec2::rotation_matrix(FRAC_PI_2);
+    let mv = m.matmul(Arr2::new(slice![3.0, 4.0], (2, 1)));
+    assert!(close(mv[[0, 0]], r.x()) && close(mv[[1, 0]], r.y()));
+
+    let arr: Arr1<f64> = v.into();
+    assert_eq!(Vec2::try_from(arr).ok(), Some(v));
+    assert_eq!(
+        Vec2::try_from(Arr1::new(slice![1.0, 2.0, 3.0])),
+        Err(LinalgError::DataLength {
+            expected: 2,
+            found: 3
+        })
+    );
+}
+
+#[test]
+fn test_vec3_geometry_and_quaternions() {
+    use std::f64::consts::FRAC_PI_2;
+    let (ex, ey, ez) = (
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    );
+    assert_eq!(ex.cross(ey), ez);
+    assert_eq!(ey.cross(ex), -ez);
+    let v = Vec3::new(2.0, 3.0, 6.0);
+    assert_eq!((v.x(), v.y(), v.z()), (2.0, 3.0, 6.0));
+    assert_eq!(v.length(), 7.0);
+    assert!(close(v.normalize().length(), 1.0));
+    assert!(close(ex.angle(ez), FRAC_PI_2));
+    assert_eq!(v.project(ez), Vec3::new(0.0, 0.0, 6.0));
+    assert_eq!(v.reflect(ey), Vec3::new(2.0, -3.0, 6.0));
+    assert_eq!(ex.lerp(ez, 0.5), Vec3::new(0.5, 0.0, 0.5));
+
+    // a quarter turn around z takes x to y, matrix and quaternion agree
+    assert!(close3(ex.rotate(ez, FRAC_PI_2), ey));
+    let q = Quat::from_axis_angle(ez, FRAC_PI_2);
+    assert!(close(q.norm(), 1.0));
+    let m = Vec3::rotation_matrix(Vec3::new(1.0, 1.0, 1.0), 1.0);
+    let q = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 1.0), 1.0);
+    let mv = m.matmul(Arr2::new(slice![2.0, 3.0, 6.0], (3, 1)));
+    assert!(close3(
+        q.rotate(v),
+        Vec3::new(mv[[0, 0]], mv[[1, 0]], mv[[2, 0]])
+    ));
+    assert!(close(q.rotate(v).length(), 7.0));
+
+    // composition, inverse and slerp
+    let a = Quat::from_axis_angle(ex, 0.3);
+    let b = Quat::from_axis_angle(ey, -1.1);
+    assert!(close3((a * b).rotate(v), a.rotate(b.rotate(v))));
+    assert!(close3((a * a.inverse()).rotate(v), v));
+    assert!(close3(a.conjugate().rotate(a.rotate(v)), v));
+    let half =
+        Quat::identity().slerp(Quat::from_axis_angle(ez, FRAC_PI_2), 0.5);
+    let expected = Quat::from_axis_angle(ez, FRAC_PI_2 / 2.0);
+    assert!(close(half.dot(expected), 1.0));
+
+    let arr: Arr1<f64> = v.into();
+    assert_eq!(Vec3::try_from(arr).ok(), Some(v));
+}
+
+#[test]
+fn test_rng() {
+    let (mut a, mut b) = (Rng::seed_from_u64(7), Rng::seed_from_u64(7));
+    let xs: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+    assert_eq!(xs, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+    assert_ne!(xs[0], Rng::seed_from_u64(8).next_u64());
+
+    // resuming from a saved state continues the same stream
+    let mut resumed = Rng::from_state(a.state());
+    assert_eq!(resumed.next_u64(), a.next_u64());
+
+    for _ in 0..1000 {
+        let f = a.next_f64();
+        assert!((0.0..1.0).contains(&f));
+        assert!(a.below(3) < 3);
+    }
+
+    let mut perm: Vec<usize> = (0..50).collect();
+    a.shuffle(&mut perm);
+    assert_ne!(perm, (0..50).collect::<Vec<_>>());
+    perm.sort_unstable();
+    assert_eq!(perm, (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_stack() {
+    let a = Tensor::new(slice![1, 2, 3, 4], ShapeDescriptor(slice![2, 2]));
+    let b = a.contiguous().reshape(&[2, 2]);
+    let stacked = Tensor::stack(&[a, b]);
+    assert_eq!(stacked.shape().as_ref(), &ShapeDescriptor(slice![2, 2, 2]));
+    assert_eq!(stacked.data(), &[1, 2, 3, 4, 1, 2, 3, 4]);
+
+    let c = Tensor::new(slice![1, 2, 3, 4], ShapeDescriptor(slice![4]));
+    assert!(matches!(
+        Tensor::try_stack(&[stacked, c]),
+        Err(LinalgError::ShapeMismatch { op: "stack", .. })
+    ));
+    let empty = Tensor::<f32>::stack(&[]);
+    assert_eq!(empty.shape().as_ref(), &ShapeDescriptor(slice![0]));
+}
+
+/// 10 samples of 3 features, the target of each is its index.
+fn counting_dataset() -> TensorDataset<f32, u32> {
+    let inputs = (0..30).map(|i| i as f32).collect();
+    let targets = (0..10).collect();
+    TensorDataset::new(
+        Tensor::new(inputs, ShapeDescriptor(slice![10, 3])),
+        Tensor::new(targets, ShapeDescriptor(slice![10, 1])),
+    )
+}
+
+fn collect_batches<D: Dataset + 'static>(
+    loader: &mut DataLoader<D>,
+) -> Vec<Batch<D::Input, D::Target>> {
+    loader.iter().map(|b| b.ok().unwrap()).collect()
+}
+
+#[test]
+fn test_dataset() {
+    let dataset = counting_dataset();
+    assert_eq!(dataset.len(), 10);
+    let (input, target) = dataset.get(4);
+    assert_eq!(input.data(), &[12.0, 13.0, 14.0]);
+    assert_eq!(target.data(), &[4]);
+
+    let mismatched = TensorDataset::try_new(
+        Tensor::new(slice![0.0; 6], ShapeDescriptor(slice![3, 2])),
+        Tensor::new(slice![0; 2], ShapeDescriptor(slice![2])),
+    );
+    assert!(matches!(
+        mismatched.err(),
+        Some(LinalgError::ShapeMismatch { op: "dataset", .. })
+    ));
+}
+
+#[test]
+fn test_data_loader_batches() {
+    let mut loader = DataLoader::new(counting_dataset(), 4);
+    assert_eq!(loader.len(), 3);
+    let batches = collect_batches(&mut loader);
+    assert_eq!(batches.len(), 3);
+    assert_eq!(
+        batches[0].inputs.shape().as_ref(),
+        &ShapeDescriptor(slice![4, 3])
+    );
+    assert_eq!(&batches[0].indices[..], &[0, 1, 2, 3]);
+    assert_eq!(batches[1].targets.data(), &[4, 5, 6, 7]);
+    // the last one is short
+    assert_eq!(
+        batches[2].targets.shape().as_ref(),
+        &ShapeDescriptor(slice![2, 1])
+    );
+
+    let mut loader =
+        DataLoader::new(counting_dataset(), 4).with_drop_last(true);
+    assert_eq!(loader.len(), 2);
+    assert_eq!(loader.iter().len(), 2);
+}
+
+#[test]
+fn test_data_loader_shuffle() {
+    let seen = |batches: &[Batch<f32, u32>]| -> Vec<usize> {
+        batches
+            .iter()
+            .flat_map(|b| b.indices.iter().copied())
+            .collect()
+    };
+    let mut a = DataLoader::new(counting_dataset(), 3).with_shuffle(42);
+    let mut b = DataLoader::new(counting_dataset(), 3).with_shuffle(42);
+
+    let first = seen(&collect_batches(&mut a));
+    assert_eq!(first, seen(&collect_batches(&mut b)));
+    let mut sorted = first.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+
+    // a new epoch reshuffles and targets still follow their inputs
+    let second = collect_batches(&mut a);
+    assert_eq!(a.epoch(), 2);
+    assert_ne!(first, seen(&second));
+    for batch in &second {
+        let targets: Vec<usize> =
+            batch.targets.iter().map(|&t| t as usize).collect();
+        assert_eq!(&targets[..], &batch.indices[..]);
+        assert_eq!(batch.inputs[&[0, 0][..]], batch.indices[0] as f32 * 3.0);
+    }
+
+    // the next seed doesn't replay this one an epoch late
+    let mut c = DataLoader::new(counting_dataset(), 3).with_shuffle(43);
+    assert_ne!(seen(&second), seen(&collect_batches(&mut c)));
+
+    // nor does a seed that differs by the epoch's own bits
+    const K: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut d = DataLoader::new(counting_dataset(), 3).with_shuffle(K);
+    let mut e = DataLoader::new(counting_dataset(), 3)
+        .with_shuffle(0)
+        .with_epoch(1);
+    assert_ne!(
+        seen(&collect_batches(&mut d)),
+        seen(&collect_batches(&mut e))
+    );
+}
+
+#[test]
+fn test_data_loader_prefetch() {
+    let mut plain = DataLoader::new(counting_dataset(), 3).with_shuffle(1);
+    let mut prefetched = DataLoader::new(counting_dataset(), 3)
+        .with_shuffle(1)
+        .with_prefetch(3, 2);
+    for _ in 0..2 {
+        let expected = collect_batches(&mut plain);
+        let batches = collect_batches(&mut prefetched);
+        assert_eq!(batches.len(), expected.len());
+        for (b, e) in batches.iter().zip(&expected) {
+            assert_eq!(b.indices, e.indices);
+            assert_eq!(b.inputs.data(), e.inputs.data());
+        }
+    }
+
+    // stopping early hangs up on the workers instead of blocking
+    let mut early = prefetched.iter();
+    assert!(early.next().is_some());
+    drop(early);
+}
+
+const IRIS: &str = "\
+sepal,petal,\"species, common\"
+5.1,1.4,setosa
+4.9,NA,setosa
+6.3,4.9,virginica
+5.8,,versicolor
+";
+
+#[test]
+fn test_csv_columns() {
+    let data = CsvReader::<f64>::new()
+        .with_columns(["petal", "sepal"])
+        .with_missing(Missing::Fill(-1.0))
+        .read_str(IRIS)
+        .unwrap();
+    assert_eq!(data.feature_names, ["petal", "sepal"]);
+    assert_eq!(
+        data.features.shape().as_ref(),
+        &ShapeDescriptor(slice![4, 2])
+    );
+    assert_eq!(
+        data.features.data(),
+        &[1.4, 5.1, -1.0, 4.9, 4.9, 6.3, -1.0, 5.8]
+    );
+    assert!(data.targets.is_none());
+
+    // without a header columns are numbered
+    let data = CsvReader::<f32>::new()
+        .with_header(false)
+        .with_delimiter(';')
+        .with_target(0)
+        .read_str("1;2;3\n4;5;6\n")
+        .unwrap();
+    assert_eq!(data.feature_names, ["1", "2"]);
+    assert_eq!(data.features.data(), &[2.0, 3.0, 5.0, 6.0]);
+    assert_eq!(data.targets.unwrap().data(), &[1.0, 4.0]);
+}
+
+#[test]
+fn test_csv_one_hot_target() {
+    let data = CsvReader::<f32>::new()
+        .with_target("species, common")
+        .with_one_hot("species, common")
+        .with_missing(Missing::Skip)
+        .read_str(IRIS)
+        .unwrap();
+    assert_eq!(data.feature_names, ["sepal", "petal"]);
+    assert_eq!(
+        data.target_names,
+        ["species, common=setosa", "species, common=virginica"]
+    );
+    assert_eq!(data.features.data(), &[5.1, 1.4, 6.3, 4.9]);
+    let targets = data.targets.as_ref().unwrap();
+    assert_eq!(targets.data(), &[1.0, 0.0, 0.0, 1.0]);
+
+    let dataset = data.into_dataset().unwrap();
+    assert_eq!(dataset.len(), 2);
+    assert_eq!(dataset.get(1).1.data(), &[0.0, 1.0]);
+}
+
+#[test]
+fn test_csv_errors() {
+    let reader = CsvReader::<f64>::new().with_one_hot(2);
+    assert_eq!(
+        reader.read_str(IRIS).err(),
+        Some(LinalgError::Csv {
+            line: 3,
+            reason: "missing value in column \"petal\"".into()
+        })
+    );
+    let bad = CsvReader::<f64>::new().read_str("a,b\n1,x\n").err();
+    assert!(matches!(bad, Some(LinalgError::Csv { line: 2, .. })));
+    let ragged = CsvReader::<f64>::new().read_str("a,b\n1,2,3\n").err();
+    assert!(matches!(ragged, Some(LinalgError::Csv { line: 2, .. })));
+    let unknown = CsvReader::<f64>::new().with_target("c").read_str("\na,b\n");
+    assert!(matches!(
+        unknown.err(),
+        Some(LinalgError::Csv { line: 2, .. })
+    ));
+    let unknown = CsvReader::<f64>::new()
+        .with_header(false)
+        .with_columns([2])
+        .read_str("1,2\n");
+    assert!(matches!(unknown.err(), Some(LinalgError::CsvColumn { .. })));
+    let untargeted = CsvReader::<f64>::new().read_str("a,b\n1,2\n").unwrap();
+    assert!(matches!(
+        untargeted.into_dataset().err(),
+        Some(LinalgError::CsvColumn { .. })
+    ));
+    let missing = CsvReader::<f64>::new().read_path("/nonexistent/x.csv");
+    assert!(matches!(
+        missing.err(),
+        Some(LinalgError::Io {
+            kind: std::io::ErrorKind::NotFound,
+            ..
+        })
+    ));
+}
+
+/// an idx file of `dims` holding `payload`, which is already big-endian.
+fn idx_bytes(dtype: u8, dims: &[u32], payload: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0, 0, dtype, dims.len() as u8];
+    for d in dims {
+        bytes.extend(d.to_be_bytes());
+    }
+    bytes.extend(payload);
+    bytes
+}
+
+#[test]
+fn test_idx() {
+    // two 2x3 "images" like the MNIST image files
+    let pixels: Vec<u8> = (0..12).map(|i| i * 20).collect();
+    let bytes = idx_bytes(0x08, &[2, 2, 3], &pixels);
+    let path = std::env::temp_dir().join("linalg-test-images.idx3-ubyte");
+    std::fs::write(&path, &bytes).unwrap();
+    let images = Idx::read_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(images.shape().as_ref(), &ShapeDescriptor(slice![2, 2, 3]));
+    let images = images.into_u8().unwrap();
+    assert_eq!(images.data(), &pixels[..]);
+    assert_eq!(images[&[1, 0, 2][..]], 160);
+
+    let labels = idx_bytes(0x08, &[3], &[7, 2, 1]);
+    let labels = Idx::read(&labels[..]).unwrap().into_f32();
+    assert_eq!(labels.data(), &[7.0, 2.0, 1.0]);
+
+    let floats: Vec<u8> = [1.5f32, -2.0]
+        .iter()
+        .flat_map(|f| f.to_be_bytes())
+        .collect();
+    let floats = Idx::read(&idx_bytes(0x0D, &[1, 2], &floats)[..]).unwrap();
+    assert!(matches!(&floats, Idx::F32(t) if t.data() == [1.5, -2.0]));
+    let shorts: Vec<u8> =
+        [-300i16, 5].iter().flat_map(|s| s.to_be_bytes()).collect();
+    let shorts = Idx::read(&idx_bytes(0x0B, &[2], &shorts)[..]).unwrap();
+    assert_eq!(shorts.into_f32().data(), &[-300.0, 5.0]);
+}
+
+#[test]
+fn test_idx_errors() {
+    let format = |bytes: &[u8]| match Idx::read(bytes) {
+        Err(LinalgError::Format { format, .. }) => format,
+        _ => "",
+    };
+    assert_eq!(format(&[1, 0, 8, 1, 0, 0, 0, 1, 0]), "idx");
+    assert_eq!(format(&idx_bytes(0x07, &[1], &[0])), "idx");
+    // the header promises more than the payload holds
+    assert_eq!(format(&idx_bytes(0x08, &[2, 2], &[1, 2, 3])), "idx");
+    assert_eq!(format(&[0, 0, 8, 2, 0, 0]), "idx");
+    assert_eq!(format(&idx_bytes(0x08, &[u32::MAX; 4], &[])), "idx");
+
+    let floats = Idx::read(&idx_bytes(0x0E, &[1], &[0; 8])[..]).unwrap();
+    assert!(floats.into_u8().is_err());
+}
+
+/// a version 1.0 `.npy` with the given header dict, unpadded.
+fn npy_bytes(dict: &str, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((dict.len() as u16).to_le_bytes());
+    bytes.extend(dict.bytes());
+    bytes.extend(payload);
+    bytes
+}
+
+#[test]
+fn test_npy_roundtrip() {
+    let tensor = Tensor::new(
+        slice![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0],
+        ShapeDescriptor(slice![2, 3]),
+    );
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &tensor).unwrap();
+    // the data starts 64 byte aligned after a '\n' terminated dict
+    assert_eq!((bytes.len() - 24) % 64, 0);
+    assert_eq!(bytes[bytes.len() - 25], b'\n');
+    let header = std::str::from_utf8(&bytes[10..bytes.len() - 24]).unwrap();
+    assert!(header.starts_with(
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"
+    ));
+    let back = read_npy::<f32>(&bytes[..]).unwrap();
+    assert_eq!(back.shape(), tensor.shape());
+    assert_eq!(back.data(), tensor.data());
+
+    // transposed tensors are written in their logical order
+    let mut transposed = tensor.clone();
+    transposed.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![3, 2]),
+        slice![1, 3],
+    )));
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &transposed).unwrap();
+    let back = read_npy::<f32>(&bytes[..]).unwrap();
+    assert_eq!(back.data(), &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+    let mut bytes = Vec::new();
+    let one = Tensor::new(slice![7u8, 8, 9], ShapeDescriptor(slice![3]));
+    write_npy(&mut bytes, &one).unwrap();
+    assert!(String::from_utf8_lossy(&bytes).contains("'|u1'"));
+    assert!(String::from_utf8_lossy(&bytes).contains("(3,)"));
+}
+
+#[test]
+fn test_npy_read() {
+    // big-endian and fortran ordered, as numpy writes `np.asfortranarray`
+    let payload: Vec<u8> = [1, 4, 2, 5, 3, 6]
+        .iter()
+        .flat_map(|x: &i32| x.to_be_bytes())
+        .collect();
+    let dict = "{'descr':'>i4','fortran_order':True,'shape':(2L, 3L)}";
+    let tensor = read_npy::<i32>(&npy_bytes(dict, &payload)[..]).unwrap();
+    assert_eq!(tensor.shape().as_ref(), &ShapeDescriptor(slice![2, 3]));
+    assert!(!tensor.is_contiguous());
+    assert_eq!(tensor[&[0, 2][..]], 3);
+    assert_eq!(tensor.to_owned_layout().data(), &[1, 2, 3, 4, 5, 6]);
+
+    let dict = "{'shape': (), 'fortran_order': False, 'descr': '<f8', }";
+    let scalar = read_npy::<f64>(&npy_bytes(dict, &2.5f64.to_le_bytes())[..]);
+    assert_eq!(scalar.unwrap().data(), &[2.5]);
+
+    let format = |bytes: &[u8]| match read_npy::<f64>(bytes) {
+        Err(LinalgError::Format { format, .. }) => format,
+        _ => "",
+    };
+    let f4 = "{'descr': '<f4', 'fortran_order': False, 'shape': (1,), }";
+    assert_eq!(format(&npy_bytes(f4, &[0; 4])), "npy");
+    let short = "{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }";
+    assert_eq!(format(&npy_bytes(short, &[0; 8])), "npy");
+    assert_eq!(format(&npy_bytes("{'descr': '<f8'}", &[])), "npy");
+    assert_eq!(format(b"\x93NUMPX\x01\x00\x00\x00"), "npy");
+}
+
+#[test]
+fn test_npz() {
+    let weights = Tensor::new(
+        slice![0.5f64, -1.0, 2.0, 0.25],
+        ShapeDescriptor(slice![2, 2]),
+    );
+    let steps = Tensor::new(slice![3i64], ShapeDescriptor(slice![1]));
+    let mut writer = NpzWriter::new(Vec::new());
+    writer
+        .add("weights", &weights)
+        .unwrap()
+        .add("steps", &steps)
+        .unwrap();
+    let mut bytes = writer.finish().unwrap();
+
+    let npz = Npz::read(&bytes[..]).unwrap();
+    assert_eq!(npz.names().collect::<Vec<_>>(), ["weights", "steps"]);
+    let back = npz.get::<f64>("weights").unwrap();
+    assert_eq!(back.shape(), weights.shape());
+    assert_eq!(back.data(), weights.data());
+    assert_eq!(npz.get::<i64>("steps").unwrap().data(), &[3]);
+    assert!(npz.get::<f32>("weights").is_err());
+    assert!(npz.get::<f64>("bias").is_err());
+
+    // flipping a data byte breaks the checksum
+    let at = bytes.len() / 3;
+    bytes[at] ^= 0xff;
+    assert!(matches!(
+        Npz::read(&bytes[..]).err(),
+        Some(LinalgError::Format { format: "npz", .. })
+    ));
+    assert!(Npz::read(&b"not a zip"[..]).is_err());
+}
+
+/// a safetensors file with the given header, unpadded.
+fn safetensors_bytes(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+    bytes.extend(header.bytes());
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn test_safetensors_roundtrip() {
+    let weight = Tensor::new(
+        slice![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0],
+        ShapeDescriptor(slice![2, 3]),
+    );
+    let bias = Tensor::new(slice![0.5f32, -0.5], ShapeDescriptor(slice![2]));
+    let bytes = safetensors::serialize(
+        &[("layer.weight", &weight), ("layer.bias", &bias)],
+        &[("format", "pt")],
+    )
+    .unwrap();
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+    assert_eq!(header_len % 8, 0);
+    assert_eq!(bytes.len() as u64, 8 + header_len + 8 * 4);
+
+    let file = SafeTensors::deserialize(&bytes).unwrap();
+    assert_eq!(
+        file.names().collect::<Vec<_>>(),
+        ["layer.weight", "layer.bias"]
+    );
+    assert_eq!(file.metadata(), &[("format".into(), "pt".into())]);
+    let view = file.view("layer.bias").unwrap();
+    assert_eq!((view.dtype(), view.data().len()), ("F32", 8));
+    // views point into the original buffer
+    assert!(bytes.as_ptr_range().contains(&view.data().as_ptr()));
+    let back = file.tensor::<f32>("layer.weight").unwrap();
+    assert_eq!(back.shape(), weight.shape());
+    assert_eq!(back.data(), weight.data());
+    assert!(file.tensor::<f64>("layer.weight").is_err());
+
+    let path = std::env::temp_dir().join("linalg-test.safetensors");
+    safetensors::save(&path, &[("w", &weight)], &[]).unwrap();
+    let loaded = safetensors::load::<f32>(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded["w"].data(), weight.data());
+}
+
+#[test]
+fn test_safetensors_read() {
+    // written the way the reference implementation lays it out, with the
+    // tensors stored in a different order than the header lists them
+    let header = r#"{"b":{"dtype":"I64","shape":[1],"data_offsets":[8,16]},
+        "__metadata__":{"note":"a \"quoted\" \u00e9"},
+        "a":{"dtype":"F64","shape":[],"data_offsets":[0,8]}}   "#;
+    let mut data = 2.5f64.to_le_bytes().to_vec();
+    data.extend((-7i64).to_le_bytes());
+    let bytes = safetensors_bytes(header, &data);
+    let file = SafeTensors::deserialize(&bytes).unwrap();
+    assert_eq!(file.names().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(file.metadata()[0].1, "a \"quoted\" \u{e9}");
+    assert_eq!(file.tensor::<f64>("a").unwrap().data(), &[2.5]);
+    assert_eq!(file.tensor::<i64>("b").unwrap().data(), &[-7]);
+
+    let invalid = |header: &str, data: &[u8]| {
+        matches!(
+            SafeTensors::deserialize(&safetensors_bytes(header, data)),
+            Err(LinalgError::Format {
+                format: "safetensors",
+                ..
+            })
+        )
+    };
+    let one = r#"{"a":{"dtype":"F32","shape":[2],"data_offsets":[0,8]}}"#;
+    assert!(!invalid(one, &[0; 8]));
+    assert!(invalid(one, &[0; 4]));
+    assert!(invalid(one, &[0; 12]));
+    let gap = r#"{"a":{"dtype":"F32","shape":[1],"data_offsets":[4,8]}}"#;
+    assert!(invalid(gap, &[0; 8]));
+    let wrong = r#"{"a":{"dtype":"F32","shape":[3],"data_offsets":[0,8]}}"#;
+    assert!(invalid(wrong, &[0; 8]));
+    assert!(invalid(r#"{"a":{"dtype":"Q7","shape":[]}}"#, &[]));
+    assert!(invalid(r#"["a"]"#, &[]));
+    assert!(invalid(r#"{"a":"#, &[]));
+    assert!(SafeTensors::deserialize(&[1, 0]).is_err());
+}
+
+#[test]
+fn test_checkpoint_roundtrip() {
+    let mut rng = Rng::seed_from_u64(11);
+    rng.next_u64();
+    let weight = Tensor::new(
+        slice![0.1f32, 0.2, 0.3, 0.4, 0.5, 0.6],
+        ShapeDescriptor(slice![3, 2]),
+    );
+    let mut checkpoint = Checkpoint {
+        epoch: 4,
+        step: 1200,
+        rng: Some(rng.clone()),
+        ..Default::default()
+    };
+    checkpoint.params.insert("fc.weight".into(), weight.clone());
+    checkpoint.optimizer.insert(
+        "fc.weight.m".into(),
+        Tensor::new(slice![0.0f32; 6], ShapeDescriptor(slice![3, 2])),
+    );
+
+    let path = std::env::temp_dir().join("linalg-test.ckpt");
+    checkpoint.save(&path).unwrap();
+    let restored = Checkpoint::<f32>::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((restored.epoch, restored.step), (4, 1200));
+    assert_eq!(restored.params["fc.weight"].data(), weight.data());
+    assert_eq!(restored.params["fc.weight"].shape(), weight.shape());
+    assert_eq!(restored.optimizer["fc.weight.m"].data(), &[0.0; 6]);
+    // the restored generator continues the same stream
+    assert_eq!(restored.rng.unwrap().next_u64(), rng.next_u64());
+
+    // a loader resumed after one epoch shuffles like the original run
+    let mut run = DataLoader::new(counting_dataset(), 4).with_shuffle(9);
+    collect_batches(&mut run);
+    let expected = collect_batches(&mut run);
+    let mut resumed = DataLoader::new(counting_dataset(), 4)
+        .with_shuffle(9)
+        .with_epoch(1);
+    for (b, e) in collect_batches(&mut resumed).iter().zip(&expected) {
+        assert_eq!(b.indices, e.indices);
+    }
+}
+
+#[test]
+fn test_checkpoint_validation() {
+    let mut checkpoint = Checkpoint::<f64> {
+        epoch: 1,
+        ..Default::default()
+    };
+    checkpoint.params.insert(
+        "b".into(),
+        Tensor::new(slice![1.0], ShapeDescriptor(slice![1])),
+    );
+    let bytes = checkpoint.to_bytes();
+    let is_format_error = |bytes: &[u8]| {
+        matches!(
+            Checkpoint::<f64>::from_bytes(bytes),
+            Err(LinalgError::Format {
+                format: "checkpoint",
+                ..
+            })
+        )
+    };
+
+    // any flipped bit fails the checksum
+    for at in [0, 9, 20, bytes.len() / 2, bytes.len() - 1] {
+        let mut corrupt = bytes.clone();
+        corrupt[at] ^= 0x10;
+        assert!(is_format_error(&corrupt));
+    }
+    assert!(is_format_error(&bytes[..bytes.len() - 3]));
+    assert!(Checkpoint::<f32>::from_bytes(&bytes).is_err());
+
+    // with a valid checksum, a newer major version is refused while an
+    // unknown section from a newer minor version is skipped
+    let reseal = |mut body: Vec<u8>| {
+        let crc = crate::io::crc::crc32(&body);
+        body.extend(crc.to_le_bytes());
+        body
+    };
+    let body = bytes[..bytes.len() - 4].to_vec();
+    let mut newer_major = body.clone();
+    newer_major[8] = 2;
+    assert!(is_format_error(&reseal(newer_major)));
+
+    let mut newer_minor = body.clone();
+    newer_minor[10] = 7;
+    newer_minor.extend(99u32.to_le_bytes());
+    newer_minor.extend(3u64.to_le_bytes());
+    newer_minor.extend([1, 2, 3]);
+    let restored = Checkpoint::<f64>::from_bytes(&reseal(newer_minor)).unwrap();
+    assert_eq!(restored.epoch, 1);
+    assert_eq!(restored.params["b"].data(), &[1.0]);
+    assert!(restored.rng.is_none());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    use serde_json::{from_str, json, to_value};
+
+    let shape = ShapeDescriptor(slice![2, 3]);
+    assert_eq!(to_value(&shape).unwrap(), json!([2, 3]));
+    assert_eq!(from_str::<ShapeDescriptor>("[2,3]").unwrap(), shape);
+    let v = Vec3::new(1.0, 2.0, 3.0);
+    assert_eq!(
+        from_str::<Vec3<f64>>(&serde_json::to_string(&v).unwrap()).unwrap(),
+        v
+    );
+    assert_eq!(to_value(Vec2::new(1, 2)).unwrap(), json!([1, 2]));
+
+    // a transposed tensor is stored as its logical layout
+    let mut tensor = Tensor::new(slice![1, 2, 3, 4, 5, 6], shape);
+    tensor.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![3, 2]),
+        slice![1, 3],
+    )));
+    let value = to_value(&tensor).unwrap();
+    assert_eq!(value, json!({"shape": [3, 2], "data": [1, 4, 2, 5, 3, 6]}));
+    let back: Tensor<i32> = serde_json::from_value(value).unwrap();
+    assert!(back.is_contiguous());
+    assert_eq!(back.data(), &[1, 4, 2, 5, 3, 6]);
+
+    let arr = Arr2::new(slice![1.0, 2.0, 3.0, 4.0], (2, 2));
+    let json = serde_json::to_string(&arr).unwrap();
+    assert_eq!(json, r#"{"shape":[2,2],"data":[1.0,2.0,3.0,4.0]}"#);
+    assert_eq!(max_diff(&from_str(&json).unwrap(), &arr), 0.0);
+    let arr1: Arr1<u8> = from_str(r#"{"shape":[3],"data":[1,2,3]}"#).unwrap();
+    assert_eq!(arr1.data(), &[1, 2, 3]);
+    let s: STensor<i32, 2, 1> =
+        from_str(r#"{"shape":[2,1],"data":[7,8]}"#).unwrap();
+    assert_eq!(
+        to_value(s).unwrap(),
+        json!({"shape": [2, 1], "data": [7, 8]})
+    );
+
+    // shapes are validated against the data and the container
+    assert!(from_str::<Tensor<f32>>(r#"{"shape":[2],"data":[1]}"#).is_err());
+    assert!(from_str::<Arr1<f32>>(r#"{"shape":[1,1],"data":[1]}"#).is_err());
+    assert!(from_str::<Arr2<f32>>(r#"{"shape":[3],"data":[1,2,3]}"#).is_err());
+    assert!(
+        from_str::<STensor<f32, 2, 2>>(r#"{"shape":[1,4],"data":[1,2,3,4]}"#)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_display() {
+    // printed through the transform, in the logical shape
+    assert_eq!(transposed(2, 3).to_string(), "[[0, 3],\n [1, 4],\n [2, 5]]");
+    let cube = Tensor::new((0..8).collect(), ShapeDescriptor(slice![2, 2, 2]));
+    assert_eq!(
+        cube.to_string(),
+        "[[[0, 1],\n  [2, 3]],\n\n [[4, 5],\n  [6, 7]]]"
+    );
+
+    // the precision applies to every element, columns are right aligned
+    let arr = Arr1::new(slice![1.5, -2.0, 10.25]);
+    assert_eq!(format!("{arr:.2}"), "[ 1.50, -2.00, 10.25]");
+    let arr = Arr2::new(slice![1.0, 200.0, -3.0, 4.5], (2, 2));
+    assert_eq!(format!("{arr}"), "[[  1, 200],\n [ -3, 4.5]]");
+    assert_eq!(Arr1::<f32>::new(Box::new([])).to_string(), "[]");
+
+    // large tensors only show their edges
+    let long = Arr1::new((0..2000).collect());
+    assert_eq!(
+        long.to_string(),
+        "[   0,    1,    2, ..., 1997, 1998, 1999]"
+    );
+    let square = Arr2::new((0..1600).collect(), (40, 40));
+    let printed = square.to_string();
+    assert_eq!(printed.lines().count(), 7);
+    assert_eq!(printed.lines().nth(3), Some(" ...,"));
+    assert!(printed.ends_with("[1560, 1561, 1562, ..., 1597, 1598, 1599]]"));
+    let small = Arr2::new((0..900).collect(), (30, 30));
+    assert!(!small.to_string().contains("..."));
+
+    // debug adds the layout
+    assert_eq!(
+        format!("{:?}", transposed(2, 3)),
+        "Tensor { shape: [3, 2], strides: [1, 3], contiguous: false, \
+         transform: true, data: [[0, 3],\n [1, 4],\n [2, 5]] }"
+    );
+    let arr = Arr1::new(slice![1.0, 0.5]);
+    assert_eq!(
+        format!("{arr:?}"),
+        "Arr1 { shape: [2], strides: [1], contiguous: true, \
+         transform: false, data: [1.0, 0.5] }"
+    );
+    assert!(format!("{arr:.3?}").ends_with("data: [1.000, 0.500] }"));
+}
+
+#[test]
+fn test_tensor_compare() {
+    // equality is on the logical shape and values, not the layout
+    let view = transposed(2, 3);
+    let owned =
+        Tensor::new(slice![0, 3, 1, 4, 2, 5], ShapeDescriptor(slice![3, 2]));
+    assert_eq!(view, owned);
+    let flat =
+        Tensor::new(slice![0, 3, 1, 4, 2, 5], ShapeDescriptor(slice![6]));
+    assert_ne!(owned, flat);
+    assert_eq!(
+        Arr2::new(slice![1.0, 2.0], (1, 2)),
+        Arr2::new(slice![1.0, 2.0], (1, 2))
+    );
+    assert_ne!(Arr1::new(slice![f64::NAN]), Arr1::new(slice![f64::NAN]));
+
+    let a = Arr2::new(slice![1.0, 2.0, 3.0, 4.0], (2, 2));
+    let b = Arr2::new(slice![1.0, 2.0 + 1e-9, 3.5, 4.0 + 1e-3], (2, 2));
+    assert!(a.allclose(&a, 0.0, 0.0));
+    assert!(!a.allclose(&b, 1e-5, 1e-8));
+    assert!(a.allclose(&b, 0.2, 0.0));
+    assert!(!a.allclose(
+        &Arr2::new(slice![1.0, 2.0, 3.0, f64::NAN], (2, 2)),
+        1.0,
+        1.0
+    ));
+
+    // equal infinities are close, anything else next to one isn't
+    let inf = Arr1::new(slice![f64::INFINITY, f64::NEG_INFINITY, 1.0]);
+    assert!(inf.allclose(&inf, 1e-5, 1e-8));
+    let finite = Arr1::new(slice![1e300, f64::NEG_INFINITY, 1.0]);
+    assert!(!finite.allclose(&inf, 1.0, 1.0));
+    // a NaN stays the max diff even when larger diffs follow
+    let nan = Arr1::new(slice![f64::NAN, 2.0, 9.0]);
+    match nan.check_close(&Arr1::new(slice![0.0, 2.0, 0.0]), 0.0, 0.0) {
+        Err(Mismatch::Values {
+            count, max_diff, ..
+        }) => assert!(count == 2 && max_diff.is_nan()),
+        other => panic!("{other:?}"),
+    }
+
+    match a.check_close(&b, 1e-5, 1e-8) {
+        Err(Mismatch::Values {
+            index,
+            lhs,
+            rhs,
+            count,
+            total,
+            max_diff,
+            ..
+        }) => {
+            assert_eq!(&*index, &[1, 0]);
+            assert_eq!((lhs, rhs), (3.0, 3.5));
+            assert_eq!((count, total), (2, 4));
+            assert_eq!(max_diff, 0.5);
+        }
+        other => panic!("{other:?}"),
+    }
+    assert!(matches!(
+        a.check_close(
+            &Tensor::new(slice![1.0; 4], ShapeDescriptor(slice![4])),
+            0.0,
+            0.0
+        ),
+        Err(Mismatch::Shape { .. })
+    ));
+
+    assert_tensor_close!(
+        a,
+        Arr2::new(slice![1.0, 2.0 + 1e-9, 3.0, 4.0], (2, 2))
+    );
+    assert_tensor_close!(&a, &b, rtol = 0.2, atol = 0.0);
+    let panic = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        assert_tensor_close!(a, b)
+    }))
+    .unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("2 of 4 elements differ, first at [1, 0]: 3 vs 3.5")
+    );
+}
+
+#[test]
+fn test_gradcheck() {
+    // f(a, b) = Σ a b² + sin(a₀₀), so ∂a = b² + cos(a₀₀) e₀₀ and ∂b = 2ab
+    let f = |xs: &[Tensor<f64>]| {
+        let (a, b) = (&xs[0], &xs[1]);
+        let sum: f64 = a
+            .iter_logical()
+            .zip(b.iter_logical())
+            .map(|(a, b)| a * b * b)
+            .sum();
+        sum + a[&[0, 0][..]].sin()
+    };
+    let a = Arr2::new(slice![0.3, -1.2, 2.0, 0.7, 1.5, -0.4], (2, 3));
+    let b = Arr2::new(slice![1.1, 0.2, -0.9, 0.5, -2.0, 1.3], (2, 3));
+    let mut da: Vec<f64> = b.iter().map(|b| b * b).collect();
+    da[0] += 0.3f64.cos();
+    let db: Vec<f64> =
+        a.iter().zip(b.iter()).map(|(a, b)| 2.0 * a * b).collect();
+    let da = Tensor::new(da.into(), ShapeDescriptor(slice![2, 3]));
+    let db = Tensor::new(db.into(), ShapeDescriptor(slice![2, 3]));
+    let inputs = [(*a).clone(), (*b).clone()];
+
+    let report = GradCheck::new()
+        .run(f, &inputs, &[da.clone(), db.clone()])
+        .unwrap();
+    report.assert_passed();
+    assert_eq!(report.entries.len(), 12);
+    assert!(report.worst().unwrap().error < 1e-8);
+
+    // a wrong element is pinned to its input and logical index
+    let mut wrong = db.clone();
+    wrong[&[1, 2][..]] += 0.1;
+    let report = GradCheck::new()
+        .run(f, &inputs, &[da.clone(), wrong])
+        .unwrap();
+    assert!(!report.passed());
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    assert_eq!((failures[0].input, &*failures[0].index), (1, &[1, 2][..]));
+    assert!(
+        report.to_string().starts_with(
+            "gradcheck failed 1 of 12 elements\n  input 1 at [1, 2]"
+        )
+    );
+    let tight = GradCheck::new().with_rtol(0.0).with_atol(0.0);
+    assert!(
+        !tight
+            .run(f, &inputs, &[da.clone(), db.clone()])
+            .unwrap()
+            .passed()
+    );
+
+    // transposed inputs are perturbed through their view, f32 works too
+    let data = (0..6).map(|x| x as f32 / 4.0).collect();
+    let mut x = Tensor::new(data, ShapeDescriptor(slice![2, 3]));
+    x.set_transform(Arc::new(IdentityTransform(
+        ShapeDescriptor(slice![3, 2]),
+        slice![1, 3],
+    )));
+    let grad = Tensor::new(
+        x.iter_logical().map(|x| 3.0 * x * x).collect(),
+        ShapeDescriptor(slice![3, 2]),
+    );
+    let cubes =
+        |xs: &[Tensor<f32>]| xs[0].iter_logical().map(|x| x * x * x).sum();
+    GradCheck::new()
+        .run(cubes, &[x], &[grad])
+        .unwrap()
+        .assert_passed();
+
+    let err = GradCheck::new()
+        .run(f, &inputs, std::slice::from_ref(&da))
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        LinalgError::GradientCount {
+            inputs: 2,
+            grads: 1
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "gradcheck needs one gradient per input but got 1 for 2 inputs"
+    );
+    let flipped = Tensor::new(slice![0.0; 6], ShapeDescriptor(slice![3, 2]));
+    assert!(GradCheck::new().run(f, &inputs, &[da, flipped]).is_err());
+}
+
+/// a direct 2-d convolution to check im2col against.
+#[allow(clippy::too_many_arguments)]
+fn naive_conv2d(
+    x: &Tensor<f64>,
+    w: &Tensor<f64>,
+    bias: &[f64],
+    stride: [usize; 2],
+    padding: [usize; 2],
+    dilation: [usize; 2],
+    groups: usize,
+) -> Tensor<f64> {
+    let (xs, ws) = (x.shape(), w.shape());
+    let (batch, cin, h, wd) = (xs[0], xs[1], xs[2], xs[3]);
+    let (cout, ig, kh, kw) = (ws[0], ws[1], ws[2], ws[3]);
+    let og = cout / groups;
+    let size = |n: usize, k: usize, d: usize| {
+        (n + 2 * padding[d] - dilation[d] * (k - 1) - 1) / stride[d] + 1
+    };
+    let (oh, ow) = (size(h, kh, 0), size(wd, kw, 1));
+    let mut out = Vec::new();
+    for b in 0..batch {
+        for o in 0..cout {
+            for i in 0..oh {
+                for j in 0..ow {
+                    let mut acc = bias[o];
+                    for c in 0..ig {
+                        for p in 0..kh {
+                            for q in 0..kw {
+                                let y = (i * stride[0] + p * dilation[0])
+                                    .checked_sub(padding[0]);
+                                let z = (j * stride[1] + q * dilation[1])
+                                    .checked_sub(padding[1]);
+                                if let (Some(y), Some(z)) = (y, z)
+                                    && y < h
+                                    && z < wd
+                                {
+                                    let channel = o / og * ig + c;
+                                    acc += x[&[b, channel, y, z][..]]
+                                        * w[&[o, c, p, q][..]];
+                                }
+                            }
+                        }
+                    }
+                    out.push(acc);
+                }
+            }
+        }
+    }
+    assert_eq!(cin, ig * groups);
+    Tensor::new(out.into(), ShapeDescriptor(slice![batch, cout, oh, ow]))
+}
+
+#[test]
+fn test_conv() {
+    let device = CpuDevice::with_threads(2).with_grain(1);
+
+    // a 1-d difference kernel
+    let config = ConvConfig::new(1, 1, [3]).with_bias(false);
+    let w =
+        Tensor::new(slice![1.0, 0.0, -1.0], ShapeDescriptor(slice![1, 1, 3]));
+    let conv = Conv1d::try_from_weights(config, w, None).unwrap();
+    let x = Tensor::new(
+        slice![1.0, 2.0, 4.0, 8.0, 16.0],
+        ShapeDescriptor(slice![1, 1, 5]),
+    );
+    assert_eq!(conv.forward(&device, &x).data(), &[-3.0, -6.0, -12.0]);
+    let padded = ConvConfig::new(1, 1, [3])
+        .with_padding([1])
+        .with_stride([2]);
+    let conv = Conv1d::try_from_weights(padded, conv.weight, None).unwrap();
+    assert_eq!(conv.forward(&device, &x).data(), &[-2.0, -6.0, 8.0]);
+
+    // every option at once against the direct definition
+    let mut rng = Rng::seed_from_u64(7);
+    let config = ConvConfig::new(4, 6, [3, 2])
+        .with_stride([2, 1])
+        .with_padding([1, 2])
+        .with_dilation([2, 1])
+        .with_groups(2);
+    let conv = Conv2d::<f64>::new(config, &mut rng);
+    assert_eq!(*conv.weight.shape(), ShapeDescriptor(slice![6, 2, 3, 2]));
+    let data = (0..2 * 4 * 7 * 5).map(|_| rng.next_f64() - 0.5).collect();
+    let x = Tensor::new(data, ShapeDescriptor(slice![2, 4, 7, 5]));
+    let y = conv.forward(&device, &x);
+    assert_eq!(*y.shape(), ShapeDescriptor(slice![2, 6, 3, 8]));
+    let bias = conv.bias.as_ref().unwrap().data().to_vec();
+    let expected =
+        naive_conv2d(&x, &conv.weight, &bias, [2, 1], [1, 2], [2, 1], 2);
+    assert_tensor_close!(y, expected, rtol = 1e-12, atol = 1e-12);
+
+    // the backward pass against finite differences of Σ y ⊙ r
+    let config = ConvConfig::new(2, 4, [2, 3])
+        .with_stride([1, 2])
+        .with_padding([1, 1])
+        .with_groups(2);
+    let conv = Conv2d::<f64>::new(config, &mut rng);
+    let data = (0..2 * 2 * 4 * 5).map(|_| rng.next_f64() - 0.5).collect();
+    let x = Tensor::new(data, ShapeDescriptor(slice![2, 2, 4, 5]));
+    let y = conv.forward(&device, &x);
+    let data = (0..y.hypervolume()).map(|_| rng.next_f64() - 0.5).collect();
+    let r = Tensor::new(data, y.shape().into_owned());
+    let grads = conv.backward(&device, &x, &r);
+    let loss = |xs: &[Tensor<f64>]| {
+        let conv = Conv2d::try_from_weights(
+            config,
+            xs[1].clone(),
+            Some(xs[2].clone()),
+        )
+        .unwrap();
+        let y = conv.forward(&device, &xs[0]);
+        y.iter_logical()
+            .zip(r.iter_logical())
+            .map(|(y, r)| y * r)
+            .sum()
+    };
+    let inputs = [x.clone(), conv.weight.clone(), conv.bias.clone().unwrap()];
+    let analytic = [grads.input, grads.weight, grads.bias.unwrap()];
+    GradCheck::new()
+        .run(loss, &inputs, &analytic)
+        .unwrap()
+        .assert_passed();
+
+    // configs and inputs that don't fit
+    let mut err = |config| Conv2d::<f32>::try_new(config, &mut rng).err();
+    assert!(matches!(
+        err(ConvConfig::new(3, 4, [3, 3]).with_groups(2)),
+        Some(LinalgError::InvalidLayer { .. })
+    ));
+    assert!(err(ConvConfig::new(2, 2, [3, 3]).with_stride([0, 1])).is_some());
+    assert!(conv.try_forward(&device, &r).is_err());
+    // empty axes are rejected, even where the padding fits the kernel
+    let tiny = Tensor::new(Box::new([]), ShapeDescriptor(slice![1, 2, 1, 0]));
+    assert!(matches!(
+        conv.try_forward(&device, &tiny),
+        Err(LinalgError::InvalidLayer { .. })
+    ));
+    let config = ConvConfig::new(1, 1, [1]).with_padding([1]);
+    let pointwise = Conv1d::<f64>::try_new(config, &mut rng).unwrap();
+    let empty = Tensor::new(Box::new([]), ShapeDescriptor(slice![1, 1, 0]));
+    assert!(matches!(
+        pointwise.try_forward(&device, &empty),
+        Err(LinalgError::InvalidLayer { .. })
+    ));
+    assert!(pointwise.try_backward(&device, &empty, &empty).is_err());
+    assert!(conv.try_backward(&device, &x, &x).is_err());
+}
+
+#[test]
+fn test_pool() {
+    let mut rng = Rng::seed_from_u64(11);
+    let mut random = |dims: &[usize]| {
+        let shape = ShapeDescriptor(dims.into());
+        let data = (0..shape.hypervolume()).map(|_| rng.next_f64()).collect();
+        Tensor::new(data, shape)
+    };
+    // Σ y ⊙ r, so the analytic input gradient is backward(r)
+    let weighted = |y: Tensor<f64>, r: &Tensor<f64>| {
+        y.iter_logical()
+            .zip(r.iter_logical())
+            .map(|(y, r)| y * r)
+            .sum()
+    };
+
+    // max pooling caches where every maximum came from
+    let x = Tensor::new(
+        (0..16).map(f64::from).collect(),
+        ShapeDescriptor(slice![1, 1, 4, 4]),
+    );
+    let mut pool = MaxPool2d::new(PoolConfig::new([2, 2]));
+    assert!(pool.try_backward(&x).is_err());
+    assert_eq!(pool.forward(&x).data(), &[5.0, 7.0, 13.0, 15.0]);
+    assert_eq!(pool.argmax(), Some(&[5, 7, 13, 15][..]));
+    let ones = Tensor::new(slice![1.0; 4], ShapeDescriptor(slice![1, 1, 2, 2]));
+    let dx = pool.backward(&ones);
+    assert_eq!(dx.iter().filter(|&&g| g == 1.0).count(), 4);
+    assert_eq!(dx[&[0, 0, 3, 3][..]], 1.0);
+    let cube = Tensor::new(
+        (0..8).map(f64::from).collect(),
+        ShapeDescriptor(slice![1, 1, 2, 2, 2]),
+    );
+    assert_eq!(
+        MaxPool::new(PoolConfig::new([2, 2, 2]))
+            .forward(&cube)
+            .data(),
+        &[7.0]
+    );
+
+    let config = PoolConfig::new([3, 2])
+        .with_stride([2, 1])
+        .with_padding([1, 1]);
+    let x = random(&[2, 3, 5, 4]);
+    let mut pool = MaxPool2d::new(config);
+    let y = pool.forward(&x);
+    assert_eq!(*y.shape(), ShapeDescriptor(slice![2, 3, 3, 5]));
+    let r = random(&[2, 3, 3, 5]);
+    let loss = |xs: &[Tensor<f64>]| {
+        weighted(MaxPool2d::new(config).forward(&xs[0]), &r)
+    };
+    GradCheck::new()
+        .run(loss, std::slice::from_ref(&x), &[pool.backward(&r)])
+        .unwrap()
+        .assert_passed();
+
+    // averages, with and without the padding in the divisor
+    let x = Tensor::new(slice![1.0f64; 9], ShapeDescriptor(slice![1, 1, 3, 3]));
+    let config = PoolConfig::new([3, 3])
+        .with_stride([1, 1])
+        .with_padding([1, 1]);
+    let y = AvgPool2d::new(config).forward(&x);
+    assert!(close(y[&[0, 0, 0, 0][..]], 4.0 / 9.0));
+    let exclusive = AvgPool2d::new(config).with_count_include_pad(false);
+    assert!(exclusive.forward(&x).iter().all(|&y| close(y, 1.0)));
+
+    let x = random(&[2, 2, 6, 5]);
+    for pool in [AvgPool2d::new(config), exclusive] {
+        let r = random(&[2, 2, 6, 5]);
+        let dx = pool.backward(&x.shape(), &r);
+        let loss = |xs: &[Tensor<f64>]| weighted(pool.forward(&xs[0]), &r);
+        GradCheck::new()
+            .run(loss, std::slice::from_ref(&x), &[dx])
+            .unwrap()
+            .assert_passed();
+    }
+
+    // adaptive bins of 5 into 3 are 0..2, 1..4 and 3..5
+    let x = Tensor::new(
+        slice![1.0, 2.0, 3.0, 4.0, 5.0],
+        ShapeDescriptor(slice![1, 1, 5]),
+    );
+    let y = AdaptiveAvgPool::new([3]).forward(&x);
+    assert_tensor_close!(
+        y,
+        Tensor::new(slice![1.5, 3.0, 4.5], ShapeDescriptor(slice![1, 1, 3]))
+    );
+    assert_eq!(
+        AdaptiveMaxPool::new([3]).forward(&x).data(),
+        &[2.0, 4.0, 5.0]
+    );
+
+    let x = random(&[2, 3, 7, 5]);
+    let pool = AdaptiveAvgPool2d::new([3, 2]);
+    let r = random(&[2, 3, 3, 2]);
+    let loss = |xs: &[Tensor<f64>]| weighted(pool.forward(&xs[0]), &r);
+    GradCheck::new()
+        .run(
+            loss,
+            std::slice::from_ref(&x),
+            &[pool.backward(&x.shape(), &r)],
+        )
+        .unwrap()
+        .assert_passed();
+    let mut pool = AdaptiveMaxPool2d::new([3, 2]);
+    pool.forward(&x);
+    let loss = |xs: &[Tensor<f64>]| {
+        weighted(AdaptiveMaxPool2d::new([3, 2]).forward(&xs[0]), &r)
+    };
+    GradCheck::new()
+        .run(loss, std::slice::from_ref(&x), &[pool.backward(&r)])
+        .unwrap()
+        .assert_passed();
+
+    // global pooling over any number of spatial axes
+    let x = random(&[2, 3, 4, 2, 3]);
+    let y = GlobalAvgPool.forward(&x);
+    assert_eq!(*y.shape(), ShapeDescriptor(slice![2, 3]));
+    let flat = AdaptiveAvgPool::new([1]).forward(&Tensor::new(
+        x.data().into(),
+        ShapeDescriptor(slice![2, 3, 24]),
+    ));
+    let y3 = Tensor::new(y.data().into(), ShapeDescriptor(slice![2, 3, 1]));
+    assert_tensor_close!(flat, y3, rtol = 1e-12, atol = 0.0);
+    let r = random(&[2, 3]);
+    let loss = |xs: &[Tensor<f64>]| weighted(GlobalAvgPool.forward(&xs[0]), &r);
+    GradCheck::new()
+        .run(
+            loss,
+            std::slice::from_ref(&x),
+            &[GlobalAvgPool.backward(&x.shape(), &r)],
+        )
+        .unwrap()
+        .assert_passed();
+
+    // configs and inputs that don't fit
+    let mut pool = MaxPool2d::new(PoolConfig::new([2, 2]).with_padding([2, 0]));
+    assert!(matches!(
+        pool.try_forward(&x),
+        Err(LinalgError::RankMismatch { .. })
+    ));
+    assert!(matches!(
+        pool.try_forward(&random(&[1, 1, 4, 4])),
+        Err(LinalgError::InvalidLayer { .. })
+    ));
+    assert!(
+        AvgPool2d::new(PoolConfig::new([5, 1]))
+            .try_forward(&random(&[1, 1, 4, 4]))
+            .is_err()
+    );
+    assert!(
+        AdaptiveAvgPool2d::new([0, 1])
+            .try_forward(&random(&[1, 1, 4, 4]))
+            .is_err()
+    );
+    assert!(
+        GlobalAvgPool
+            .try_backward(&[2, 3, 4], &random(&[3, 2]))
+            .is_err()
+    );
+}
@@ -1,14 +1,112 @@
 use std::borrow::Cow;
 
-use crate::shape::{Shape, ShapeDescriptor};
+use crate::{
+    error::LinalgError,
+    ndarr::{arr1::Arr1, arr2::Arr2},
+    number::{Real, Scalar},
+    shape::{Shape, ShapeDescriptor},
+};
 
 ///====================== Vec2 ======================
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec2<T>([T; 2]);
 impl<T> Vec2<T> {
     pub fn new(x: T, y: T) -> Self {
         Self([x, y])
     }
+
+    #[inline]
+    pub fn x(&self) -> T
+    where
+        T: Copy,
+    {
+        self.0[0]
+    }
+
+    #[inline]
+    pub fn y(&self) -> T
+    where
+        T: Copy,
+    {
+        self.0[1]
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> &[T; 2] {
+        &self.0
+    }
+}
+
+///====================== Vec2 Scalar ======================
+impl<T: Scalar> Vec2<T> {
+    pub fn dot(self, rhs: Self) -> T {
+        self * rhs
+    }
+
+    /// the z component of the 3-d cross product, positive when `rhs` is
+    /// counter-clockwise of `self`.
+    pub fn cross(self, rhs: Self) -> T {
+        self.x() * rhs.y() - self.y() * rhs.x()
+    }
+
+    pub fn length_squared(self) -> T {
+        self * self
+    }
+}
+
+///====================== Vec2 Real ======================
+impl<T: Real> Vec2<T> {
+    pub fn length(self) -> T {
+        self.length_squared().sqrt()
+    }
+
+    /// `self` scaled to unit length, `None` for the zero vector.
+    pub fn try_normalize(self) -> Option<Self> {
+        let len = self.length();
+        (len > T::ZERO).then(|| self / len)
+    }
+
+    /// `self` scaled to unit length, the zero vector stays zero.
+    pub fn normalize(self) -> Self {
+        self.try_normalize().unwrap_or(self)
+    }
+
+    /// `self` at `t = 0` to `rhs` at `t = 1`.
+    pub fn lerp(self, rhs: Self, t: T) -> Self {
+        self + (rhs - self) * t
+    }
+
+    /// the unsigned angle to `rhs` in radians, in `[0, π]`.
+    pub fn angle(self, rhs: Self) -> T {
+        // atan2 stays accurate for nearly parallel vectors, acos doesn't
+        self.cross(rhs).abs().atan2(self.dot(rhs))
+    }
+
+    /// the component of `self` along `onto`.
+    pub fn project(self, onto: Self) -> Self {
+        onto * (self.dot(onto) / onto.length_squared())
+    }
+
+    /// mirrors `self` on the line with unit `normal`.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * ((T::ONE + T::ONE) * self.dot(normal))
+    }
+
+    /// `self` rotated counter-clockwise by `angle` radians.
+    pub fn rotate(self, angle: T) -> Self {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        Self::new(
+            cos * self.x() - sin * self.y(),
+            sin * self.x() + cos * self.y(),
+        )
+    }
+
+    /// the 2x2 matrix rotating column vectors counter-clockwise by `angle`.
+    pub fn rotation_matrix(angle: T) -> Arr2<T> {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        Arr2::new(Box::new([cos, -sin, sin, cos]), (2, 2))
+    }
 }
 
 ///====================== Vec2 Shape ======================
@@ -87,3 +185,60 @@ where
         self + (-rhs)
     }
 }
+
+///====================== Vec2 Mul<T> ======================
+impl<T> std::ops::Mul<T> for Vec2<T>
+where
+    T: std::ops::Mul<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+
+    /// scales both components.
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.0[0] * rhs, self.0[1] * rhs)
+    }
+}
+
+///====================== Vec2 Div<T> ======================
+impl<T> std::ops::Div<T> for Vec2<T>
+where
+    T: std::ops::Div<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self::new(self.0[0] / rhs, self.0[1] / rhs)
+    }
+}
+
+///====================== Vec2 From<[T; 2]> ======================
+impl<T> From<[T; 2]> for Vec2<T> {
+    fn from(value: [T; 2]) -> Self {
+        Self(value)
+    }
+}
+
+///====================== Arr1 From<Vec2> ======================
+impl<T> From<Vec2<T>> for Arr1<T> {
+    fn from(value: Vec2<T>) -> Self {
+        Arr1::new(Box::new(value.0))
+    }
+}
+
+///====================== Vec2 TryFrom<Arr1> ======================
+impl<T: Copy> TryFrom<Arr1<T>> for Vec2<T> {
+    type Error = LinalgError;
+
+    fn try_from(value: Arr1<T>) -> Result<Self, Self::Error> {
+        // checked up front so a wrong length never copies the data
+        if value.hypervolume() != 2 {
+            return Err(LinalgError::DataLength {
+                expected: 2,
+                found: value.hypervolume(),
+            });
+        }
+        Ok(Self::new(value[[0]], value[[1]]))
+    }
+}
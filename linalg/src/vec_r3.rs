@@ -1,14 +1,116 @@
 use std::borrow::Cow;
 
-use crate::shape::{Shape, ShapeDescriptor};
+use crate::{
+    error::LinalgError,
+    ndarr::{arr1::Arr1, arr2::Arr2},
+    number::{Real, Scalar},
+    quat::Quat,
+    shape::{Shape, ShapeDescriptor},
+};
 
 ///====================== Vec3 ======================
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec3<T>([T; 3]);
 impl<T> Vec3<T> {
     pub fn new(x: T, y: T, z: T) -> Self {
         Self([x, y, z])
     }
+
+    #[inline]
+    pub fn x(&self) -> T
+    where
+        T: Copy,
+    {
+        self.0[0]
+    }
+
+    #[inline]
+    pub fn y(&self) -> T
+    where
+        T: Copy,
+    {
+        self.0[1]
+    }
+
+    #[inline]
+    pub fn z(&self) -> T
+    where
+        T: Copy,
+    {
+        self.0[2]
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> &[T; 3] {
+        &self.0
+    }
+}
+
+///====================== Vec3 Scalar ======================
+impl<T: Scalar> Vec3<T> {
+    pub fn dot(self, rhs: Self) -> T {
+        self * rhs
+    }
+
+    /// the right handed cross product.
+    pub fn cross(self, rhs: Self) -> Self {
+        let ([ax, ay, az], [bx, by, bz]) = (self.0, rhs.0);
+        Self::new(ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx)
+    }
+
+    pub fn length_squared(self) -> T {
+        self * self
+    }
+}
+
+///====================== Vec3 Real ======================
+impl<T: Real> Vec3<T> {
+    pub fn length(self) -> T {
+        self.length_squared().sqrt()
+    }
+
+    /// `self` scaled to unit length, `None` for the zero vector.
+    pub fn try_normalize(self) -> Option<Self> {
+        let len = self.length();
+        (len > T::ZERO).then(|| self / len)
+    }
+
+    /// `self` scaled to unit length, the zero vector stays zero.
+    pub fn normalize(self) -> Self {
+        self.try_normalize().unwrap_or(self)
+    }
+
+    /// `self` at `t = 0` to `rhs` at `t = 1`.
+    pub fn lerp(self, rhs: Self, t: T) -> Self {
+        self + (rhs - self) * t
+    }
+
+    /// the unsigned angle to `rhs` in radians, in `[0, π]`.
+    pub fn angle(self, rhs: Self) -> T {
+        // atan2 stays accurate for nearly parallel vectors, acos doesn't
+        self.cross(rhs).length().atan2(self.dot(rhs))
+    }
+
+    /// the component of `self` along `onto`.
+    pub fn project(self, onto: Self) -> Self {
+        onto * (self.dot(onto) / onto.length_squared())
+    }
+
+    /// mirrors `self` on the plane with unit `normal`.
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * ((T::ONE + T::ONE) * self.dot(normal))
+    }
+
+    /// `self` rotated by `angle` radians around `axis`, right handed.
+    pub fn rotate(self, axis: Self, angle: T) -> Self {
+        Quat::from_axis_angle(axis, angle).rotate(self)
+    }
+
+    /// the 3x3 matrix rotating column vectors by `angle` around `axis`.
+    pub fn rotation_matrix(axis: Self, angle: T) -> Arr2<T> {
+        Quat::from_axis_angle(axis, angle).to_rotation_matrix()
+    }
 }
 
 ///====================== Vec3 Shape ======================
@@ -86,3 +188,60 @@ where
         self + (-rhs)
     }
 }
+
+///====================== Vec3 Mul<T> ======================
+impl<T> std::ops::Mul<T> for Vec3<T>
+where
+    T: std::ops::Mul<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+
+    /// scales every component.
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.0[0] * rhs, self.0[1] * rhs, self.0[2] * rhs)
+    }
+}
+
+///====================== Vec3 Div<T> ======================
+impl<T> std::ops::Div<T> for Vec3<T>
+where
+    T: std::ops::Div<Output = T>,
+    T: Clone + Copy,
+{
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self::new(self.0[0] / rhs, self.0[1] / rhs, self.0[2] / rhs)
+    }
+}
+
+///====================== Vec3 From<[T; 3]> ======================
+impl<T> From<[T; 3]> for Vec3<T> {
+    fn from(value: [T; 3]) -> Self {
+        Self(value)
+    }
+}
+
+///====================== Arr1 From<Vec3> ======================
+impl<T> From<Vec3<T>> for Arr1<T> {
+    fn from(value: Vec3<T>) -> Self {
+        Arr1::new(Box::new(value.0))
+    }
+}
+
+///====================== Vec3 TryFrom<Arr1> ======================
+impl<T: Copy> TryFrom<Arr1<T>> for Vec3<T> {
+    type Error = LinalgError;
+
+    fn try_from(value: Arr1<T>) -> Result<Self, Self::Error> {
+        // checked up front so a wrong length never copies the data
+        if value.hypervolume() != 3 {
+            return Err(LinalgError::DataLength {
+                expected: 3,
+                found: value.hypervolume(),
+            });
+        }
+        Ok(Self::new(value[[0]], value[[1]], value[[2]]))
+    }
+}